@@ -0,0 +1,393 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::Serialize;
+use tantivy::collector::{DocSetCollector, TopDocs};
+use tantivy::directory::MmapDirectory;
+use tantivy::query::{AllQuery, QueryParser};
+use tantivy::schema::{Field, Schema, Value, STORED, STRING, TEXT};
+use tantivy::{doc, Index, IndexReader, IndexWriter, TantivyDocument, Term};
+use tauri::{Emitter, Manager};
+
+use crate::extract::Document;
+use crate::SearXNGResult;
+
+/// Indexing memory; tantivy needs at least 15 MB per writer thread
+const WRITER_MEMORY: usize = 32 * 1024 * 1024;
+
+/// Characters of context returned around the matched terms
+const SNIPPET_CHARS: usize = 240;
+
+/// Pages kept; past it the least recently fetched tenth is dropped
+const MAX_PAGES: u64 = 50_000;
+
+/// Staged pages that force a commit; fewer wait for `COMMIT_INTERVAL`
+const COMMIT_BATCH: usize = 64;
+
+/// How often `run_committer` commits staged pages
+const COMMIT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A page found in the archive
+#[derive(Serialize, Debug, Clone)]
+pub struct ArchiveHit {
+    pub url: String,
+    pub title: String,
+    /// e.g. "web page", "PDF document"
+    pub kind: String,
+    /// RFC 3339 time of the latest fetch
+    pub fetched_at: String,
+    pub snippet: String,
+    pub score: f32,
+}
+
+impl From<ArchiveHit> for SearXNGResult {
+    fn from(hit: ArchiveHit) -> Self {
+        SearXNGResult {
+            url: hit.url,
+            title: hit.title,
+            snippet: Some(hit.snippet).filter(|s| !s.is_empty()),
+            published_date: Some(hit.fetched_at),
+            score: Some(hit.score as f64),
+            category: Some("archive".to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Fields {
+    url: Field,
+    title: Field,
+    content: Field,
+    kind: Field,
+    fetched_at: Field,
+}
+
+/// The writer and how many pages were added since its last commit
+struct Staged {
+    writer: IndexWriter,
+    pending: usize,
+}
+
+/// Full-text index of the extracted text of every fetched page, in the
+/// app data dir. A URL holds only its latest fetch, and at most
+/// `MAX_PAGES` URLs are kept. Cheap to clone, so indexing can move to a
+/// blocking thread.
+#[derive(Clone)]
+pub struct Archive {
+    index: Index,
+    reader: IndexReader,
+    writer: Arc<Mutex<Staged>>,
+    fields: Fields,
+}
+
+impl Archive {
+    pub fn open(dir: &Path) -> Result<Self, String> {
+        let mut schema = Schema::builder();
+        let fields = Fields {
+            url: schema.add_text_field("url", STRING | STORED),
+            title: schema.add_text_field("title", TEXT | STORED),
+            content: schema.add_text_field("content", TEXT | STORED),
+            kind: schema.add_text_field("kind", STRING | STORED),
+            fetched_at: schema.add_i64_field("fetched_at", STORED),
+        };
+
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create archive dir: {}", e))?;
+        let directory =
+            MmapDirectory::open(dir).map_err(|e| format!("Failed to open archive: {}", e))?;
+        let index = Index::open_or_create(directory, schema.build())
+            .map_err(|e| format!("Failed to open archive: {}", e))?;
+        let writer = index
+            .writer_with_num_threads(1, WRITER_MEMORY)
+            .map_err(|e| format!("Failed to open archive writer: {}", e))?;
+        let reader = index
+            .reader()
+            .map_err(|e| format!("Failed to open archive reader: {}", e))?;
+        Ok(Self {
+            index,
+            reader,
+            writer: Arc::new(Mutex::new(Staged { writer, pending: 0 })),
+            fields,
+        })
+    }
+
+    /// Stages a fetched page, replacing any earlier copy of the same URL.
+    /// Staged pages are committed in batches, by `run_committer`, or before
+    /// the next search. May commit, so call it off the async runtime.
+    pub fn add(&self, url: &str, document: &Document) -> Result<(), String> {
+        let f = self.fields;
+        let mut staged = self.writer.lock().unwrap();
+        staged.writer.delete_term(Term::from_field_text(f.url, url));
+        staged
+            .writer
+            .add_document(doc!(
+                f.url => url,
+                f.title => document.title.clone().unwrap_or_default(),
+                f.content => document.content.as_str(),
+                f.kind => document.describe(),
+                f.fetched_at => chrono::Utc::now().timestamp(),
+            ))
+            .map_err(|e| format!("Failed to archive {}: {}", url, e))?;
+        staged.pending += 1;
+        let full = staged.pending >= COMMIT_BATCH;
+        drop(staged);
+        if full {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Commits the staged pages, if any, and makes them searchable
+    pub fn flush(&self) -> Result<(), String> {
+        let mut staged = self.writer.lock().unwrap();
+        if staged.pending == 0 {
+            return Ok(());
+        }
+        staged
+            .writer
+            .commit()
+            .map_err(|e| format!("Failed to commit archive: {}", e))?;
+        staged.pending = 0;
+        drop(staged);
+        // Make the pages searchable now rather than on the next reload tick
+        self.reader
+            .reload()
+            .map_err(|e| format!("Failed to reload archive: {}", e))?;
+
+        let count = self.reader.searcher().num_docs();
+        if count > MAX_PAGES {
+            let mut pages = self.pages()?;
+            pages.sort_by_key(|(_, fetched_at)| *fetched_at);
+            let excess = (count - MAX_PAGES + MAX_PAGES / 10) as usize;
+            self.delete(pages.into_iter().take(excess).map(|(url, _)| url))?;
+        }
+        Ok(())
+    }
+
+    /// Removes the pages last fetched before `before` (Unix time), or all
+    /// of them. Returns how many were removed.
+    pub fn clear(&self, before: Option<i64>) -> Result<usize, String> {
+        self.flush()?;
+        let urls: Vec<String> = self
+            .pages()?
+            .into_iter()
+            .filter(|(_, fetched_at)| before.is_none_or(|before| *fetched_at < before))
+            .map(|(url, _)| url)
+            .collect();
+        let removed = urls.len();
+        self.delete(urls.into_iter())?;
+        Ok(removed)
+    }
+
+    /// URL and fetch time of every stored page
+    fn pages(&self) -> Result<Vec<(String, i64)>, String> {
+        let f = self.fields;
+        let searcher = self.reader.searcher();
+        let addresses = searcher
+            .search(&AllQuery, &DocSetCollector)
+            .map_err(|e| format!("Failed to read archive: {}", e))?;
+        addresses
+            .into_iter()
+            .map(|address| {
+                let doc: TantivyDocument = searcher
+                    .doc(address)
+                    .map_err(|e| format!("Failed to read archive: {}", e))?;
+                let url = doc
+                    .get_first(f.url)
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string();
+                let fetched_at = doc
+                    .get_first(f.fetched_at)
+                    .and_then(|v| v.as_i64())
+                    .unwrap_or_default();
+                Ok((url, fetched_at))
+            })
+            .collect()
+    }
+
+    fn delete(&self, urls: impl Iterator<Item = String>) -> Result<(), String> {
+        let f = self.fields;
+        let mut staged = self.writer.lock().unwrap();
+        for url in urls {
+            staged
+                .writer
+                .delete_term(Term::from_field_text(f.url, &url));
+        }
+        staged
+            .writer
+            .commit()
+            .map_err(|e| format!("Failed to update archive: {}", e))?;
+        staged.pending = 0;
+        drop(staged);
+        self.reader
+            .reload()
+            .map_err(|e| format!("Failed to reload archive: {}", e))
+    }
+
+    /// Best matches for `query` in titles (weighted up) and page text.
+    /// Accepts tantivy query syntax (`"exact phrase"`, `+must -not`,
+    /// `title:word`); text that doesn't parse is searched as plain words.
+    /// Commits staged pages first, so call it off the async runtime.
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<ArchiveHit>, String> {
+        self.flush()?;
+        let f = self.fields;
+        let mut parser = QueryParser::for_index(&self.index, vec![f.title, f.content]);
+        parser.set_field_boost(f.title, 2.0);
+        let (query, _) = parser.parse_query_lenient(query);
+
+        let searcher = self.reader.searcher();
+        let top = searcher
+            .search(&query, &TopDocs::with_limit(limit.max(1)))
+            .map_err(|e| format!("Archive search failed: {}", e))?;
+        let mut snippets = tantivy::SnippetGenerator::create(&searcher, &*query, f.content)
+            .map_err(|e| format!("Archive search failed: {}", e))?;
+        snippets.set_max_num_chars(SNIPPET_CHARS);
+
+        let text = |doc: &TantivyDocument, field: Field| {
+            doc.get_first(field)
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+        top.into_iter()
+            .map(|(score, address)| {
+                let doc: TantivyDocument = searcher
+                    .doc(address)
+                    .map_err(|e| format!("Archive read failed: {}", e))?;
+                let url = text(&doc, f.url);
+                let title = Some(text(&doc, f.title))
+                    .filter(|t| !t.is_empty())
+                    .unwrap_or_else(|| url.clone());
+                let fetched_at = doc
+                    .get_first(f.fetched_at)
+                    .and_then(|v| v.as_i64())
+                    .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+                    .map(|time| time.to_rfc3339())
+                    .unwrap_or_default();
+                let snippet = snippets.snippet_from_doc(&doc);
+                Ok(ArchiveHit {
+                    url,
+                    title,
+                    kind: text(&doc, f.kind),
+                    fetched_at,
+                    snippet: snippet
+                        .fragment()
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    score,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Commits pages staged by `Archive::add` every `COMMIT_INTERVAL`
+pub async fn run_committer(app: tauri::AppHandle) {
+    let archive = app.state::<Archive>().inner().clone();
+    loop {
+        tokio::time::sleep(COMMIT_INTERVAL).await;
+        let archive = archive.clone();
+        let committed = tokio::task::spawn_blocking(move || archive.flush())
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|result| result);
+        if let Err(e) = committed {
+            let _ = app.emit(
+                "workflow_log",
+                crate::WorkflowLog {
+                    step: "persistence".to_string(),
+                    status: "error".to_string(),
+                    message: format!("Archive commit failed: {}", e),
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::DocumentKind;
+
+    fn page(title: &str, content: &str) -> Document {
+        Document {
+            kind: DocumentKind::Html,
+            title: Some(title.to_string()),
+            content: content.to_string(),
+            data: None,
+            api_spec: None,
+        }
+    }
+
+    #[test]
+    fn a_url_keeps_only_its_latest_fetch() {
+        let dir = std::env::temp_dir().join(format!("archive-{}", uuid::Uuid::new_v4()));
+        let archive = Archive::open(&dir).unwrap();
+        archive
+            .add("https://a.example/", &page("Old", "tantivy indexes text"))
+            .unwrap();
+        archive
+            .add(
+                "https://a.example/",
+                &page("New", "tantivy merges segments"),
+            )
+            .unwrap();
+        archive
+            .add("https://b.example/", &page("Other", "unrelated words"))
+            .unwrap();
+
+        let hits = archive.search("tantivy", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].title, "New");
+        assert_eq!(hits[0].kind, "web page");
+        assert!(hits[0].snippet.contains("segments"));
+        assert!(archive.search("indexes", 10).unwrap().is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn clear_removes_pages_by_fetch_time() {
+        let dir = std::env::temp_dir().join(format!("archive-{}", uuid::Uuid::new_v4()));
+        let archive = Archive::open(&dir).unwrap();
+        archive
+            .add("https://a.example/", &page("A", "alpha"))
+            .unwrap();
+        archive
+            .add("https://b.example/", &page("B", "beta"))
+            .unwrap();
+
+        let an_hour_ago = chrono::Utc::now().timestamp() - 3600;
+        assert_eq!(archive.clear(Some(an_hour_ago)).unwrap(), 0);
+        assert_eq!(archive.search("alpha", 10).unwrap().len(), 1);
+        assert_eq!(archive.clear(None).unwrap(), 2);
+        assert!(archive.search("alpha", 10).unwrap().is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn pages_are_committed_in_batches() {
+        let dir = std::env::temp_dir().join(format!("archive-{}", uuid::Uuid::new_v4()));
+        let archive = Archive::open(&dir).unwrap();
+        let committed = |archive: &Archive| archive.reader.searcher().num_docs() as usize;
+
+        archive
+            .add("https://a.example/", &page("A", "alpha"))
+            .unwrap();
+        assert_eq!(committed(&archive), 0);
+        archive.flush().unwrap();
+        assert_eq!(committed(&archive), 1);
+
+        for n in 0..COMMIT_BATCH {
+            let url = format!("https://b.example/{}", n);
+            archive.add(&url, &page("B", "beta")).unwrap();
+        }
+        assert_eq!(committed(&archive), 1 + COMMIT_BATCH);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,450 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, RwLock};
+
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Manager};
+use tokio::sync::Semaphore;
+
+use crate::openapi;
+use crate::vault::VaultGraph;
+use crate::{DuplicatePolicy, WorkflowOptions};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatchSelection {
+    pub url: String,
+    pub title: String,
+}
+
+/// Concurrency limits; fetches are cheap, local inference is not
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchLimits {
+    #[serde(default = "default_fetch_concurrency")]
+    pub fetch_concurrency: usize,
+    #[serde(default = "default_ollama_concurrency")]
+    pub ollama_concurrency: usize,
+}
+
+fn default_fetch_concurrency() -> usize {
+    4
+}
+
+fn default_ollama_concurrency() -> usize {
+    1
+}
+
+impl Default for BatchLimits {
+    fn default() -> Self {
+        Self {
+            fetch_concurrency: default_fetch_concurrency(),
+            ollama_concurrency: default_ollama_concurrency(),
+        }
+    }
+}
+
+/// Emitted as `batch_progress` for every stage of every item
+#[derive(Serialize, Clone)]
+pub struct BatchProgress {
+    pub index: usize,
+    pub url: String,
+    pub step: String,
+    pub status: String,
+    pub message: String,
+}
+
+/// Why a single item failed
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum BatchError {
+    /// Already saved in the vault (or earlier in the same batch)
+    Duplicate(String),
+    Fetch(String),
+    Summarize(String),
+    Save(String),
+}
+
+#[derive(Serialize, Clone)]
+pub struct BatchItemResult {
+    pub index: usize,
+    pub url: String,
+    pub title: String,
+    pub file_path: Option<String>,
+    pub error: Option<BatchError>,
+    /// Set when saved despite a duplicate under `DuplicatePolicy::Warn`
+    pub warning: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct BatchSummary {
+    pub total: usize,
+    pub succeeded: usize,
+    /// Duplicates left alone, see `BatchError::Duplicate`
+    pub skipped: usize,
+    pub failed: usize,
+    pub items: Vec<BatchItemResult>,
+}
+
+/// Runs fetch -> summarize -> save for every selection, bounded by `limits`
+pub async fn run_batch(
+    app: tauri::AppHandle,
+    query: String,
+    selections: Vec<BatchSelection>,
+    options: WorkflowOptions,
+    limits: BatchLimits,
+) -> Result<BatchSummary, String> {
+    let client = reqwest::Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let graph = app.state::<RwLock<VaultGraph>>();
+    let graph_ready = crate::ensure_vault_graph(&graph, &client, &options.obsidian_api_key).await;
+    if let Err(e) = &graph_ready {
+        let _ = app.emit(
+            "workflow_log",
+            crate::WorkflowLog {
+                step: "discovery".to_string(),
+                status: "running".to_string(),
+                message: format!("Duplicate check unavailable: {}", e),
+            },
+        );
+    }
+
+    let fetch_permits = Arc::new(Semaphore::new(limits.fetch_concurrency.max(1)));
+    let ollama_permits = Arc::new(Semaphore::new(limits.ollama_concurrency.max(1)));
+    let options = Arc::new(options);
+    let claimed_paths = Arc::new(Mutex::new(HashSet::new()));
+    let total = selections.len();
+
+    let mut seen = HashSet::new();
+    let mut items = Vec::with_capacity(total);
+    let mut tasks = tokio::task::JoinSet::new();
+
+    for (index, selection) in selections.into_iter().enumerate() {
+        // The same page selected twice in one batch is only processed once
+        if !seen.insert(crate::urls::normalize_url(&selection.url)) {
+            items.push(BatchItemResult {
+                index,
+                url: selection.url,
+                title: selection.title,
+                file_path: None,
+                error: Some(BatchError::Duplicate(
+                    "Selected twice in this batch".to_string(),
+                )),
+                warning: None,
+            });
+            continue;
+        }
+
+        let existing = graph.read().unwrap().find_by_source(&selection.url);
+        let (overwrite_path, warning) = match (existing, options.on_duplicate) {
+            (Some(path), DuplicatePolicy::Skip) => {
+                items.push(BatchItemResult {
+                    index,
+                    url: selection.url,
+                    title: selection.title,
+                    file_path: Some(path.clone()),
+                    error: Some(BatchError::Duplicate(format!("Already saved as {}", path))),
+                    warning: None,
+                });
+                continue;
+            }
+            (Some(path), DuplicatePolicy::Refresh) => (Some(path), None),
+            (Some(path), DuplicatePolicy::Warn) => {
+                let warning = format!("Already saved as {}", path);
+                let _ = app.emit(
+                    "batch_progress",
+                    BatchProgress {
+                        index,
+                        url: selection.url.clone(),
+                        step: "discovery".to_string(),
+                        status: "running".to_string(),
+                        message: format!("Warning: {}", warning),
+                    },
+                );
+                (None, Some(warning))
+            }
+            (None, _) => (None, None),
+        };
+
+        let app = app.clone();
+        let client = client.clone();
+        let query = query.clone();
+        let options = options.clone();
+        let fetch_permits = fetch_permits.clone();
+        let ollama_permits = ollama_permits.clone();
+        let claimed_paths = claimed_paths.clone();
+        let graph_ready = graph_ready.is_ok();
+        tasks.spawn(async move {
+            let outcome = process_item(
+                &app,
+                &client,
+                index,
+                &query,
+                &selection,
+                &options,
+                overwrite_path.as_deref(),
+                &fetch_permits,
+                &ollama_permits,
+                &claimed_paths,
+                graph_ready,
+            )
+            .await;
+            let (file_path, error) = match outcome {
+                Ok(path) => (Some(path), None),
+                Err(e) => (None, Some(e)),
+            };
+            BatchItemResult {
+                index,
+                url: selection.url,
+                title: selection.title,
+                file_path,
+                error,
+                warning,
+            }
+        });
+    }
+
+    while let Some(joined) = tasks.join_next().await {
+        items.push(joined.map_err(|e| format!("Batch task failed: {}", e))?);
+    }
+    items.sort_by_key(|i| i.index);
+
+    let skipped = items
+        .iter()
+        .filter(|i| matches!(i.error, Some(BatchError::Duplicate(_))))
+        .count();
+    let failed = items.iter().filter(|i| i.error.is_some()).count() - skipped;
+    Ok(BatchSummary {
+        total,
+        succeeded: total - failed - skipped,
+        skipped,
+        failed,
+        items,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn process_item(
+    app: &tauri::AppHandle,
+    client: &reqwest::Client,
+    index: usize,
+    query: &str,
+    selection: &BatchSelection,
+    options: &WorkflowOptions,
+    overwrite_path: Option<&str>,
+    fetch_permits: &Semaphore,
+    ollama_permits: &Semaphore,
+    claimed_paths: &Mutex<HashSet<String>>,
+    graph_ready: bool,
+) -> Result<String, BatchError> {
+    let progress = |step: &str, status: &str, message: String| {
+        let _ = app.emit(
+            "batch_progress",
+            BatchProgress {
+                index,
+                url: selection.url.clone(),
+                step: step.to_string(),
+                status: status.to_string(),
+                message,
+            },
+        );
+    };
+
+    // Acquisition
+    let mut document = {
+        let _permit = fetch_permits
+            .acquire()
+            .await
+            .map_err(|e| BatchError::Fetch(e.to_string()))?;
+        progress(
+            "acquisition",
+            "running",
+            format!("Fetching: {}...", selection.url),
+        );
+        crate::fetch_url_content(
+            app,
+            client,
+            &selection.url,
+            options.headless,
+            crate::fetch::CacheMode::Default,
+        )
+        .await
+        .inspect_err(|e| progress("acquisition", "error", e.clone()))
+        .map_err(BatchError::Fetch)?
+    };
+    progress(
+        "acquisition",
+        "complete",
+        format!(
+            "Retrieved {} chars ({})",
+            document.content.len(),
+            document.describe()
+        ),
+    );
+
+    let fields = crate::source_fields(app, &selection.url, &document);
+
+    // Synthesis
+    let api_spec = document.api_spec.take();
+    let summary = if api_spec.is_some() {
+        progress(
+            "synthesis",
+            "skipped",
+            "API spec: building structured reference".to_string(),
+        );
+        document.content.clone()
+    } else if options.use_ai {
+        let _permit = ollama_permits
+            .acquire()
+            .await
+            .map_err(|e| BatchError::Summarize(e.to_string()))?;
+        progress(
+            "synthesis",
+            "running",
+            format!("Summarizing with {}...", options.model_name),
+        );
+        let sum = crate::call_ollama_summarize(
+            app,
+            client,
+            &document,
+            &options.model_name,
+            options.bypass_llm_cache,
+        )
+        .await
+        .inspect_err(|e| progress("synthesis", "error", e.clone()))
+        .map_err(BatchError::Summarize)?;
+        progress("synthesis", "complete", "Summary generated".to_string());
+        sum
+    } else {
+        document.content.clone()
+    };
+
+    // Persistence
+    progress(
+        "persistence",
+        "running",
+        "Saving to Obsidian vault...".to_string(),
+    );
+    let note_title = if selection.title.trim().is_empty() {
+        format!("{} - {}", query, selection.url)
+    } else {
+        selection.title.clone()
+    };
+    let saved = match &api_spec {
+        Some(spec) => {
+            openapi::save_api_notes(
+                client,
+                &app.state::<RwLock<VaultGraph>>(),
+                spec,
+                &selection.url,
+                &selection.title,
+                &options.obsidian_api_key,
+                overwrite_path,
+                options.split_api_by_tag,
+                &fields,
+            )
+            .await
+        }
+        None => {
+            let path = match overwrite_path {
+                Some(path) => Ok(path.to_string()),
+                None => {
+                    let taken = if graph_ready {
+                        NoteLookup::Graph(&app.state::<RwLock<VaultGraph>>())
+                    } else {
+                        NoteLookup::Vault(client, &options.obsidian_api_key)
+                    };
+                    unique_note_path(&note_title, taken, claimed_paths).await
+                }
+            };
+            match path {
+                Ok(path) => {
+                    crate::save_to_obsidian(
+                        client,
+                        &note_title,
+                        &summary,
+                        &selection.url,
+                        &selection.title,
+                        &options.obsidian_api_key,
+                        Some(&path),
+                        &fields,
+                    )
+                    .await
+                }
+                Err(e) => Err(e),
+            }
+        }
+    };
+    let file_path = saved
+        .inspect_err(|e| progress("persistence", "error", e.clone()))
+        .map_err(BatchError::Save)?;
+    progress("persistence", "complete", format!("Saved: {}", file_path));
+    crate::record_baseline(app, &selection.url, &document);
+
+    let graph = app.state::<RwLock<VaultGraph>>();
+    crate::index_saved_note(&graph, client, &file_path, &options.obsidian_api_key).await;
+
+    Ok(file_path)
+}
+
+/// Where `unique_note_path` looks for existing notes
+enum NoteLookup<'a> {
+    Graph(&'a RwLock<VaultGraph>),
+    /// Asks the REST API per path, for when the graph could not be built
+    Vault(&'a reqwest::Client, &'a str),
+}
+
+impl NoteLookup<'_> {
+    async fn exists(&self, path: &str) -> Result<bool, String> {
+        match self {
+            NoteLookup::Graph(graph) => Ok(graph.read().unwrap().get(path).is_some()),
+            NoteLookup::Vault(client, api_key) => {
+                crate::vault::note_exists(client, path, api_key).await
+            }
+        }
+    }
+}
+
+/// A path for a new note that neither the vault nor another item of the
+/// batch uses: `Title.md`, else `Title (2).md` and so on. Fails rather than
+/// risk overwriting a note when the vault can't be checked.
+async fn unique_note_path(
+    title: &str,
+    lookup: NoteLookup<'_>,
+    claimed: &Mutex<HashSet<String>>,
+) -> Result<String, String> {
+    let first = crate::new_note_path(title);
+    let stem = first.trim_end_matches(".md").to_string();
+    let mut path = first;
+    let mut n = 2;
+    loop {
+        let exists = lookup
+            .exists(&path)
+            .await
+            .map_err(|e| format!("Could not check for an existing note: {}", e))?;
+        if !exists && claimed.lock().unwrap().insert(path.clone()) {
+            return Ok(path);
+        }
+        path = format!("{} ({}).md", stem, n);
+        n += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn same_titles_get_distinct_paths() {
+        let graph = RwLock::new(VaultGraph::from_notes(vec![crate::vault::parse_note(
+            "Reference/Docs/Guide.md",
+            "# Guide",
+        )]));
+        let claimed = Mutex::new(HashSet::new());
+        let path = |title| unique_note_path(title, NoteLookup::Graph(&graph), &claimed);
+        assert_eq!(path("Guide").await.unwrap(), "Reference/Docs/Guide (2).md");
+        assert_eq!(path("Guide").await.unwrap(), "Reference/Docs/Guide (3).md");
+        assert_eq!(path("a/b").await.unwrap(), "Reference/Docs/a_b.md");
+    }
+}
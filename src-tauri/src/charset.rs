@@ -0,0 +1,143 @@
+use encoding_rs::{Encoding, UTF_8};
+
+/// How far into the document a `<meta charset>` declaration is looked for
+const META_SCAN_BYTES: usize = 4096;
+
+/// Decodes a response body to text. The encoding is taken from, in order:
+/// a byte order mark, the Content-Type charset, a `<meta>` / XML declaration,
+/// and finally statistical detection (with the URL's TLD as a hint).
+pub fn decode(body: &[u8], content_type: Option<&str>, url: &str) -> String {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(body) {
+        return encoding
+            .decode_without_bom_handling(&body[bom_len..])
+            .0
+            .into_owned();
+    }
+
+    let encoding = content_type
+        .and_then(header_charset)
+        .or_else(|| declared_charset(body))
+        .unwrap_or_else(|| detect(body, url));
+    encoding.decode_without_bom_handling(body).0.into_owned()
+}
+
+fn header_charset(content_type: &str) -> Option<&'static Encoding> {
+    content_type
+        .split(';')
+        .skip(1)
+        .filter_map(|param| param.split_once('='))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("charset"))
+        .and_then(|(_, value)| Encoding::for_label(value.trim().trim_matches('"').as_bytes()))
+}
+
+/// `<meta charset="...">`, `<meta http-equiv="Content-Type" content="...; charset=...">`
+/// or `<?xml ... encoding="..."?>` near the start of the document
+fn declared_charset(body: &[u8]) -> Option<&'static Encoding> {
+    let head = &body[..body.len().min(META_SCAN_BYTES)];
+    // Declarations are ASCII, so a lossy lowercase view is enough to find them
+    let head = String::from_utf8_lossy(head).to_ascii_lowercase();
+
+    let label = if head.starts_with("<?xml") {
+        head.find("?>")
+            .and_then(|end| attribute_value(&head[..end], "encoding="))
+    } else {
+        None
+    };
+    let label = label.or_else(|| {
+        let mut rest = head.as_str();
+        while let Some(start) = rest.find("<meta") {
+            let tag = &rest[start..];
+            let tag = &tag[..tag.find('>').unwrap_or(tag.len())];
+            if let Some(value) = attribute_value(tag, "charset=") {
+                return Some(value);
+            }
+            rest = &rest[start + 5..];
+        }
+        None
+    })?;
+
+    // A UTF-16 declaration in bytes we could read as ASCII means UTF-8 (HTML spec)
+    Encoding::for_label(label.as_bytes()).map(|e| e.output_encoding())
+}
+
+/// Value after `key` (e.g. `charset=`), quoted or not
+fn attribute_value(tag: &str, key: &str) -> Option<String> {
+    let start = tag.find(key)? + key.len();
+    let value = tag[start..].trim_start_matches(['"', '\'', ' ']);
+    let value: String = value
+        .chars()
+        .take_while(|c| !matches!(c, '"' | '\'' | ';' | ' ' | '/' | '>'))
+        .collect();
+    (!value.is_empty()).then_some(value)
+}
+
+fn detect(body: &[u8], url: &str) -> &'static Encoding {
+    if std::str::from_utf8(body).is_ok() {
+        return UTF_8;
+    }
+    let host = reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.to_string()))
+        .unwrap_or_default();
+    let tld = host.rsplit('.').next().filter(|t| !t.is_empty());
+
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(body, true);
+    detector.guess(tld.map(|t| t.as_bytes()), true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_bom_wins_over_the_header() {
+        let body = [b"\xEF\xBB\xBF".as_slice(), "caf\u{e9}".as_bytes()].concat();
+        assert_eq!(
+            decode(
+                &body,
+                Some("text/html; charset=iso-8859-1"),
+                "https://a.example/"
+            ),
+            "caf\u{e9}"
+        );
+    }
+
+    #[test]
+    fn the_header_charset_is_used() {
+        assert_eq!(
+            decode(
+                b"caf\xE9",
+                Some("text/html; Charset=\"ISO-8859-1\""),
+                "https://a.example/"
+            ),
+            "caf\u{e9}"
+        );
+    }
+
+    #[test]
+    fn meta_and_xml_declarations_are_read() {
+        let html = b"<html><head><meta http-equiv=\"Content-Type\" content=\"text/html; charset=windows-1251\"></head>\xCF\xF0\xE8\xE2\xE5\xF2";
+        assert!(decode(html, Some("text/html"), "https://a.example/").ends_with("Привет"));
+
+        let xml = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><a>caf\xE9</a>";
+        assert!(decode(xml, None, "https://a.example/").contains("caf\u{e9}"));
+
+        // A UTF-16 declaration in ASCII-readable bytes means UTF-8
+        let utf8 = "<meta charset=\"utf-16\">café".as_bytes();
+        assert!(decode(utf8, None, "https://a.example/").ends_with("café"));
+    }
+
+    #[test]
+    fn undeclared_text_is_detected() {
+        assert_eq!(
+            decode("naïve".as_bytes(), None, "https://a.example/"),
+            "naïve"
+        );
+        let shift_jis = b"\x82\xb1\x82\xf1\x82\xc9\x82\xbf\x82\xcd\x90\xa2\x8a\x45";
+        assert_eq!(
+            decode(shift_jis, None, "https://a.example.jp/"),
+            "こんにちは世界"
+        );
+    }
+}
@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Message;
+
+/// A vault conversation. `messages` only holds the user questions and the
+/// assistant answers; retrieved note context is rebuilt on every turn.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ChatSession {
+    pub id: String,
+    pub title: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub messages: Vec<Message>,
+}
+
+/// Lightweight listing entry for the UI
+#[derive(Serialize, Clone)]
+pub struct ChatSessionSummary {
+    pub id: String,
+    pub title: String,
+    pub updated_at: String,
+    pub turns: usize,
+}
+
+/// Chat sessions kept in memory and mirrored to `<app data>/chat_sessions/<id>.json`
+pub struct ChatStore {
+    dir: PathBuf,
+    sessions: Mutex<HashMap<String, ChatSession>>,
+}
+
+impl ChatStore {
+    pub fn load(dir: PathBuf) -> Result<Self, String> {
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create chat session dir: {}", e))?;
+
+        let mut sessions = HashMap::new();
+        let entries =
+            std::fs::read_dir(&dir).map_err(|e| format!("Failed to read chat sessions: {}", e))?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            // A corrupt session file should not prevent the app from starting
+            if let Ok(text) = std::fs::read_to_string(&path) {
+                if let Ok(session) = serde_json::from_str::<ChatSession>(&text) {
+                    sessions.insert(session.id.clone(), session);
+                }
+            }
+        }
+
+        Ok(Self {
+            dir,
+            sessions: Mutex::new(sessions),
+        })
+    }
+
+    /// Returns the requested session, or a fresh one when no `id` is given.
+    /// An unknown `id` is an error rather than a silent new conversation.
+    pub fn get_or_create(
+        &self,
+        id: Option<&str>,
+        first_question: &str,
+    ) -> Result<ChatSession, String> {
+        if let Some(id) = id {
+            return self
+                .get(id)
+                .ok_or_else(|| format!("Unknown chat session: {}", id));
+        }
+
+        let now = chrono::Local::now();
+        Ok(ChatSession {
+            id: uuid::Uuid::new_v4().to_string(),
+            title: first_question.chars().take(60).collect(),
+            created_at: now.to_rfc3339(),
+            updated_at: now.to_rfc3339(),
+            messages: Vec::new(),
+        })
+    }
+
+    pub fn get(&self, id: &str) -> Option<ChatSession> {
+        self.sessions.lock().unwrap().get(id).cloned()
+    }
+
+    pub fn list(&self) -> Vec<ChatSessionSummary> {
+        let mut list: Vec<ChatSessionSummary> = self
+            .sessions
+            .lock()
+            .unwrap()
+            .values()
+            .map(|s| ChatSessionSummary {
+                id: s.id.clone(),
+                title: s.title.clone(),
+                updated_at: s.updated_at.clone(),
+                turns: s.messages.iter().filter(|m| m.role == "user").count(),
+            })
+            .collect();
+        list.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        list
+    }
+
+    /// Appends a turn to the stored copy of `session`, or stores `session`
+    /// with it when new. The copy is re-read under the lock, so turns that
+    /// finish concurrently on one session are both kept.
+    pub fn append_turn(&self, session: &ChatSession, turn: Vec<Message>) -> Result<(), String> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let mut stored = sessions
+            .get(&session.id)
+            .cloned()
+            .unwrap_or_else(|| session.clone());
+        stored.messages.extend(turn);
+        stored.updated_at = chrono::Local::now().to_rfc3339();
+        let json = serde_json::to_string_pretty(&stored).map_err(|e| e.to_string())?;
+        std::fs::write(self.dir.join(format!("{}.json", stored.id)), json)
+            .map_err(|e| format!("Failed to save chat session: {}", e))?;
+        sessions.insert(stored.id.clone(), stored);
+        Ok(())
+    }
+
+    /// Only sessions the store knows are removed, so `id` can never name
+    /// another file
+    pub fn delete(&self, id: &str) -> Result<(), String> {
+        let session = self
+            .sessions
+            .lock()
+            .unwrap()
+            .remove(id)
+            .ok_or_else(|| format!("Unknown chat session: {}", id))?;
+        let path = self.dir.join(format!("{}.json", session.id));
+        if path.exists() {
+            std::fs::remove_file(path)
+                .map_err(|e| format!("Failed to delete chat session: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+/// Builds the prompt that turns a follow-up ("what about its config?") into a
+/// query that can be searched without the conversation.
+pub fn condense_question_prompt(history: &[Message], question: &str) -> Vec<Message> {
+    let transcript = history
+        .iter()
+        .map(|m| format!("{}: {}", m.role, m.content))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    vec![
+        Message {
+            role: "system".to_string(),
+            content: "Rewrite the user's follow-up question as a standalone search query for their notes. \
+                Resolve pronouns and references using the conversation. \
+                Reply with the query only, no explanation or quotes."
+                .to_string(),
+        },
+        Message {
+            role: "user".to_string(),
+            content: format!(
+                "Conversation:\n{}\n\nFollow-up question: {}\n\nStandalone query:",
+                transcript, question
+            ),
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: &str, content: &str) -> Message {
+        Message {
+            role: role.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    fn store() -> (ChatStore, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("chat-{}", uuid::Uuid::new_v4()));
+        (ChatStore::load(dir.clone()).unwrap(), dir)
+    }
+
+    #[test]
+    fn sessions_survive_a_reload() {
+        let (store, dir) = store();
+        let session = store.get_or_create(None, "How do I set up sync?").unwrap();
+        store
+            .append_turn(
+                &session,
+                vec![
+                    message("user", "How do I set up sync?"),
+                    message("assistant", "Use the plugin."),
+                ],
+            )
+            .unwrap();
+
+        let reloaded = ChatStore::load(dir.clone()).unwrap();
+        let restored = reloaded.get(&session.id).unwrap();
+        assert_eq!(restored.title, "How do I set up sync?");
+        assert_eq!(restored.messages.len(), 2);
+        assert_eq!(restored.messages[1].content, "Use the plugin.");
+        assert_eq!(reloaded.list()[0].turns, 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn concurrent_turns_are_both_kept() {
+        let (store, dir) = store();
+        let session = store.get_or_create(None, "q").unwrap();
+        let other = store.get_or_create(None, "q").unwrap();
+        assert_ne!(session.id, other.id);
+
+        // Both turns started from the same, still empty, copy
+        store
+            .append_turn(&session, vec![message("user", "first")])
+            .unwrap();
+        store
+            .append_turn(&session, vec![message("user", "second")])
+            .unwrap();
+        let contents: Vec<_> = store
+            .get(&session.id)
+            .unwrap()
+            .messages
+            .into_iter()
+            .map(|m| m.content)
+            .collect();
+        assert_eq!(contents, ["first", "second"]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unknown_ids_are_rejected() {
+        let (store, dir) = store();
+        std::fs::write(dir.join("notes.txt"), "keep").unwrap();
+        assert!(store.get_or_create(Some("missing"), "q").is_err());
+        assert!(store.delete("../notes").is_err());
+        assert!(store.delete("notes.txt").is_err());
+        assert!(dir.join("notes.txt").exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn history_goes_into_the_rewrite_prompt() {
+        let history = [
+            message("user", "What is Tantivy?"),
+            message("assistant", "A search library."),
+        ];
+        let prompt = condense_question_prompt(&history, "How do I configure it?");
+        assert_eq!(prompt[0].role, "system");
+        let request = &prompt[1].content;
+        assert!(request.contains("user: What is Tantivy?\nassistant: A search library."));
+        assert!(request.contains("Follow-up question: How do I configure it?"));
+    }
+}
@@ -0,0 +1,567 @@
+use std::collections::{HashSet, VecDeque};
+use std::sync::RwLock;
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use crate::extract::{Document, DocumentKind};
+use crate::fetch::CacheMode;
+use crate::politeness::pattern_matches;
+use crate::urls::normalize_url;
+use crate::vault::VaultGraph;
+use crate::{emit_log, DuplicatePolicy, WorkflowOptions};
+
+/// Links to these are never pages
+const ASSET_EXTENSIONS: &[&str] = &[
+    ".png", ".jpg", ".jpeg", ".gif", ".svg", ".webp", ".ico", ".css", ".js", ".zip", ".tar", ".gz",
+    ".mp4", ".woff", ".woff2",
+];
+
+/// Bounds for a docs crawl
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CrawlLimits {
+    /// Link hops from the start page
+    #[serde(default = "default_max_depth")]
+    pub max_depth: usize,
+    #[serde(default = "default_max_pages")]
+    pub max_pages: usize,
+    /// Path patterns (robots.txt syntax: `*` wildcards, `$` anchor); when
+    /// set, only matching pages are crawled
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Take the page list from the site's sitemap.xml instead of following links
+    #[serde(default)]
+    pub use_sitemap: bool,
+}
+
+fn default_max_depth() -> usize {
+    2
+}
+
+fn default_max_pages() -> usize {
+    30
+}
+
+impl Default for CrawlLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: default_max_depth(),
+            max_pages: default_max_pages(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            use_sitemap: false,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct CrawledNote {
+    pub url: String,
+    pub title: String,
+    pub depth: usize,
+    pub file_path: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct CrawlResult {
+    pub index_path: String,
+    /// In index order: every page followed by its children
+    pub pages: Vec<CrawledNote>,
+}
+
+struct Page {
+    url: String,
+    title: String,
+    document: Document,
+    /// Index of the nearest crawled page above this one in the URL tree
+    parent: Option<usize>,
+    depth: usize,
+}
+
+/// The part of the site a crawl stays in: the start page's host and directory
+struct Scope {
+    host: String,
+    prefix: String,
+}
+
+impl Scope {
+    fn new(start: &reqwest::Url) -> Self {
+        let path = start.path();
+        let prefix = match path.rfind('/') {
+            Some(i) => &path[..=i],
+            None => "/",
+        };
+        Self {
+            host: start.host_str().unwrap_or_default().to_string(),
+            prefix: prefix.to_string(),
+        }
+    }
+
+    fn contains(&self, url: &reqwest::Url, limits: &CrawlLimits) -> bool {
+        let path = url.path();
+        let lower = path.to_lowercase();
+        matches!(url.scheme(), "http" | "https")
+            && url
+                .host_str()
+                .unwrap_or_default()
+                .trim_start_matches("www.")
+                == self.host.trim_start_matches("www.")
+            && path.starts_with(&self.prefix)
+            && !ASSET_EXTENSIONS.iter().any(|ext| lower.ends_with(ext))
+            && (limits.include.is_empty()
+                || limits.include.iter().any(|p| pattern_matches(p, path)))
+            && !limits.exclude.iter().any(|p| pattern_matches(p, path))
+    }
+}
+
+/// Value of `name="..."` (or single-quoted / bare) inside a tag
+pub fn tag_attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(found) = lower[from..].find(name) {
+        let start = from + found;
+        from = start + name.len();
+        let before = lower[..start].chars().last();
+        if !before.is_some_and(char::is_whitespace) {
+            continue;
+        }
+        let rest = lower[from..].trim_start();
+        let Some(rest) = rest.strip_prefix('=') else {
+            continue;
+        };
+        let offset = tag.len() - rest.trim_start().len();
+        let value = &tag[offset..];
+        let value = match value.chars().next() {
+            Some(q @ ('"' | '\'')) => value[1..].split(q).next().unwrap_or(""),
+            _ => value
+                .split(|c: char| c.is_whitespace() || c == '>')
+                .next()
+                .unwrap_or(""),
+        };
+        return Some(value.replace("&amp;", "&"));
+    }
+    None
+}
+
+/// `href`s of `<a>` tags, in document order, and the canonical URL if declared
+fn page_links(html: &str) -> (Vec<String>, Option<String>) {
+    let mut links = Vec::new();
+    let mut canonical = None;
+    let mut rest = html;
+    while let Some(lt) = rest.find('<') {
+        rest = &rest[lt + 1..];
+        let end = rest.find('>').unwrap_or(rest.len());
+        let tag = &rest[..end];
+        let name: String = tag
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        match name.as_str() {
+            "a" => links.extend(tag_attribute(tag, "href")),
+            "link"
+                if canonical.is_none()
+                    && tag_attribute(tag, "rel")
+                        .is_some_and(|r| r.eq_ignore_ascii_case("canonical")) =>
+            {
+                canonical = tag_attribute(tag, "href");
+            }
+            _ => {}
+        }
+        rest = &rest[end..];
+    }
+    (links, canonical)
+}
+
+/// Resolves a link against the page it appears on, without the fragment
+fn resolve(base: &reqwest::Url, href: &str) -> Option<reqwest::Url> {
+    let mut url = base.join(href.trim()).ok()?;
+    url.set_fragment(None);
+    Some(url)
+}
+
+/// Fetches the start page and everything reachable from it within `limits`.
+/// Pages are deduplicated by normalized URL and by their canonical link.
+async fn crawl(
+    app: &tauri::AppHandle,
+    window: &tauri::WebviewWindow,
+    client: &reqwest::Client,
+    start: &reqwest::Url,
+    limits: &CrawlLimits,
+    headless: bool,
+) -> Result<Vec<Page>, String> {
+    let scope = Scope::new(start);
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut queue: VecDeque<(reqwest::Url, usize)> = VecDeque::new();
+    seen.insert(normalize_url(start.as_str()));
+    queue.push_back((start.clone(), 0));
+
+    if limits.use_sitemap {
+        let urls = crate::discover::sitemap_urls(app, client, start, usize::MAX).await;
+        if urls.is_empty() {
+            return Err("No sitemap found".to_string());
+        }
+        for url in urls
+            .iter()
+            .filter_map(|u| reqwest::Url::parse(u).ok())
+            .filter(|u| scope.contains(u, limits))
+        {
+            if seen.insert(normalize_url(url.as_str())) {
+                queue.push_back((url, 1));
+            }
+        }
+    }
+
+    let mut pages: Vec<Page> = Vec::new();
+    while let Some((url, depth)) = queue.pop_front() {
+        if pages.len() >= limits.max_pages {
+            break;
+        }
+        emit_log(
+            window,
+            "acquisition",
+            "running",
+            &format!("[{}/{}] {}", pages.len() + 1, limits.max_pages, url),
+        )?;
+        // Links are read from the rendered DOM when the page needed rendering
+        let fetched = match crate::fetch_page(
+            app,
+            client,
+            url.as_str(),
+            headless,
+            CacheMode::Default,
+        )
+        .await
+        {
+            Ok(page) => page,
+            Err(e) => {
+                emit_log(window, "acquisition", "error", &format!("{}: {}", url, e))?;
+                continue;
+            }
+        };
+
+        if fetched.document.kind == DocumentKind::Html {
+            let (links, canonical) = page_links(&fetched.html(url.as_str()));
+
+            // The same page reachable under several URLs is kept once
+            if let Some(canonical) = canonical.and_then(|c| resolve(&url, &c)) {
+                let key = normalize_url(canonical.as_str());
+                if key != normalize_url(url.as_str()) && !seen.insert(key) {
+                    continue;
+                }
+            }
+            if !limits.use_sitemap && depth < limits.max_depth {
+                for link in links.iter().filter_map(|href| resolve(&url, href)) {
+                    if scope.contains(&link, limits) && seen.insert(normalize_url(link.as_str())) {
+                        queue.push_back((link, depth + 1));
+                    }
+                }
+            }
+        }
+
+        let document = fetched.document;
+        let title = document
+            .title
+            .clone()
+            .filter(|t| !t.trim().is_empty())
+            .unwrap_or_else(|| url.path().to_string());
+        pages.push(Page {
+            url: url.to_string(),
+            title,
+            document,
+            parent: None,
+            depth,
+        });
+    }
+
+    // Hierarchy follows the URL tree: a page's parent is the crawled page
+    // with the longest path prefix, falling back to the start page
+    let paths: Vec<String> = pages
+        .iter()
+        .map(|p| {
+            reqwest::Url::parse(&p.url)
+                .map(|u| u.path().trim_end_matches('/').to_string())
+                .unwrap_or_default()
+        })
+        .collect();
+    for i in 1..pages.len() {
+        pages[i].parent = (0..pages.len())
+            .filter(|&j| j != i && paths[i].starts_with(&format!("{}/", paths[j])))
+            .max_by_key(|&j| paths[j].len())
+            .or(Some(0));
+    }
+    Ok(pages)
+}
+
+/// Start page first, then each page followed by its children in the order
+/// they were discovered
+fn tree_order(pages: &[Page]) -> Vec<usize> {
+    fn visit(pages: &[Page], node: usize, out: &mut Vec<usize>) {
+        out.push(node);
+        for child in (0..pages.len()).filter(|&c| pages[c].parent == Some(node)) {
+            visit(pages, child, out);
+        }
+    }
+    let mut order = Vec::with_capacity(pages.len());
+    if !pages.is_empty() {
+        visit(pages, 0, &mut order);
+    }
+    order
+}
+
+fn sanitize(segment: &str) -> String {
+    segment.replace(
+        [
+            '/', '\\', ':', '*', '?', '"', '<', '>', '|', '#', '^', '[', ']',
+        ],
+        "_",
+    )
+}
+
+/// Vault path (without `.md`) for every page, mirroring the URL path below
+/// the crawl scope. The start page is the folder's `index` note.
+fn note_paths(pages: &[Page], folder: &str, prefix: &str) -> Vec<String> {
+    let mut used = HashSet::new();
+    pages
+        .iter()
+        .enumerate()
+        .map(|(i, page)| {
+            let url = reqwest::Url::parse(&page.url).ok();
+            let relative = url
+                .as_ref()
+                .map(|u| u.path().strip_prefix(prefix).unwrap_or(u.path()))
+                .unwrap_or_default();
+            let relative = relative
+                .trim_matches('/')
+                .trim_end_matches(".html")
+                .trim_end_matches(".htm");
+            let mut name = if i == 0 || relative.is_empty() {
+                "index".to_string()
+            } else {
+                relative
+                    .split('/')
+                    .map(sanitize)
+                    .collect::<Vec<_>>()
+                    .join("/")
+            };
+            if let Some(query) = url.as_ref().and_then(|u| u.query()) {
+                name = format!("{}_{}", name, sanitize(query));
+            }
+            let mut path = format!("{}/{}", folder, name);
+            let mut n = 2;
+            while !used.insert(path.clone()) {
+                path = format!("{}/{}-{}", folder, name, n);
+                n += 1;
+            }
+            path
+        })
+        .collect()
+}
+
+/// Crawls a docs site from `start_url` and saves it as a folder of notes
+/// linked to their parent and children, plus an index note. A start URL
+/// already in the vault is handled by `options.on_duplicate`; a re-crawl
+/// rewrites the same notes.
+pub async fn crawl_docs(
+    app: &tauri::AppHandle,
+    window: &tauri::WebviewWindow,
+    client: &reqwest::Client,
+    start_url: &str,
+    options: &WorkflowOptions,
+    limits: &CrawlLimits,
+) -> Result<CrawlResult, String> {
+    let start = reqwest::Url::parse(start_url.trim()).map_err(|e| format!("Invalid URL: {}", e))?;
+
+    let graph = app.state::<RwLock<VaultGraph>>();
+    let api_key = &options.obsidian_api_key;
+    if let Err(e) = crate::ensure_vault_graph(&graph, client, api_key).await {
+        emit_log(
+            window,
+            "discovery",
+            "running",
+            &format!("Duplicate check unavailable: {}", e),
+        )?;
+    }
+    let existing = graph.read().unwrap().find_by_source(start.as_str());
+    if let Some(existing_path) = existing {
+        match options.on_duplicate {
+            DuplicatePolicy::Skip => {
+                emit_log(
+                    window,
+                    "discovery",
+                    "skipped",
+                    &format!("Already in vault: {}", existing_path),
+                )?;
+                return Ok(CrawlResult {
+                    index_path: existing_path,
+                    pages: Vec::new(),
+                });
+            }
+            DuplicatePolicy::Warn => emit_log(
+                window,
+                "discovery",
+                "running",
+                &format!("Warning: already saved as {}", existing_path),
+            )?,
+            DuplicatePolicy::Refresh => emit_log(
+                window,
+                "discovery",
+                "running",
+                &format!("Re-crawling into existing notes from {}", existing_path),
+            )?,
+        }
+    }
+
+    let pages = crawl(app, window, client, &start, limits, options.headless).await?;
+    let root = pages.first().ok_or("Start page could not be fetched")?;
+    emit_log(
+        window,
+        "acquisition",
+        "complete",
+        &format!("Crawled {} pages", pages.len()),
+    )?;
+
+    let site = sanitize(&root.title);
+    let folder = format!("Reference/Docs/{}", site);
+    let paths = note_paths(&pages, &folder, &Scope::new(&start).prefix);
+    let link = |i: usize| format!("[[{}|{}]]", paths[i], pages[i].title.replace('|', "-"));
+    let order = tree_order(&pages);
+
+    let mut notes = Vec::with_capacity(pages.len());
+    for (n, &i) in order.iter().enumerate() {
+        let page = &pages[i];
+        let summary = if options.use_ai {
+            emit_log(
+                window,
+                "synthesis",
+                "running",
+                &format!("[{}/{}] Summarizing {}...", n + 1, order.len(), page.title),
+            )?;
+            crate::call_ollama_summarize(
+                app,
+                client,
+                &page.document,
+                &options.model_name,
+                options.bypass_llm_cache,
+            )
+            .await
+        } else {
+            Ok(page.document.content.clone())
+        };
+
+        let saved = match summary {
+            Ok(summary) => {
+                let mut body = summary;
+                if let Some(parent) = page.parent {
+                    body = format!("Up: {}\n\n{}", link(parent), body);
+                }
+                let children: Vec<String> = order
+                    .iter()
+                    .filter(|&&c| pages[c].parent == Some(i))
+                    .map(|&c| format!("- {}", link(c)))
+                    .collect();
+                if !children.is_empty() {
+                    body = format!("{}\n\n## Pages\n\n{}", body, children.join("\n"));
+                }
+                crate::save_to_obsidian(
+                    client,
+                    &page.title,
+                    &body,
+                    &page.url,
+                    &page.title,
+                    &options.obsidian_api_key,
+                    Some(&format!("{}.md", paths[i])),
+                    &crate::source_fields(app, &page.url, &page.document),
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+        match &saved {
+            Ok(path) => {
+                crate::record_baseline(app, &page.url, &page.document);
+                crate::index_saved_note(&graph, client, path, api_key).await
+            }
+            Err(e) => emit_log(
+                window,
+                "persistence",
+                "error",
+                &format!("{}: {}", page.url, e),
+            )?,
+        }
+        notes.push(CrawledNote {
+            url: page.url.clone(),
+            title: page.title.clone(),
+            depth: page.depth,
+            file_path: saved.as_ref().ok().cloned(),
+            error: saved.err(),
+        });
+    }
+
+    // Index: the page tree as a nested list
+    let mut levels = vec![0usize; pages.len()];
+    for &i in &order {
+        levels[i] = pages[i].parent.map_or(0, |p| levels[p] + 1);
+    }
+    let index: Vec<String> = order
+        .iter()
+        .map(|&i| format!("{}- {}", "  ".repeat(levels[i]), link(i)))
+        .collect();
+    let index_path = crate::save_to_obsidian(
+        client,
+        &format!("{} (docs index)", root.title),
+        &format!(
+            "{} pages crawled from {}\n\n{}",
+            pages.len(),
+            start_url.trim(),
+            index.join("\n")
+        ),
+        start_url.trim(),
+        &root.title,
+        api_key,
+        None,
+        &[],
+    )
+    .await?;
+    crate::index_saved_note(&graph, client, &index_path, api_key).await;
+
+    Ok(CrawlResult {
+        index_path,
+        pages: notes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attributes_are_read_in_every_quoting_style() {
+        assert_eq!(
+            tag_attribute("a class=nav HREF='/docs?a=1&amp;b=2'", "href").as_deref(),
+            Some("/docs?a=1&b=2")
+        );
+        assert_eq!(
+            tag_attribute("a href = \"/spaced\" title=x", "href").as_deref(),
+            Some("/spaced")
+        );
+        assert_eq!(
+            tag_attribute("link rel=canonical href=https://a.example/docs/", "href").as_deref(),
+            Some("https://a.example/docs/")
+        );
+    }
+
+    #[test]
+    fn attribute_names_must_stand_alone() {
+        assert_eq!(
+            tag_attribute("a data-href=\"no\" href=\"yes\"", "href").as_deref(),
+            Some("yes")
+        );
+        assert_eq!(tag_attribute("a hreflang=en", "href"), None);
+        assert_eq!(tag_attribute("a title=\"href=x\"", "href"), None);
+    }
+}
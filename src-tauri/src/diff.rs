@@ -0,0 +1,421 @@
+use serde::Serialize;
+
+/// Largest LCS table (old blocks × new blocks, after trimming what both
+/// ends share) diffed block by block; past it the rest counts as replaced
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+/// A paragraph/list item or a fenced code block
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", content = "text", rename_all = "lowercase")]
+pub enum Block {
+    Text(String),
+    Code(String),
+}
+
+/// Content under one heading. `heading` is the full path
+/// (`Install > Linux`) so equally named subsections don't collide.
+#[derive(Clone, Debug)]
+struct Section {
+    heading: String,
+    blocks: Vec<Block>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct BlockChange {
+    pub kind: ChangeKind,
+    pub block: Block,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SectionStatus {
+    Added,
+    Removed,
+    Modified,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SectionDiff {
+    pub heading: String,
+    pub status: SectionStatus,
+    pub changes: Vec<BlockChange>,
+}
+
+/// Section-aware diff between two Markdown documents
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct DocumentDiff {
+    pub sections: Vec<SectionDiff>,
+}
+
+impl DocumentDiff {
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+
+    /// Markdown body for a "Changes since" section
+    pub fn to_markdown(&self) -> String {
+        let mut out = Vec::new();
+        for section in &self.sections {
+            let label = match section.status {
+                SectionStatus::Added => "new section",
+                SectionStatus::Removed => "removed section",
+                SectionStatus::Modified => "modified",
+            };
+            out.push(format!("### {} ({})", section.heading, label));
+            for change in &section.changes {
+                let sign = match change.kind {
+                    ChangeKind::Added => "➕",
+                    ChangeKind::Removed => "➖",
+                };
+                match &change.block {
+                    Block::Text(t) if change.kind == ChangeKind::Removed => {
+                        out.push(format!("- {} ~~{}~~", sign, t))
+                    }
+                    Block::Text(t) => out.push(format!("- {} {}", sign, t)),
+                    Block::Code(code) => {
+                        out.push(format!("- {} code block:", sign));
+                        out.push(format!("```\n{}\n```", code));
+                    }
+                }
+            }
+            out.push(String::new());
+        }
+        out.join("\n").trim_end().to_string()
+    }
+}
+
+/// Sections in document order. Text before the first heading goes into an
+/// "(Introduction)" section.
+fn parse_sections(markdown: &str) -> Vec<Section> {
+    let mut sections = vec![Section {
+        heading: "(Introduction)".to_string(),
+        blocks: Vec::new(),
+    }];
+    let mut path: Vec<(usize, String)> = Vec::new();
+    let mut code: Option<Vec<&str>> = None;
+
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            match code.take() {
+                Some(lines) => {
+                    let block = Block::Code(lines.join("\n"));
+                    sections.last_mut().unwrap().blocks.push(block);
+                }
+                None => code = Some(Vec::new()),
+            }
+            continue;
+        }
+        if let Some(lines) = code.as_mut() {
+            lines.push(line);
+            continue;
+        }
+
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let level = trimmed.chars().take_while(|c| *c == '#').count();
+        if (1..=6).contains(&level) && trimmed[level..].starts_with(' ') {
+            let title = trimmed[level..].trim().to_string();
+            path.retain(|(l, _)| *l < level);
+            path.push((level, title));
+            sections.push(Section {
+                heading: path
+                    .iter()
+                    .map(|(_, t)| t.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" > "),
+                blocks: Vec::new(),
+            });
+            continue;
+        }
+        sections
+            .last_mut()
+            .unwrap()
+            .blocks
+            .extend(split_long(trimmed).into_iter().map(Block::Text));
+    }
+    // Unterminated fence: keep what we have
+    if let Some(lines) = code {
+        sections
+            .last_mut()
+            .unwrap()
+            .blocks
+            .push(Block::Code(lines.join("\n")));
+    }
+
+    sections.retain(|s| !s.blocks.is_empty() || s.heading != "(Introduction)");
+    sections
+}
+
+/// Very long lines (e.g. flattened page text) are compared sentence by sentence
+fn split_long(line: &str) -> Vec<String> {
+    if line.len() <= 300 {
+        return vec![line.to_string()];
+    }
+    let mut out = Vec::new();
+    let mut current = String::new();
+    for word in line.split_whitespace() {
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+        if word.ends_with(['.', '!', '?']) {
+            out.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        out.push(current);
+    }
+    out
+}
+
+/// Longest-common-subsequence edit script over two slices. The common
+/// prefix and suffix are skipped; a middle too large for the table is
+/// reported as removed and added whole.
+fn lcs_changes<T: PartialEq + Clone>(a: &[T], b: &[T]) -> Vec<(ChangeKind, T)> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    let suffix = a
+        .iter()
+        .rev()
+        .zip(b.iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a, b) = (&a[..a.len() - suffix], &b[..b.len() - suffix]);
+
+    if (a.len() + 1).saturating_mul(b.len() + 1) > MAX_LCS_CELLS {
+        return a
+            .iter()
+            .cloned()
+            .map(|x| (ChangeKind::Removed, x))
+            .chain(b.iter().cloned().map(|x| (ChangeKind::Added, x)))
+            .collect();
+    }
+
+    // lcs[i][j] = LCS length of a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            changes.push((ChangeKind::Removed, a[i].clone()));
+            i += 1;
+        } else {
+            changes.push((ChangeKind::Added, b[j].clone()));
+            j += 1;
+        }
+    }
+    changes.extend(a[i..].iter().cloned().map(|x| (ChangeKind::Removed, x)));
+    changes.extend(b[j..].iter().cloned().map(|x| (ChangeKind::Added, x)));
+    changes
+}
+
+/// Compares two Markdown documents section by section. Sections are matched
+/// by heading path; blocks inside a matched section are diffed in order.
+pub fn diff_documents(old: &str, new: &str) -> DocumentDiff {
+    let old_sections = parse_sections(old);
+    let new_sections = parse_sections(new);
+    let mut sections = Vec::new();
+
+    for new_section in &new_sections {
+        match old_sections
+            .iter()
+            .find(|s| s.heading == new_section.heading)
+        {
+            None => sections.push(SectionDiff {
+                heading: new_section.heading.clone(),
+                status: SectionStatus::Added,
+                changes: new_section
+                    .blocks
+                    .iter()
+                    .map(|b| BlockChange {
+                        kind: ChangeKind::Added,
+                        block: b.clone(),
+                    })
+                    .collect(),
+            }),
+            Some(old_section) => {
+                let changes: Vec<BlockChange> =
+                    lcs_changes(&old_section.blocks, &new_section.blocks)
+                        .into_iter()
+                        .map(|(kind, block)| BlockChange { kind, block })
+                        .collect();
+                if !changes.is_empty() {
+                    sections.push(SectionDiff {
+                        heading: new_section.heading.clone(),
+                        status: SectionStatus::Modified,
+                        changes,
+                    });
+                }
+            }
+        }
+    }
+
+    for old_section in &old_sections {
+        if !new_sections
+            .iter()
+            .any(|s| s.heading == old_section.heading)
+        {
+            sections.push(SectionDiff {
+                heading: old_section.heading.clone(),
+                status: SectionStatus::Removed,
+                changes: old_section
+                    .blocks
+                    .iter()
+                    .map(|b| BlockChange {
+                        kind: ChangeKind::Removed,
+                        block: b.clone(),
+                    })
+                    .collect(),
+            });
+        }
+    }
+
+    DocumentDiff { sections }
+}
+
+/// Prompt asking the model for a short "what changed" summary of a diff
+pub fn summary_prompt(url: &str, diff: &DocumentDiff) -> String {
+    let rendered: String = diff.to_markdown().chars().take(6000).collect();
+    format!(
+        "The documentation page {} changed. Below is a section-by-section diff \
+        (➕ added, ➖ removed).\n\n{}\n\n\
+        Summarize what changed in 3-5 bullet points for a developer. \
+        Mention renamed or removed APIs and changed code examples explicitly.",
+        url, rendered
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(t: &str) -> Block {
+        Block::Text(t.to_string())
+    }
+
+    #[test]
+    fn identical_documents_have_no_changes() {
+        let doc = "Intro\n\n# Install\n\nRun it.\n\n```\nnpm i\n```\n";
+        assert!(diff_documents(doc, doc).is_empty());
+    }
+
+    #[test]
+    fn sections_are_matched_by_heading_path() {
+        let old = "# Install\n## Linux\nOld step.\n# Usage\nCall it.\n# Legacy\nGone soon.\n";
+        let new = "# Install\n## Linux\nNew step.\n# Usage\nCall it.\n# Upgrade\nBump it.\n";
+        let diff = diff_documents(old, new);
+        let summary: Vec<_> = diff
+            .sections
+            .iter()
+            .map(|s| (s.heading.as_str(), s.status.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("Install > Linux", SectionStatus::Modified),
+                ("Upgrade", SectionStatus::Added),
+                ("Legacy", SectionStatus::Removed),
+            ]
+        );
+
+        let changes = &diff.sections[0].changes;
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].kind, ChangeKind::Removed);
+        assert_eq!(changes[0].block, text("Old step."));
+        assert_eq!(changes[1].kind, ChangeKind::Added);
+        assert_eq!(changes[1].block, text("New step."));
+    }
+
+    #[test]
+    fn code_blocks_compare_as_a_whole() {
+        let old = "# Api\n```rust\nfoo(1);\n```\n";
+        let new = "# Api\n```rust\nfoo(2);\n```\n";
+        let diff = diff_documents(old, new);
+        let blocks: Vec<_> = diff.sections[0]
+            .changes
+            .iter()
+            .map(|c| c.block.clone())
+            .collect();
+        assert_eq!(
+            blocks,
+            [
+                Block::Code("foo(1);".to_string()),
+                Block::Code("foo(2);".to_string())
+            ]
+        );
+        let markdown = diff.to_markdown();
+        assert!(markdown.starts_with("### Api (modified)"));
+        assert!(markdown.contains("```\nfoo(2);\n```"));
+    }
+
+    #[test]
+    fn long_lines_are_diffed_by_sentence() {
+        let filler = "word ".repeat(70);
+        let old = format!("{}end. Same sentence.", filler);
+        let new = format!("{}end. Other sentence.", filler);
+        let diff = diff_documents(&old, &new);
+        let changes = &diff.sections[0].changes;
+        assert_eq!(diff.sections[0].heading, "(Introduction)");
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].block, text("Same sentence."));
+        assert_eq!(changes[1].block, text("Other sentence."));
+    }
+
+    #[test]
+    fn huge_sections_fall_back_to_replacement() {
+        let same: Vec<u32> = (0..10).collect();
+        let old: Vec<u32> = same
+            .iter()
+            .copied()
+            .chain(100..3100)
+            .chain(same.iter().copied())
+            .collect();
+        let new: Vec<u32> = same
+            .iter()
+            .copied()
+            .chain(5000..8000)
+            .chain(same.iter().copied())
+            .collect();
+        let changes = lcs_changes(&old, &new);
+        assert_eq!(changes.len(), 6000);
+        assert!(changes[..3000]
+            .iter()
+            .all(|(k, _)| *k == ChangeKind::Removed));
+        assert_eq!(changes[0].1, 100);
+        assert_eq!(changes[3000], (ChangeKind::Added, 5000));
+
+        // Shared ends alone don't need a table
+        let long: Vec<u32> = (0..50_000).collect();
+        let mut edited = long.clone();
+        edited[25_000] = 1_000_000;
+        assert_eq!(
+            lcs_changes(&long, &edited),
+            [
+                (ChangeKind::Removed, 25_000),
+                (ChangeKind::Added, 1_000_000)
+            ]
+        );
+    }
+}
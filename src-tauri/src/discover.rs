@@ -0,0 +1,357 @@
+use std::collections::HashSet;
+use std::io::Read;
+
+use crate::crawl::tag_attribute;
+use crate::fetch::CacheMode;
+use crate::SearXNGResult;
+
+/// Child sitemaps followed from a sitemap index
+const MAX_SITEMAPS: usize = 20;
+
+/// Decompressed sitemaps larger than this are cut off (the spec allows 50 MB)
+const MAX_SITEMAP_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Where sites usually put their feed when the page doesn't link one
+const COMMON_FEED_PATHS: &[&str] = &["/feed", "/rss.xml", "/atom.xml", "/index.xml", "/feed.xml"];
+
+const FEED_TYPES: &[&str] = &["application/rss+xml", "application/atom+xml"];
+
+/// Fetches a URL through the usual cache and politeness checks; `None` for
+/// errors and non-2xx responses, which just mean "not there" during discovery
+async fn fetch_body(
+    app: &tauri::AppHandle,
+    client: &reqwest::Client,
+    url: &str,
+) -> Option<Vec<u8>> {
+    let response = crate::fetch::fetch_raw(app, client, url, CacheMode::Default)
+        .await
+        .ok()?;
+    (response.status < 300).then_some(response.body)
+}
+
+/// Sitemaps may be served gzipped (`sitemap.xml.gz`) without Content-Encoding
+fn gunzip(body: Vec<u8>) -> Vec<u8> {
+    if !body.starts_with(&[0x1f, 0x8b]) {
+        return body;
+    }
+    let mut out = Vec::new();
+    match flate2::read::GzDecoder::new(body.as_slice())
+        .take(MAX_SITEMAP_BYTES)
+        .read_to_end(&mut out)
+    {
+        Ok(_) => out,
+        Err(_) => body,
+    }
+}
+
+fn xml_text(body: &[u8]) -> String {
+    crate::charset::decode(body, Some("application/xml"), "")
+}
+
+/// Text of the first child element called `name`
+fn child_text(node: roxmltree::Node, name: &str) -> Option<String> {
+    node.children()
+        .find(|c| c.tag_name().name() == name)
+        .and_then(|c| c.text())
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+}
+
+/// Readable title from the last path segment, e.g. `getting-started.html`
+/// becomes `Getting started`
+fn title_from_url(url: &str) -> String {
+    let segment = reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| {
+            u.path_segments()
+                .and_then(|mut s| s.rfind(|s| !s.is_empty()).map(|s| s.to_string()))
+        })
+        .unwrap_or_default();
+    let segment = urlencoding::decode(&segment)
+        .map(|s| s.into_owned())
+        .unwrap_or(segment);
+    let stem = segment
+        .rsplit_once('.')
+        .map_or(segment.as_str(), |(s, _)| s);
+    let words = stem.replace(['-', '_'], " ");
+    let mut chars = words.trim().chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => url.to_string(),
+    }
+}
+
+/// Sitemaps listed in robots.txt, else `/sitemap.xml`
+async fn sitemap_locations(
+    app: &tauri::AppHandle,
+    client: &reqwest::Client,
+    origin: &reqwest::Url,
+) -> Vec<String> {
+    let mut locations: Vec<String> = Vec::new();
+    let Ok(robots) = origin.join("/robots.txt") else {
+        return locations;
+    };
+    if let Some(body) = fetch_body(app, client, robots.as_str()).await {
+        for line in String::from_utf8_lossy(&body).lines() {
+            if let Some((key, value)) = line.split_once(':') {
+                if key.trim().eq_ignore_ascii_case("sitemap") {
+                    locations.push(value.trim().to_string());
+                }
+            }
+        }
+    }
+    if locations.is_empty() {
+        locations.extend(origin.join("/sitemap.xml").map(|u| u.to_string()));
+    }
+    locations
+}
+
+/// Page URLs from a site's sitemaps, in listed order. Sitemap indexes are
+/// followed (up to [`MAX_SITEMAPS`] files) and gzipped sitemaps decompressed.
+pub async fn sitemap_urls(
+    app: &tauri::AppHandle,
+    client: &reqwest::Client,
+    site: &reqwest::Url,
+    limit: usize,
+) -> Vec<String> {
+    let mut pending = sitemap_locations(app, client, site).await;
+    // Popped from the back, so reverse to read them in declared order
+    pending.reverse();
+    let mut visited = HashSet::new();
+    let mut urls = Vec::new();
+
+    while let Some(location) = pending.pop() {
+        if urls.len() >= limit || visited.len() >= MAX_SITEMAPS {
+            break;
+        }
+        if !visited.insert(location.clone()) {
+            continue;
+        }
+        let Some(body) = fetch_body(app, client, &location).await else {
+            continue;
+        };
+        let text = xml_text(&gunzip(body));
+        let Ok(xml) = roxmltree::Document::parse(&text) else {
+            continue;
+        };
+        let root = xml.root_element();
+        let entries = root.children().filter(|c| c.is_element());
+        match root.tag_name().name() {
+            "sitemapindex" => {
+                // Popped from the back, so push in reverse to keep file order
+                let mut children: Vec<String> =
+                    entries.filter_map(|e| child_text(e, "loc")).collect();
+                children.reverse();
+                pending.extend(children);
+            }
+            "urlset" => urls.extend(entries.filter_map(|e| child_text(e, "loc"))),
+            _ => {}
+        }
+    }
+    urls.truncate(limit);
+    urls
+}
+
+/// Feeds declared with `<link rel="alternate" type="application/rss+xml">`
+fn linked_feeds(html: &str, base: &reqwest::Url) -> Vec<String> {
+    let mut feeds = Vec::new();
+    let lower = html.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(found) = lower[from..].find("<link") {
+        let start = from + found;
+        let end = start + lower[start..].find('>').unwrap_or(lower.len() - start);
+        let tag = &html[start + 1..end];
+        from = end;
+        let is_feed = tag_attribute(tag, "rel")
+            .is_some_and(|r| r.eq_ignore_ascii_case("alternate"))
+            && tag_attribute(tag, "type")
+                .is_some_and(|t| FEED_TYPES.contains(&t.to_ascii_lowercase().as_str()));
+        if let Some(href) = tag_attribute(tag, "href").filter(|_| is_feed) {
+            if let Ok(url) = base.join(&href) {
+                feeds.push(url.to_string());
+            }
+        }
+    }
+    feeds
+}
+
+/// Items of an RSS 2.0 / RSS 1.0 (RDF) or Atom feed
+fn feed_items(text: &str) -> Vec<SearXNGResult> {
+    let Ok(xml) = roxmltree::Document::parse(text) else {
+        return Vec::new();
+    };
+    let item = |node: roxmltree::Node, url: Option<String>| {
+        let url = url?;
+        // Descriptions are usually HTML
+        let snippet = ["description", "summary", "content"]
+            .iter()
+            .find_map(|name| child_text(node, name))
+            .map(|text| crate::extract::html_to_markdown(&text))
+            .map(|text| text.chars().take(300).collect::<String>());
+        let published_date = ["pubDate", "published", "updated", "date"]
+            .iter()
+            .find_map(|name| child_text(node, name));
+        Some(SearXNGResult {
+            title: child_text(node, "title").unwrap_or_else(|| title_from_url(&url)),
+            url,
+            snippet,
+            published_date,
+            category: Some("feed".to_string()),
+            ..Default::default()
+        })
+    };
+    xml.descendants()
+        .filter_map(|node| match node.tag_name().name() {
+            "item" => item(node, child_text(node, "link")),
+            "entry" => {
+                let links: Vec<_> = node
+                    .children()
+                    .filter(|c| c.tag_name().name() == "link")
+                    .collect();
+                let link = links
+                    .iter()
+                    .find(|l| l.attribute("rel").is_none_or(|r| r == "alternate"))
+                    .or(links.first())
+                    .and_then(|l| l.attribute("href"))
+                    .map(|h| h.to_string());
+                item(node, link)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Entries of the feeds a site links from its home page, or of a feed at a
+/// common path when it links none
+async fn feed_results(
+    app: &tauri::AppHandle,
+    client: &reqwest::Client,
+    site: &reqwest::Url,
+) -> Vec<SearXNGResult> {
+    let mut feeds = match fetch_body(app, client, site.as_str()).await {
+        Some(body) => linked_feeds(&crate::charset::decode(&body, None, site.as_str()), site),
+        None => Vec::new(),
+    };
+    let guessed = feeds.is_empty();
+    if guessed {
+        feeds = COMMON_FEED_PATHS
+            .iter()
+            .filter_map(|p| site.join(p).ok())
+            .map(|u| u.to_string())
+            .collect();
+    }
+
+    let mut results = Vec::new();
+    for feed in feeds {
+        if let Some(body) = fetch_body(app, client, &feed).await {
+            let items = feed_items(&xml_text(&body));
+            // The first common path that is a real feed is enough
+            let found = !items.is_empty();
+            results.extend(items);
+            if guessed && found {
+                break;
+            }
+        }
+    }
+    results
+}
+
+/// Pages of a site from its sitemaps and feeds, shaped like search results.
+/// Feed entries come first (they carry titles), duplicates are dropped.
+pub async fn discover(
+    app: &tauri::AppHandle,
+    client: &reqwest::Client,
+    site: &str,
+    limit: usize,
+) -> Result<Vec<SearXNGResult>, String> {
+    let mut site = site.trim().to_string();
+    if !site.contains("://") {
+        site = format!("https://{}", site);
+    }
+    let site = reqwest::Url::parse(&site).map_err(|e| format!("Invalid URL: {}", e))?;
+
+    let mut results = feed_results(app, client, &site).await;
+    results.extend(
+        sitemap_urls(app, client, &site, limit)
+            .await
+            .into_iter()
+            .map(|url| SearXNGResult {
+                title: title_from_url(&url),
+                url,
+                category: Some("sitemap".to_string()),
+                ..Default::default()
+            }),
+    );
+
+    let mut seen = HashSet::new();
+    results.retain(|r| seen.insert(crate::urls::normalize_url(&r.url)));
+    results.truncate(limit);
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rss_items_become_results() {
+        let rss = r#"<?xml version="1.0"?>
+            <rss version="2.0"><channel>
+              <title>Blog</title>
+              <item>
+                <title>Release 2.0</title>
+                <link>https://a.example/blog/release-2</link>
+                <description>&lt;p&gt;New &lt;b&gt;features&lt;/b&gt;&lt;/p&gt;</description>
+                <pubDate>Mon, 02 Mar 2026 10:00:00 GMT</pubDate>
+              </item>
+              <item><title>No link</title></item>
+              <item><link>https://a.example/blog/getting-started.html</link></item>
+            </channel></rss>"#;
+        let items = feed_items(rss);
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].title, "Release 2.0");
+        assert_eq!(items[0].url, "https://a.example/blog/release-2");
+        let snippet = items[0].snippet.as_deref().unwrap();
+        assert!(snippet.contains("features") && !snippet.contains("<p>"));
+        assert_eq!(
+            items[0].published_date.as_deref(),
+            Some("Mon, 02 Mar 2026 10:00:00 GMT")
+        );
+        assert_eq!(items[0].category.as_deref(), Some("feed"));
+        assert_eq!(items[1].title, "Getting started");
+    }
+
+    #[test]
+    fn atom_entries_use_their_alternate_link() {
+        let atom = r#"<feed xmlns="http://www.w3.org/2005/Atom">
+              <entry>
+                <title>Changelog</title>
+                <link rel="self" href="https://a.example/feed/1"/>
+                <link rel="alternate" href="https://a.example/changelog"/>
+                <updated>2026-03-02T10:00:00Z</updated>
+                <summary>What changed</summary>
+              </entry>
+              <entry>
+                <title>Plain</title>
+                <link href="https://a.example/plain"/>
+              </entry>
+            </feed>"#;
+        let items = feed_items(atom);
+        let urls: Vec<_> = items.iter().map(|i| i.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            ["https://a.example/changelog", "https://a.example/plain"]
+        );
+        assert_eq!(
+            items[0].published_date.as_deref(),
+            Some("2026-03-02T10:00:00Z")
+        );
+        assert_eq!(items[0].snippet.as_deref(), Some("What changed"));
+    }
+
+    #[test]
+    fn broken_feeds_have_no_items() {
+        assert!(feed_items("<rss><item>").is_empty());
+        assert!(feed_items("not xml").is_empty());
+    }
+}
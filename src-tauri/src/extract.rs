@@ -0,0 +1,544 @@
+use serde::Serialize;
+
+use crate::openapi::ApiSpec;
+
+/// What a fetched source turned out to be
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentKind {
+    Html,
+    Pdf,
+    Markdown,
+    Text,
+    Json,
+    Yaml,
+    /// A JSON/YAML OpenAPI or Swagger document, rendered as a reference
+    OpenApi,
+}
+
+impl DocumentKind {
+    fn label(&self) -> &'static str {
+        match self {
+            DocumentKind::Html => "web page",
+            DocumentKind::Pdf => "PDF document",
+            DocumentKind::Markdown => "Markdown file",
+            DocumentKind::Text => "plain text file",
+            DocumentKind::Json => "JSON document",
+            DocumentKind::Yaml => "YAML document",
+            DocumentKind::OpenApi => "OpenAPI specification",
+        }
+    }
+}
+
+/// Extracted source content as Markdown, whatever the original format.
+/// This is what the summarizer, the diff engine and the agent consume.
+#[derive(Serialize, Clone, Debug)]
+pub struct Document {
+    pub kind: DocumentKind,
+    pub title: Option<String>,
+    pub content: String,
+    /// Parsed JSON/YAML, for extractors that need the structure
+    #[serde(skip)]
+    pub data: Option<serde_json::Value>,
+    /// Set for `OpenApi` documents, so the spec is parsed once
+    #[serde(skip)]
+    pub api_spec: Option<ApiSpec>,
+}
+
+impl Document {
+    /// Short description used in prompts, e.g. "PDF document"
+    pub fn describe(&self) -> &'static str {
+        self.kind.label()
+    }
+}
+
+/// Picks an extractor from the Content-Type, the URL's extension and the
+/// first bytes of the body, then converts the body into a [`Document`].
+pub fn extract_document(
+    body: &[u8],
+    content_type: Option<&str>,
+    url: &str,
+) -> Result<Document, String> {
+    let kind = detect_kind(body, content_type, url)?;
+    let text = || crate::charset::decode(body, content_type, url);
+    let document = match kind {
+        DocumentKind::Pdf => extract_pdf(body)?,
+        DocumentKind::Html => {
+            let html = text();
+            Document {
+                kind,
+                title: html_title(&html),
+                content: html_to_markdown(&html),
+                data: None,
+                api_spec: None,
+            }
+        }
+        DocumentKind::Markdown | DocumentKind::Text => {
+            let text = text();
+            Document {
+                kind,
+                title: markdown_title(&text),
+                content: text.trim().to_string(),
+                data: None,
+                api_spec: None,
+            }
+        }
+        // Never detected directly; specs are recognized after parsing below
+        DocumentKind::Json | DocumentKind::OpenApi => {
+            let value: serde_json::Value =
+                serde_json::from_str(&text()).map_err(|e| format!("Invalid JSON: {}", e))?;
+            let pretty = serde_json::to_string_pretty(&value).map_err(|e| e.to_string())?;
+            Document {
+                kind: DocumentKind::Json,
+                title: structured_title(&value),
+                content: format!("```json\n{}\n```", pretty),
+                data: Some(value),
+                api_spec: None,
+            }
+        }
+        DocumentKind::Yaml => {
+            let value: serde_yaml::Value =
+                serde_yaml::from_str(&text()).map_err(|e| format!("Invalid YAML: {}", e))?;
+            let pretty = serde_yaml::to_string(&value).map_err(|e| e.to_string())?;
+            let data = serde_json::to_value(&value).ok();
+            Document {
+                kind,
+                title: data.as_ref().and_then(structured_title),
+                content: format!("```yaml\n{}\n```", pretty.trim_end()),
+                data,
+                api_spec: None,
+            }
+        }
+    };
+
+    // API specs get a structured reference instead of a raw dump
+    if let Some(spec) = document.data.as_ref().and_then(ApiSpec::parse) {
+        return Ok(Document {
+            kind: DocumentKind::OpenApi,
+            title: Some(spec.title.clone()).filter(|t| !t.is_empty()),
+            content: spec.to_markdown(),
+            data: document.data,
+            api_spec: Some(spec),
+        });
+    }
+    Ok(document)
+}
+
+/// [`extract_document`] for async callers. PDF parsing is CPU-bound and
+/// can take seconds, so it runs on the blocking pool instead of a runtime
+/// worker.
+pub async fn extract_document_async(
+    body: &[u8],
+    content_type: Option<&str>,
+    url: &str,
+) -> Result<Document, String> {
+    if detect_kind(body, content_type, url)? != DocumentKind::Pdf {
+        return extract_document(body, content_type, url);
+    }
+    let body = body.to_vec();
+    tokio::task::spawn_blocking(move || extract_pdf(&body))
+        .await
+        .map_err(|e| format!("Failed to read PDF: {}", e))?
+}
+
+fn detect_kind(body: &[u8], content_type: Option<&str>, url: &str) -> Result<DocumentKind, String> {
+    // Magic bytes beat whatever the server claims
+    if body.starts_with(b"%PDF-") {
+        return Ok(DocumentKind::Pdf);
+    }
+
+    let mime = content_type
+        .and_then(|ct| ct.split(';').next())
+        .map(|m| m.trim().to_ascii_lowercase())
+        .unwrap_or_default();
+    let path = reqwest::Url::parse(url)
+        .map(|u| u.path().to_ascii_lowercase())
+        .unwrap_or_default();
+    let extension = path.rsplit_once('.').map(|(_, ext)| ext).unwrap_or("");
+
+    // Raw file hosts serve everything as text/plain or octet-stream, so a
+    // known extension wins over those generic types
+    let generic = mime.is_empty()
+        || mime == "text/plain"
+        || mime == "application/octet-stream"
+        || mime == "binary/octet-stream";
+    if generic {
+        match extension {
+            "md" | "markdown" | "mdx" => return Ok(DocumentKind::Markdown),
+            "json" => return Ok(DocumentKind::Json),
+            "yaml" | "yml" => return Ok(DocumentKind::Yaml),
+            "pdf" => return Ok(DocumentKind::Pdf),
+            "html" | "htm" => return Ok(DocumentKind::Html),
+            _ => {}
+        }
+    }
+
+    let kind = match mime.as_str() {
+        "text/html" | "application/xhtml+xml" => DocumentKind::Html,
+        "application/pdf" => DocumentKind::Pdf,
+        "text/markdown" | "text/x-markdown" => DocumentKind::Markdown,
+        "application/json" | "text/json" => DocumentKind::Json,
+        "application/yaml" | "application/x-yaml" | "text/yaml" | "text/x-yaml" => {
+            DocumentKind::Yaml
+        }
+        m if m.ends_with("+json") => DocumentKind::Json,
+        m if m.ends_with("+yaml") => DocumentKind::Yaml,
+        "text/plain" => sniff_text(body),
+        m if m.starts_with("text/") => DocumentKind::Text,
+        "" | "application/octet-stream" | "binary/octet-stream" => {
+            if std::str::from_utf8(&body[..body.len().min(1024)]).is_err()
+                && body[..body.len().min(1024)].contains(&0)
+            {
+                return Err("Unsupported content: binary data".to_string());
+            }
+            sniff_text(body)
+        }
+        other => return Err(format!("Unsupported content type: {}", other)),
+    };
+    Ok(kind)
+}
+
+/// Distinguishes HTML and JSON served without a useful Content-Type
+fn sniff_text(body: &[u8]) -> DocumentKind {
+    let start = String::from_utf8_lossy(&body[..body.len().min(512)])
+        .trim_start()
+        .to_ascii_lowercase();
+    if start.starts_with("<!doctype html") || start.starts_with("<html") {
+        DocumentKind::Html
+    } else if (start.starts_with('{') || start.starts_with('['))
+        && serde_json::from_slice::<serde_json::Value>(body).is_ok()
+    {
+        DocumentKind::Json
+    } else {
+        DocumentKind::Text
+    }
+}
+
+fn extract_pdf(body: &[u8]) -> Result<Document, String> {
+    // pdf-extract panics on some malformed files
+    let text = std::panic::catch_unwind(|| pdf_extract::extract_text_from_mem(body))
+        .map_err(|_| "Failed to read PDF: malformed document".to_string())?
+        .map_err(|e| format!("Failed to read PDF: {}", e))?;
+    let content = tidy(&text);
+    if content.is_empty() {
+        return Err("PDF contains no extractable text (scanned document?)".to_string());
+    }
+    Ok(Document {
+        kind: DocumentKind::Pdf,
+        title: content
+            .lines()
+            .next()
+            .map(|l| l.chars().take(120).collect()),
+        content,
+        data: None,
+        api_spec: None,
+    })
+}
+
+fn html_title(html: &str) -> Option<String> {
+    let lower = html.to_ascii_lowercase();
+    let start = lower.find("<title")?;
+    let open_end = start + lower[start..].find('>')? + 1;
+    let close = open_end + lower[open_end..].find("</title")?;
+    let title = decode_entities(html[open_end..close].trim());
+    (!title.is_empty()).then_some(title.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+fn markdown_title(text: &str) -> Option<String> {
+    text.lines()
+        .find_map(|l| l.strip_prefix("# "))
+        .map(|t| t.trim().to_string())
+}
+
+/// `title` / `info.title` of JSON or YAML documents such as API specs
+fn structured_title(value: &serde_json::Value) -> Option<String> {
+    value["info"]["title"]
+        .as_str()
+        .or(value["title"].as_str())
+        .map(|t| t.to_string())
+}
+
+/// Tags whose content is never page text
+const SKIPPED_TAGS: &[&str] = &[
+    "script", "style", "noscript", "svg", "head", "template", "iframe",
+];
+
+/// Tags that start a new line in the output
+const BLOCK_TAGS: &[&str] = &[
+    "p",
+    "div",
+    "section",
+    "article",
+    "main",
+    "header",
+    "footer",
+    "aside",
+    "nav",
+    "br",
+    "hr",
+    "ul",
+    "ol",
+    "table",
+    "tr",
+    "blockquote",
+    "dl",
+    "dt",
+    "dd",
+    "figure",
+    "figcaption",
+    "details",
+    "summary",
+];
+
+/// Simple HTML to Markdown converter. Keeps the structure the summarizer and
+/// the diff engine care about: headings, list items, paragraphs and code
+/// blocks. Everything else is flattened to text.
+pub fn html_to_markdown(html: &str) -> String {
+    let mut out = String::with_capacity(html.len() / 3);
+    let mut skip_depth: Vec<String> = Vec::new();
+    let mut in_pre = false;
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        let Some(lt) = rest.find('<') else {
+            push_text(&mut out, rest, in_pre, &skip_depth);
+            break;
+        };
+        push_text(&mut out, &rest[..lt], in_pre, &skip_depth);
+        rest = &rest[lt..];
+
+        // Comments may contain '>'
+        if rest.starts_with("<!--") {
+            rest = rest.find("-->").map(|i| &rest[i + 3..]).unwrap_or("");
+            continue;
+        }
+        let Some(gt) = rest.find('>') else {
+            break;
+        };
+        let tag = &rest[1..gt];
+        rest = &rest[gt + 1..];
+
+        let closing = tag.starts_with('/');
+        let name: String = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        if name.is_empty() {
+            continue;
+        }
+
+        if SKIPPED_TAGS.contains(&name.as_str()) {
+            if closing {
+                if let Some(pos) = skip_depth.iter().rposition(|t| *t == name) {
+                    skip_depth.truncate(pos);
+                }
+            } else if !tag.ends_with('/') {
+                skip_depth.push(name);
+            }
+            continue;
+        }
+        if !skip_depth.is_empty() {
+            continue;
+        }
+
+        match name.as_str() {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                out.push('\n');
+                if !closing {
+                    let level = name[1..].parse::<usize>().unwrap_or(1);
+                    out.push_str(&"#".repeat(level));
+                    out.push(' ');
+                }
+            }
+            "pre" => {
+                in_pre = !closing;
+                out.push_str("\n```\n");
+            }
+            "li" if !closing => out.push_str("\n- "),
+            "td" | "th" if !closing => out.push_str(" | "),
+            _ if BLOCK_TAGS.contains(&name.as_str()) => out.push('\n'),
+            _ => {}
+        }
+    }
+
+    tidy(&out)
+}
+
+fn push_text(out: &mut String, text: &str, in_pre: bool, skip_depth: &[String]) {
+    if !skip_depth.is_empty() || text.is_empty() {
+        return;
+    }
+    let decoded = decode_entities(text);
+    if in_pre {
+        out.push_str(&decoded);
+    } else {
+        // Inline whitespace collapses to single spaces; newlines come from tags
+        let mut last_space = out.ends_with([' ', '\n']);
+        for c in decoded.chars() {
+            if c.is_whitespace() {
+                if !last_space {
+                    out.push(' ');
+                    last_space = true;
+                }
+            } else {
+                out.push(c);
+                last_space = false;
+            }
+        }
+    }
+}
+
+/// Trims lines and collapses blank runs, leaving code blocks untouched
+fn tidy(text: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    let mut in_code = false;
+    for line in text.lines() {
+        if line.trim() == "```" {
+            in_code = !in_code;
+            lines.push("```");
+            continue;
+        }
+        if in_code {
+            lines.push(line.trim_end());
+            continue;
+        }
+        let trimmed = line.trim();
+        // Drop empty list markers and headings left behind by icon-only markup
+        if trimmed.is_empty() || trimmed == "-" || trimmed.trim_start_matches('#').is_empty() {
+            if lines.last().is_some_and(|l| !l.is_empty()) {
+                lines.push("");
+            }
+            continue;
+        }
+        lines.push(trimmed);
+    }
+    lines.join("\n").trim().to_string()
+}
+
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.bytes().take(12).position(|b| b == b';') else {
+            out.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+        let entity = &rest[1..semi];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => entity
+                .strip_prefix("#x")
+                .or(entity.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|n| n.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kind_comes_from_magic_bytes_then_extension_then_mime() {
+        let kind = |body: &[u8], ct: Option<&str>, url: &str| detect_kind(body, ct, url).unwrap();
+        assert_eq!(
+            kind(b"%PDF-1.7", Some("text/html"), "https://a.dev/x"),
+            DocumentKind::Pdf
+        );
+        assert_eq!(
+            kind(b"# Hi", Some("text/plain"), "https://raw.host/README.md"),
+            DocumentKind::Markdown
+        );
+        assert_eq!(
+            kind(b"a: 1", None, "https://a.dev/openapi.yaml"),
+            DocumentKind::Yaml
+        );
+        // A specific type wins over the extension
+        assert_eq!(
+            kind(
+                b"<p>",
+                Some("text/html; charset=utf-8"),
+                "https://a.dev/x.json"
+            ),
+            DocumentKind::Html
+        );
+        assert_eq!(
+            kind(
+                b"{\"a\": 1}",
+                Some("application/vnd.api+json"),
+                "https://a.dev/x"
+            ),
+            DocumentKind::Json
+        );
+        assert_eq!(
+            kind(
+                b"<!DOCTYPE html><html>",
+                Some("text/plain"),
+                "https://a.dev/x"
+            ),
+            DocumentKind::Html
+        );
+        assert_eq!(kind(b"[1, 2]", None, "https://a.dev/x"), DocumentKind::Json);
+        assert_eq!(
+            kind(b"plain words", Some("text/css"), "https://a.dev/x"),
+            DocumentKind::Text
+        );
+    }
+
+    #[test]
+    fn binary_and_unknown_types_are_rejected() {
+        assert!(detect_kind(b"\x00\x01\xff\xfe", None, "https://a.dev/x").is_err());
+        assert!(detect_kind(b"GIF89a", Some("image/gif"), "https://a.dev/x.gif").is_err());
+    }
+
+    #[test]
+    fn html_keeps_headings_lists_and_code() {
+        let html = "<html><head><title>T</title><style>p{}</style></head><body>\
+            <h2>Install</h2><p>Run   the\n tool &amp; wait.</p>\
+            <ul><li>one</li><li>two</li></ul>\
+            <pre>let x = 1;\n    y</pre>\
+            <!-- a > b --><script>alert(1)</script></body></html>";
+        assert_eq!(
+            html_to_markdown(html),
+            "## Install\n\nRun the tool & wait.\n\n- one\n- two\n\n```\nlet x = 1;\n    y\n```"
+        );
+    }
+
+    #[test]
+    fn entities_are_decoded() {
+        assert_eq!(
+            decode_entities("a &lt;b&gt; &quot;c&quot; &#39;d&#x27; &#X41;&nbsp;e"),
+            "a <b> \"c\" 'd' A e"
+        );
+        // Unknown or unterminated entities stay as written
+        assert_eq!(decode_entities("&bogus; & &amp"), "&bogus; & &amp");
+        assert_eq!(decode_entities("&#xZZ; &#1114112;"), "&#xZZ; &#1114112;");
+    }
+}
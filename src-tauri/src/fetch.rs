@@ -0,0 +1,114 @@
+use tauri::Manager;
+
+use crate::http_cache::HttpCache;
+use crate::politeness::Politeness;
+use crate::profiles::HeaderProfiles;
+
+/// A response as received (or replayed from the cache)
+#[derive(Clone)]
+pub struct RawResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl RawResponse {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// How `fetch_raw` may use a cached response
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CacheMode {
+    /// Fresh entries are served without asking the server
+    Default,
+    /// Always ask the server, conditionally when an entry exists; for
+    /// checks whose whole point is to see the current page
+    Revalidate,
+}
+
+fn header_pairs(headers: &reqwest::header::HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter_map(|(k, v)| Some((k.as_str().to_string(), v.to_str().ok()?.to_string())))
+        .collect()
+}
+
+/// GETs a URL through the HTTP cache: fresh entries are served directly
+/// (unless `mode` is `Revalidate`), stale ones are revalidated with
+/// If-None-Match / If-Modified-Since. Network requests go through the
+/// robots.txt and per-host rate checks.
+pub async fn fetch_raw(
+    app: &tauri::AppHandle,
+    client: &reqwest::Client,
+    url: &str,
+    mode: CacheMode,
+) -> Result<RawResponse, String> {
+    let cache = app.state::<HttpCache>();
+    let now = chrono::Utc::now().timestamp();
+    let cached = cache.lookup(url);
+
+    if let Some((entry, body)) = &cached {
+        if mode == CacheMode::Default && entry.is_fresh(now) {
+            return Ok(RawResponse {
+                status: 200,
+                headers: entry.headers.clone(),
+                body: body.clone(),
+            });
+        }
+    }
+
+    // Held until the body is read so per-host limits cover the whole transfer
+    let profile = app.state::<HeaderProfiles>().profile_for(url);
+    let _permit = app
+        .state::<Politeness>()
+        .acquire(client, url, &profile)
+        .await?;
+
+    let mut request = profile.apply(client.get(url));
+    if let Some((entry, _)) = &cached {
+        if let Some(etag) = &entry.etag {
+            request = request.header("If-None-Match", etag);
+        }
+        if let Some(modified) = &entry.last_modified {
+            request = request.header("If-Modified-Since", modified);
+        }
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch URL: {}", e))?;
+    let status = response.status().as_u16();
+    let headers = header_pairs(response.headers());
+
+    if status == 304 {
+        if let Some((entry, body)) = cached {
+            cache.revalidated(url, &headers);
+            return Ok(RawResponse {
+                status: 200,
+                headers: entry.headers,
+                body,
+            });
+        }
+    }
+
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to read response: {}", e))?
+        .to_vec();
+    if status == 200 {
+        cache.store(url, headers.clone(), &body);
+    }
+
+    Ok(RawResponse {
+        status,
+        headers,
+        body,
+    })
+}
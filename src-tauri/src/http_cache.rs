@@ -0,0 +1,336 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::refresh::content_hash;
+
+/// Default upper bound for stored bodies before least-recently-used entries go
+const DEFAULT_MAX_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Metadata of one cached response, stored as `<key>.json` next to the
+/// `<key>.body` it describes
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CacheEntry {
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// Unix seconds the response was stored or last revalidated
+    pub stored_at: i64,
+    /// Unix seconds until which the entry may be served without revalidation
+    pub fresh_until: Option<i64>,
+    pub size: u64,
+    pub last_access: i64,
+}
+
+impl CacheEntry {
+    pub fn is_fresh(&self, now: i64) -> bool {
+        self.fresh_until.is_some_and(|t| now < t)
+    }
+}
+
+/// Cache-Control directives we act on
+#[derive(Default)]
+pub struct CachePolicy {
+    pub no_store: bool,
+    pub no_cache: bool,
+    pub max_age: Option<i64>,
+}
+
+impl CachePolicy {
+    pub fn from_headers(headers: &[(String, String)], now: i64) -> Self {
+        let mut policy = CachePolicy::default();
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.as_str())
+        };
+
+        if let Some(cc) = header("cache-control") {
+            for directive in cc.split(',').map(|d| d.trim().to_ascii_lowercase()) {
+                let (name, value) = directive
+                    .split_once('=')
+                    .map(|(n, v)| (n.trim().to_string(), Some(v.trim().trim_matches('"'))))
+                    .unwrap_or((directive.clone(), None));
+                match name.as_str() {
+                    // `private` only keeps shared caches out; this one has a
+                    // single user
+                    "no-store" => policy.no_store = true,
+                    "no-cache" | "must-revalidate" => policy.no_cache = true,
+                    // s-maxage is for shared caches, but it is the better hint
+                    // when a server only sends that
+                    "max-age" | "s-maxage" => {
+                        if let Some(secs) = value.and_then(|v| v.parse::<i64>().ok()) {
+                            policy.max_age = Some(policy.max_age.map_or(secs, |m| m.min(secs)));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        if policy.max_age.is_none() {
+            policy.max_age = header("expires")
+                .and_then(|v| chrono::DateTime::parse_from_rfc2822(v).ok())
+                .map(|d| (d.timestamp() - now).max(0));
+        }
+        policy
+    }
+}
+
+/// The single `index.json` older versions kept every entry in
+#[derive(Serialize, Deserialize, Default)]
+struct CacheIndex {
+    entries: HashMap<String, CacheEntry>,
+}
+
+/// On-disk HTTP response cache keyed by normalized URL, bounded by total
+/// body size with least-recently-used eviction. Each entry keeps its own
+/// metadata file, so a store or lookup only rewrites that one.
+pub struct HttpCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    index: Mutex<CacheIndex>,
+}
+
+impl HttpCache {
+    pub fn open(dir: PathBuf) -> Result<Self, String> {
+        std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create cache dir: {}", e))?;
+        let mut index = CacheIndex::default();
+        let files =
+            std::fs::read_dir(&dir).map_err(|e| format!("Failed to read cache dir: {}", e))?;
+        for path in files.filter_map(|f| f.ok()).map(|f| f.path()) {
+            let key = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or_default();
+            if key == "index" || path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let entry = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|text| serde_json::from_str(&text).ok());
+            if let Some(entry) = entry {
+                index.entries.insert(key.to_string(), entry);
+            }
+        }
+
+        let cache = Self {
+            dir,
+            max_bytes: DEFAULT_MAX_BYTES,
+            index: Mutex::new(index),
+        };
+        cache.migrate_index();
+        Ok(cache)
+    }
+
+    /// Splits an `index.json` left by older versions into entry files
+    fn migrate_index(&self) {
+        let legacy = self.dir.join("index.json");
+        let Ok(text) = std::fs::read_to_string(&legacy) else {
+            return;
+        };
+        let old: CacheIndex = serde_json::from_str(&text).unwrap_or_default();
+        let mut index = self.index.lock().unwrap();
+        for (key, entry) in old.entries {
+            if let std::collections::hash_map::Entry::Vacant(slot) = index.entries.entry(key) {
+                self.write_meta(slot.key(), &entry);
+                slot.insert(entry);
+            }
+        }
+        let _ = std::fs::remove_file(legacy);
+    }
+
+    /// Normalized URL plus its scheme: http and https may serve different
+    /// content, even where dedupe treats them as one page
+    fn key(url: &str) -> String {
+        let scheme = reqwest::Url::parse(url.trim())
+            .map(|u| u.scheme().to_string())
+            .unwrap_or_default();
+        content_hash(&format!("{}://{}", scheme, crate::urls::normalize_url(url)))
+    }
+
+    fn body_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.body", key))
+    }
+
+    fn meta_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+
+    fn write_meta(&self, key: &str, entry: &CacheEntry) {
+        if let Ok(json) = serde_json::to_string(entry) {
+            let _ = std::fs::write(self.meta_path(key), json);
+        }
+    }
+
+    fn remove_files(&self, key: &str) {
+        let _ = std::fs::remove_file(self.body_path(key));
+        let _ = std::fs::remove_file(self.meta_path(key));
+    }
+
+    /// Cached entry and body for a URL, marking it as recently used
+    pub fn lookup(&self, url: &str) -> Option<(CacheEntry, Vec<u8>)> {
+        let key = Self::key(url);
+        let mut index = self.index.lock().unwrap();
+        let entry = index.entries.get_mut(&key)?;
+        match std::fs::read(self.body_path(&key)) {
+            Ok(body) => {
+                entry.last_access = chrono::Utc::now().timestamp();
+                let entry = entry.clone();
+                self.write_meta(&key, &entry);
+                Some((entry, body))
+            }
+            Err(_) => {
+                // Body vanished from disk; forget the entry
+                index.entries.remove(&key);
+                self.remove_files(&key);
+                None
+            }
+        }
+    }
+
+    /// Stores a 200 response unless its Cache-Control forbids it
+    pub fn store(&self, url: &str, headers: Vec<(String, String)>, body: &[u8]) {
+        let now = chrono::Utc::now().timestamp();
+        let policy = CachePolicy::from_headers(&headers, now);
+        if policy.no_store {
+            return;
+        }
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.clone())
+        };
+        let entry = CacheEntry {
+            url: url.to_string(),
+            etag: header("etag"),
+            last_modified: header("last-modified"),
+            stored_at: now,
+            fresh_until: if policy.no_cache {
+                None
+            } else {
+                policy.max_age.map(|age| now + age)
+            },
+            size: body.len() as u64,
+            last_access: now,
+            headers,
+        };
+
+        let key = Self::key(url);
+        if std::fs::write(self.body_path(&key), body).is_err() {
+            return;
+        }
+        self.write_meta(&key, &entry);
+        let mut index = self.index.lock().unwrap();
+        index.entries.insert(key, entry);
+        self.evict(&mut index);
+    }
+
+    /// Records a 304: the stored body is still valid, headers may be updated
+    pub fn revalidated(&self, url: &str, headers: &[(String, String)]) {
+        let now = chrono::Utc::now().timestamp();
+        let key = Self::key(url);
+        let mut index = self.index.lock().unwrap();
+        let Some(entry) = index.entries.get_mut(&key) else {
+            return;
+        };
+        for (name, value) in headers {
+            match entry
+                .headers
+                .iter_mut()
+                .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            {
+                Some(existing) => existing.1 = value.clone(),
+                None => entry.headers.push((name.clone(), value.clone())),
+            }
+        }
+        let policy = CachePolicy::from_headers(&entry.headers, now);
+        entry.stored_at = now;
+        entry.last_access = now;
+        entry.fresh_until = if policy.no_cache {
+            None
+        } else {
+            policy.max_age.map(|age| now + age)
+        };
+        self.write_meta(&key, entry);
+    }
+
+    fn evict(&self, index: &mut CacheIndex) {
+        let mut total: u64 = index.entries.values().map(|e| e.size).sum();
+        if total <= self.max_bytes {
+            return;
+        }
+        let mut by_age: Vec<(String, i64, u64)> = index
+            .entries
+            .iter()
+            .map(|(k, e)| (k.clone(), e.last_access, e.size))
+            .collect();
+        by_age.sort_by_key(|(_, access, _)| *access);
+        for (key, _, size) in by_age {
+            if total <= self.max_bytes {
+                break;
+            }
+            index.entries.remove(&key);
+            self.remove_files(&key);
+            total -= size;
+        }
+    }
+
+    /// Removes every entry; returns the number of bytes freed
+    pub fn clear(&self) -> Result<u64, String> {
+        let mut index = self.index.lock().unwrap();
+        let freed = index.entries.values().map(|e| e.size).sum();
+        for key in index.entries.keys() {
+            self.remove_files(key);
+        }
+        index.entries.clear();
+        Ok(freed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(cache_control: &str) -> Vec<(String, String)> {
+        vec![("Cache-Control".to_string(), cache_control.to_string())]
+    }
+
+    #[test]
+    fn only_no_store_keeps_responses_out() {
+        let now = 1_000;
+        let private = CachePolicy::from_headers(&headers("private, max-age=60"), now);
+        assert!(!private.no_store);
+        assert_eq!(private.max_age, Some(60));
+        assert!(CachePolicy::from_headers(&headers("no-store"), now).no_store);
+    }
+
+    #[test]
+    fn entries_and_access_times_survive_a_reopen() {
+        let dir = std::env::temp_dir().join(format!("http-cache-{}", uuid::Uuid::new_v4()));
+        let cache = HttpCache::open(dir.clone()).unwrap();
+        cache.store("https://a.example/", headers("private"), b"body");
+        cache
+            .index
+            .lock()
+            .unwrap()
+            .entries
+            .values_mut()
+            .for_each(|e| e.last_access = 0);
+        let (_, body) = cache.lookup("https://a.example/").unwrap();
+        assert_eq!(body, b"body");
+
+        let reopened = HttpCache::open(dir.clone()).unwrap();
+        let index = reopened.index.lock().unwrap();
+        assert_eq!(index.entries.len(), 1);
+        assert!(index.entries.values().all(|e| e.last_access > 0));
+        drop(index);
+        assert!(reopened.lookup("https://b.example/").is_none());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
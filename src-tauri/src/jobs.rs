@@ -0,0 +1,743 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Manager};
+use tokio::sync::Notify;
+
+use crate::batch::{BatchError, BatchLimits, BatchSelection};
+use crate::vault::VaultGraph;
+use crate::WorkflowOptions;
+
+/// Work that can be queued and resumed after a restart
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JobPayload {
+    /// Fetch/summarize/save one or more selections
+    Batch {
+        query: String,
+        selections: Vec<BatchSelection>,
+        options: WorkflowOptions,
+        #[serde(default)]
+        limits: Option<BatchLimits>,
+    },
+    /// A full autonomous agent run
+    Agent {
+        query: String,
+        model: String,
+        #[serde(default)]
+        api_key: String,
+        #[serde(default)]
+        bypass_cache: bool,
+    },
+}
+
+impl JobPayload {
+    fn kind(&self) -> &'static str {
+        match self {
+            JobPayload::Batch { .. } => "batch",
+            JobPayload::Agent { .. } => "agent",
+        }
+    }
+
+    fn api_key(&self) -> &str {
+        match self {
+            JobPayload::Batch { options, .. } => &options.obsidian_api_key,
+            JobPayload::Agent { api_key, .. } => api_key,
+        }
+    }
+
+    fn with_api_key(mut self, key: String) -> Self {
+        match &mut self {
+            JobPayload::Batch { options, .. } => options.obsidian_api_key = key,
+            JobPayload::Agent { api_key, .. } => *api_key = key,
+        }
+        self
+    }
+
+    /// The payload as stored in the database, without the Obsidian API key
+    fn to_stored_json(&self) -> Result<String, String> {
+        serde_json::to_string(&self.clone().with_api_key(String::new())).map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done,
+    Failed,
+    Cancelled,
+    /// Resumed after a restart; waits for the frontend to supply the API key
+    #[serde(rename = "needs_key")]
+    NeedsKey,
+}
+
+impl JobStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Done => "done",
+            JobStatus::Failed => "failed",
+            JobStatus::Cancelled => "cancelled",
+            JobStatus::NeedsKey => "needs_key",
+        }
+    }
+
+    fn parse(s: &str) -> Result<JobStatus, String> {
+        match s {
+            "queued" => Ok(JobStatus::Queued),
+            "running" => Ok(JobStatus::Running),
+            "done" => Ok(JobStatus::Done),
+            "failed" => Ok(JobStatus::Failed),
+            "cancelled" => Ok(JobStatus::Cancelled),
+            "needs_key" => Ok(JobStatus::NeedsKey),
+            _ => Err(format!("Unknown job status: {}", s)),
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct Job {
+    pub id: i64,
+    pub kind: String,
+    pub status: JobStatus,
+    pub attempts: u32,
+    pub max_attempts: u32,
+    pub payload: JobPayload,
+    /// JSON result of the last successful run
+    pub result: Option<serde_json::Value>,
+    pub error: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// SQLite-backed queue in `<app data>/thefetcher.db`. API keys are not
+/// stored; a job runs with the key of the latest `enqueue` or
+/// `set_api_key` in this session. Until one arrives, due jobs are parked
+/// as [`JobStatus::NeedsKey`].
+pub struct JobQueue {
+    conn: Mutex<Connection>,
+    wake: Notify,
+    /// Also held while a job is claimed and started, so `cancel` never
+    /// falls between the two
+    running: Mutex<HashMap<i64, tokio::task::AbortHandle>>,
+    api_key: Mutex<String>,
+}
+
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+/// Wait before the first retry of a failed job; each further retry waits
+/// four times as long
+const RETRY_DELAY_SECS: i64 = 30;
+
+/// Longest the worker sleeps without checking for due jobs
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long a write waits for another connection to `thefetcher.db`
+pub const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+impl JobQueue {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let conn =
+            Connection::open(path).map_err(|e| format!("Failed to open job database: {}", e))?;
+        // The refresh scheduler and LLM cache write to the same file
+        conn.busy_timeout(BUSY_TIMEOUT)
+            .map_err(|e| format!("Failed to open job database: {}", e))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS jobs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                kind TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                status TEXT NOT NULL,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                max_attempts INTEGER NOT NULL,
+                result TEXT,
+                error TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+            -- Jobs interrupted by a shutdown are resumed from the start
+            UPDATE jobs SET status = 'queued' WHERE status = 'running';
+            -- Earlier versions stored API keys with the payload
+            UPDATE jobs SET payload = json_set(payload, '$.api_key', '')
+                WHERE kind = 'agent' AND json_extract(payload, '$.api_key') != '';
+            UPDATE jobs SET payload = json_set(payload, '$.options.obsidianApiKey', '')
+                WHERE kind = 'batch' AND json_extract(payload, '$.options.obsidianApiKey') != '';",
+        )
+        .map_err(|e| format!("Failed to initialise job database: {}", e))?;
+
+        // Unix time before which a failed job is not retried
+        let has_not_before: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('jobs') WHERE name = 'not_before'",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Failed to initialise job database: {}", e))?;
+        if !has_not_before {
+            conn.execute("ALTER TABLE jobs ADD COLUMN not_before INTEGER", [])
+                .map_err(|e| format!("Failed to initialise job database: {}", e))?;
+        }
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+            wake: Notify::new(),
+            running: Mutex::new(HashMap::new()),
+            api_key: Mutex::new(String::new()),
+        })
+    }
+
+    pub fn enqueue(&self, payload: &JobPayload) -> Result<i64, String> {
+        self.set_api_key(payload.api_key())?;
+        let now = chrono::Local::now().to_rfc3339();
+        let json = payload.to_stored_json()?;
+        let id = {
+            let conn = self.conn.lock().unwrap();
+            conn.execute(
+                "INSERT INTO jobs (kind, payload, status, max_attempts, created_at, updated_at)
+                 VALUES (?1, ?2, 'queued', ?3, ?4, ?4)",
+                params![payload.kind(), json, DEFAULT_MAX_ATTEMPTS, now],
+            )
+            .map_err(|e| format!("Failed to queue job: {}", e))?;
+            conn.last_insert_rowid()
+        };
+        self.wake.notify_one();
+        Ok(id)
+    }
+
+    pub fn list(&self, status: Option<JobStatus>) -> Result<Vec<Job>, String> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, kind, status, attempts, max_attempts, payload, result, error, created_at, updated_at
+                 FROM jobs WHERE ?1 IS NULL OR status = ?1 ORDER BY id DESC",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![status.map(|s| s.as_str())], row_to_job)
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read jobs: {}", e))
+    }
+
+    pub fn get(&self, id: i64) -> Result<Option<Job>, String> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT id, kind, status, attempts, max_attempts, payload, result, error, created_at, updated_at
+             FROM jobs WHERE id = ?1",
+            params![id],
+            row_to_job,
+        )
+        .optional()
+        .map_err(|e| e.to_string())
+    }
+
+    /// Puts a failed or cancelled job back in the queue with a fresh attempt budget
+    pub fn retry(&self, id: i64) -> Result<(), String> {
+        let changed = self
+            .conn
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE jobs SET status = 'queued', attempts = 0, error = NULL, not_before = NULL,
+                 updated_at = ?2
+                 WHERE id = ?1 AND status IN ('failed', 'cancelled')",
+                params![id, chrono::Local::now().to_rfc3339()],
+            )
+            .map_err(|e| e.to_string())?;
+        if changed == 0 {
+            return Err(format!("Job {} is not failed or cancelled", id));
+        }
+        self.wake.notify_one();
+        Ok(())
+    }
+
+    /// Cancels a queued job, or aborts a running one
+    pub fn cancel(&self, id: i64) -> Result<(), String> {
+        if let Some(handle) = self.running.lock().unwrap().remove(&id) {
+            handle.abort();
+        }
+        let changed = self
+            .conn
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE jobs SET status = 'cancelled', updated_at = ?2
+                 WHERE id = ?1 AND status IN ('queued', 'running', 'needs_key')",
+                params![id, chrono::Local::now().to_rfc3339()],
+            )
+            .map_err(|e| e.to_string())?;
+        if changed == 0 {
+            return Err(format!("Job {} is not queued or running", id));
+        }
+        Ok(())
+    }
+
+    /// Sets the key jobs run with and releases jobs waiting for one.
+    /// An empty key is ignored.
+    pub fn set_api_key(&self, key: &str) -> Result<(), String> {
+        if key.is_empty() {
+            return Ok(());
+        }
+        // Under the connection lock so `park_for_key` can't park jobs
+        // after they were released
+        let conn = self.conn.lock().unwrap();
+        *self.api_key.lock().unwrap() = key.to_string();
+        let released = conn
+            .execute(
+                "UPDATE jobs SET status = 'queued', updated_at = ?1 WHERE status = 'needs_key'",
+                params![chrono::Local::now().to_rfc3339()],
+            )
+            .map_err(|e| e.to_string())?;
+        drop(conn);
+        if released > 0 {
+            self.wake.notify_one();
+        }
+        Ok(())
+    }
+
+    /// The key jobs run with, see [`JobQueue`]
+    fn api_key(&self) -> Option<String> {
+        let key = self.api_key.lock().unwrap().clone();
+        (!key.is_empty()).then_some(key)
+    }
+
+    /// Moves queued jobs to `needs_key` while no API key is known and
+    /// returns their ids
+    fn park_for_key(&self) -> Result<Vec<i64>, String> {
+        let conn = self.conn.lock().unwrap();
+        if self.api_key().is_some() {
+            return Ok(Vec::new());
+        }
+        let ids = conn
+            .prepare("SELECT id FROM jobs WHERE status = 'queued'")
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| row.get(0))?
+                    .collect::<Result<Vec<i64>, _>>()
+            })
+            .map_err(|e| e.to_string())?;
+        conn.execute(
+            "UPDATE jobs SET status = 'needs_key', updated_at = ?1 WHERE status = 'queued'",
+            params![chrono::Local::now().to_rfc3339()],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(ids)
+    }
+
+    /// Marks the oldest queued job that is due as running and returns it
+    fn claim_next(&self) -> Result<Option<Job>, String> {
+        let conn = self.conn.lock().unwrap();
+        let id: Option<i64> = conn
+            .query_row(
+                "SELECT id FROM jobs
+                 WHERE status = 'queued' AND (not_before IS NULL OR not_before <= ?1)
+                 ORDER BY id LIMIT 1",
+                params![chrono::Utc::now().timestamp()],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        let Some(id) = id else {
+            return Ok(None);
+        };
+        conn.execute(
+            "UPDATE jobs SET status = 'running', attempts = attempts + 1, updated_at = ?2 WHERE id = ?1",
+            params![id, chrono::Local::now().to_rfc3339()],
+        )
+        .map_err(|e| e.to_string())?;
+        drop(conn);
+        self.get(id)
+    }
+
+    /// How long until the next queued job is due, capped at the poll interval
+    fn next_due(&self) -> Duration {
+        let conn = self.conn.lock().unwrap();
+        let earliest: Option<i64> = conn
+            .query_row(
+                "SELECT MIN(COALESCE(not_before, 0)) FROM jobs WHERE status = 'queued'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap_or(None);
+        match earliest {
+            Some(at) => {
+                let wait = (at - chrono::Utc::now().timestamp()).max(1) as u64;
+                Duration::from_secs(wait).min(POLL_INTERVAL)
+            }
+            None => POLL_INTERVAL,
+        }
+    }
+
+    fn finish(
+        &self,
+        job: &Job,
+        outcome: Result<serde_json::Value, String>,
+        payload: Option<&JobPayload>,
+    ) -> Result<JobStatus, String> {
+        let now = chrono::Local::now().to_rfc3339();
+        let conn = self.conn.lock().unwrap();
+
+        // Cancelled while running: keep it cancelled
+        let current: String = conn
+            .query_row(
+                "SELECT status FROM jobs WHERE id = ?1",
+                params![job.id],
+                |r| r.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if current == "cancelled" {
+            return Ok(JobStatus::Cancelled);
+        }
+
+        if let Some(payload) = payload {
+            let json = payload.to_stored_json()?;
+            conn.execute(
+                "UPDATE jobs SET payload = ?2 WHERE id = ?1",
+                params![job.id, json],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+
+        let status = match outcome {
+            Ok(value) => {
+                conn.execute(
+                    "UPDATE jobs SET status = 'done', result = ?2, error = NULL, updated_at = ?3 WHERE id = ?1",
+                    params![job.id, value.to_string(), now],
+                )
+                .map_err(|e| e.to_string())?;
+                JobStatus::Done
+            }
+            Err(error) => {
+                let status = if job.attempts < job.max_attempts {
+                    JobStatus::Queued
+                } else {
+                    JobStatus::Failed
+                };
+                let delay = RETRY_DELAY_SECS * 4i64.pow(job.attempts.saturating_sub(1).min(8));
+                conn.execute(
+                    "UPDATE jobs SET status = ?2, error = ?3, updated_at = ?4, not_before = ?5
+                     WHERE id = ?1",
+                    params![
+                        job.id,
+                        status.as_str(),
+                        error,
+                        now,
+                        chrono::Utc::now().timestamp() + delay
+                    ],
+                )
+                .map_err(|e| e.to_string())?;
+                status
+            }
+        };
+        Ok(status)
+    }
+}
+
+fn row_to_job(row: &rusqlite::Row) -> rusqlite::Result<Job> {
+    let payload: String = row.get(5)?;
+    let result: Option<String> = row.get(6)?;
+    let status: String = row.get(2)?;
+    Ok(Job {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        status: JobStatus::parse(&status).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, e.into())
+        })?,
+        attempts: row.get(3)?,
+        max_attempts: row.get(4)?,
+        payload: serde_json::from_str(&payload).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(5, rusqlite::types::Type::Text, Box::new(e))
+        })?,
+        result: result.and_then(|r| serde_json::from_str(&r).ok()),
+        error: row.get(7)?,
+        created_at: row.get(8)?,
+        updated_at: row.get(9)?,
+    })
+}
+
+/// Emitted as `job_update` whenever a job changes state
+#[derive(Serialize, Clone)]
+struct JobUpdate {
+    id: i64,
+    status: JobStatus,
+    error: Option<String>,
+}
+
+fn log_error(app: &tauri::AppHandle, message: String) {
+    let _ = app.emit(
+        "workflow_log",
+        crate::WorkflowLog {
+            step: "error".to_string(),
+            status: "error".to_string(),
+            message,
+        },
+    );
+}
+
+/// Background worker: runs queued jobs one at a time, forever
+pub async fn run_worker(app: tauri::AppHandle) {
+    loop {
+        let queue = app.state::<JobQueue>();
+        let Some(api_key) = queue.api_key() else {
+            match queue.park_for_key() {
+                Ok(ids) => {
+                    for id in ids {
+                        let _ = app.emit(
+                            "job_update",
+                            JobUpdate {
+                                id,
+                                status: JobStatus::NeedsKey,
+                                error: None,
+                            },
+                        );
+                    }
+                }
+                Err(e) => log_error(&app, format!("Job queue error: {}", e)),
+            }
+            // Woken by `set_api_key` or a new job
+            queue.wake.notified().await;
+            continue;
+        };
+        let claimed = {
+            let mut running = queue.running.lock().unwrap();
+            queue.claim_next().map(|job| {
+                job.map(|job| {
+                    let payload = job.payload.clone().with_api_key(api_key.clone());
+                    let task = tokio::spawn(execute(app.clone(), payload));
+                    running.insert(job.id, task.abort_handle());
+                    (job, task)
+                })
+            })
+        };
+        let (job, task) = match claimed {
+            Ok(Some(claimed)) => claimed,
+            Ok(None) => {
+                // Also wakes for retries coming due, and in case a wake-up was missed
+                let _ = tokio::time::timeout(queue.next_due(), queue.wake.notified()).await;
+                continue;
+            }
+            Err(e) => {
+                log_error(&app, format!("Job queue error: {}", e));
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+        };
+        let _ = app.emit(
+            "job_update",
+            JobUpdate {
+                id: job.id,
+                status: JobStatus::Running,
+                error: None,
+            },
+        );
+
+        let joined = task.await;
+        queue.running.lock().unwrap().remove(&job.id);
+
+        let (outcome, remaining) = match joined {
+            Ok(result) => result,
+            Err(_) => (Err("Job was cancelled".to_string()), None),
+        };
+        let error = outcome.as_ref().err().cloned();
+        match queue.finish(&job, outcome, remaining.as_ref()) {
+            Ok(status) => {
+                let _ = app.emit(
+                    "job_update",
+                    JobUpdate {
+                        id: job.id,
+                        status,
+                        error,
+                    },
+                );
+            }
+            Err(e) => log_error(&app, format!("Failed to record job {}: {}", job.id, e)),
+        }
+    }
+}
+
+/// Runs one payload with the same functions the interactive commands use.
+/// For batches, also returns the payload narrowed to the items that still
+/// need work, so a retry does not redo what already succeeded.
+async fn execute(
+    app: tauri::AppHandle,
+    payload: JobPayload,
+) -> (Result<serde_json::Value, String>, Option<JobPayload>) {
+    match payload {
+        JobPayload::Batch {
+            query,
+            selections,
+            options,
+            limits,
+        } => {
+            let summary = match crate::batch::run_batch(
+                app,
+                query.clone(),
+                selections.clone(),
+                options.clone(),
+                limits.unwrap_or_default(),
+            )
+            .await
+            {
+                Ok(summary) => summary,
+                Err(e) => return (Err(e), None),
+            };
+
+            // Already-saved pages are not worth retrying
+            let pending: Vec<BatchSelection> = summary
+                .items
+                .iter()
+                .filter(
+                    |i| matches!(i.error, Some(ref e) if !matches!(e, BatchError::Duplicate(_))),
+                )
+                .filter_map(|i| selections.get(i.index).cloned())
+                .collect();
+            let value = serde_json::to_value(&summary).unwrap_or_default();
+            if pending.is_empty() {
+                (Ok(value), None)
+            } else {
+                let error = format!("{} of {} items failed", pending.len(), summary.total);
+                (
+                    Err(error),
+                    Some(JobPayload::Batch {
+                        query,
+                        selections: pending,
+                        options,
+                        limits,
+                    }),
+                )
+            }
+        }
+        JobPayload::Agent {
+            query,
+            model,
+            api_key,
+            bypass_cache,
+        } => {
+            let Some(window) = app.get_webview_window("main") else {
+                return (Err("No main window".to_string()), None);
+            };
+            let graph = app.state::<RwLock<VaultGraph>>();
+            let outcome =
+                crate::agent_loop(&window, &graph, &query, &model, &api_key, bypass_cache)
+                    .await
+                    .map(|r| serde_json::to_value(r).unwrap_or_default());
+            (outcome, None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agent(api_key: &str) -> JobPayload {
+        JobPayload::Agent {
+            query: "q".to_string(),
+            model: "m".to_string(),
+            api_key: api_key.to_string(),
+            bypass_cache: false,
+        }
+    }
+
+    fn stored_payload(queue: &JobQueue, id: i64) -> String {
+        queue
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT payload FROM jobs WHERE id = ?1", params![id], |r| {
+                r.get(0)
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn status_parse_rejects_unknown_values() {
+        for status in [
+            JobStatus::Queued,
+            JobStatus::Running,
+            JobStatus::Done,
+            JobStatus::Failed,
+            JobStatus::Cancelled,
+            JobStatus::NeedsKey,
+        ] {
+            assert_eq!(JobStatus::parse(status.as_str()), Ok(status));
+        }
+        assert!(JobStatus::parse("paused").is_err());
+        assert!(JobStatus::parse("").is_err());
+    }
+
+    #[test]
+    fn api_keys_are_not_stored() {
+        let queue = JobQueue::open(Path::new(":memory:")).unwrap();
+        let id = queue.enqueue(&agent("secret")).unwrap();
+        assert!(!stored_payload(&queue, id).contains("secret"));
+        assert_eq!(*queue.api_key.lock().unwrap(), "secret");
+    }
+
+    #[test]
+    fn jobs_wait_for_a_key_after_a_restart() {
+        let queue = JobQueue::open(Path::new(":memory:")).unwrap();
+        let id = queue.enqueue(&agent("")).unwrap();
+        assert_eq!(queue.park_for_key().unwrap(), vec![id]);
+        assert_eq!(queue.get(id).unwrap().unwrap().status, JobStatus::NeedsKey);
+        assert!(queue.claim_next().unwrap().is_none());
+
+        queue.set_api_key("").unwrap();
+        assert_eq!(queue.get(id).unwrap().unwrap().status, JobStatus::NeedsKey);
+
+        queue.set_api_key("secret").unwrap();
+        assert!(queue.park_for_key().unwrap().is_empty());
+        assert_eq!(queue.claim_next().unwrap().unwrap().id, id);
+    }
+
+    #[test]
+    fn failed_jobs_wait_before_the_retry() {
+        let queue = JobQueue::open(Path::new(":memory:")).unwrap();
+        let id = queue.enqueue(&agent("")).unwrap();
+        let job = queue.claim_next().unwrap().unwrap();
+        assert_eq!(
+            (job.id, job.status, job.attempts),
+            (id, JobStatus::Running, 1)
+        );
+
+        let status = queue.finish(&job, Err("boom".to_string()), None).unwrap();
+        assert_eq!(status, JobStatus::Queued);
+        assert!(
+            queue.claim_next().unwrap().is_none(),
+            "retried without delay"
+        );
+        let wait = queue.next_due();
+        assert!(wait > Duration::from_secs(1) && wait <= POLL_INTERVAL);
+
+        // Pretend the delay passed
+        queue
+            .conn
+            .lock()
+            .unwrap()
+            .execute("UPDATE jobs SET not_before = 0", [])
+            .unwrap();
+        let job = queue.claim_next().unwrap().unwrap();
+        assert_eq!(job.attempts, 2);
+    }
+
+    #[test]
+    fn cancelled_jobs_stay_cancelled() {
+        let queue = JobQueue::open(Path::new(":memory:")).unwrap();
+        let id = queue.enqueue(&agent("")).unwrap();
+        let job = queue.claim_next().unwrap().unwrap();
+        queue.cancel(id).unwrap();
+        let status = queue
+            .finish(&job, Ok(serde_json::Value::Null), None)
+            .unwrap();
+        assert_eq!(status, JobStatus::Cancelled);
+        assert!(queue.cancel(id).is_err());
+        queue.retry(id).unwrap();
+        assert_eq!(queue.claim_next().unwrap().unwrap().id, id);
+    }
+}
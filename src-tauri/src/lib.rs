@@ -3,6 +3,51 @@
 use serde::{Deserialize, Serialize};
 use tauri::{Emitter, Manager};
 
+use std::sync::RwLock;
+
+mod archive;
+mod batch;
+mod charset;
+mod chat;
+mod crawl;
+mod diff;
+mod discover;
+mod extract;
+mod fetch;
+mod http_cache;
+mod jobs;
+mod llm_cache;
+mod openapi;
+mod politeness;
+mod profiles;
+mod providers;
+mod refresh;
+mod render;
+mod repo;
+mod search;
+mod urls;
+mod vault;
+mod warc;
+
+use archive::{Archive, ArchiveHit};
+use batch::{BatchLimits, BatchSelection, BatchSummary};
+use chat::{ChatSession, ChatSessionSummary, ChatStore};
+use crawl::{CrawlLimits, CrawlResult};
+use diff::DocumentDiff;
+use extract::{Document, DocumentKind};
+use fetch::CacheMode;
+use http_cache::HttpCache;
+use jobs::{Job, JobPayload, JobQueue, JobStatus};
+use llm_cache::LlmCache;
+use politeness::{Politeness, PolitenessConfig};
+use profiles::{HeaderConfig, HeaderProfile, HeaderProfiles};
+use providers::{ProviderConfig, SearchProviders};
+use refresh::{RefreshConfig, RefreshReport, RefreshState};
+use repo::{RepoConfig, RepoIngestResult, RepoMode, RepoSettings};
+use search::SearchOptions;
+use vault::{NoteLinks, VaultFilter, VaultGraph};
+use warc::{Snapshot, Snapshots};
+
 #[derive(Clone, Serialize)]
 struct WorkflowLog {
     step: String,
@@ -17,6 +62,11 @@ struct FetchResult {
     summary: String,
     file_path: String,
     error: Option<String>,
+    #[serde(default)]
+    session_id: Option<String>,
+    /// Existing note saved from the same source URL
+    #[serde(default)]
+    duplicate_of: Option<String>,
 }
 
 // SearXNG response structures
@@ -26,12 +76,73 @@ struct SearXNGResponse {
     results: Vec<SearXNGResult>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)] // Added Serialize, Clone
+#[derive(Deserialize, Serialize, Debug, Clone, Default)] // Added Serialize, Clone
 struct SearXNGResult {
     #[serde(default)]
     url: String,
     #[serde(default)]
     title: String,
+    /// Path of the vault note already saved from this URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    in_vault: Option<String>,
+    /// Excerpt shown by the search engine. SearXNG calls it `content`; it is
+    /// sent to the UI as `snippet` so it isn't mistaken for fetched content.
+    #[serde(default, alias = "content", skip_serializing_if = "Option::is_none")]
+    snippet: Option<String>,
+    /// Engine that ranked the result highest
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    engine: Option<String>,
+    /// Every engine that returned the result
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    engines: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    score: Option<f64>,
+    #[serde(
+        default,
+        alias = "publishedDate",
+        skip_serializing_if = "Option::is_none"
+    )]
+    published_date: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    category: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    thumbnail: Option<String>,
+}
+
+impl SearXNGResult {
+    /// One line for the agent: title, URL, snippet and provenance
+    fn agent_line(&self) -> String {
+        let mut line = format!("- {} ({})", self.title, self.url);
+        if let Some(snippet) = self.snippet.as_deref().filter(|s| !s.trim().is_empty()) {
+            let snippet: String = snippet.split_whitespace().collect::<Vec<_>>().join(" ");
+            line.push_str(&format!(
+                ": {}",
+                snippet.chars().take(200).collect::<String>()
+            ));
+        }
+        let mut notes = Vec::new();
+        let engines = if self.engines.is_empty() {
+            self.engine.iter().cloned().collect()
+        } else {
+            self.engines.clone()
+        };
+        if !engines.is_empty() {
+            notes.push(format!("engines: {}", engines.join(", ")));
+        }
+        if let Some(score) = self.score {
+            notes.push(format!("score: {:.2}", score));
+        }
+        if let Some(date) = &self.published_date {
+            notes.push(format!("published: {}", date));
+        }
+        if let Some(path) = &self.in_vault {
+            notes.push(format!("already in vault: {}", path));
+        }
+        if !notes.is_empty() {
+            line.push_str(&format!(" [{}]", notes.join("; ")));
+        }
+        line
+    }
 }
 
 // Ollama response structure
@@ -40,22 +151,55 @@ struct OllamaResponse {
     response: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")] // Matches JS options object
 struct WorkflowOptions {
     use_ai: bool,
-    #[allow(dead_code)]
+    /// Hide the browser window when a page has to be rendered
     headless: bool,
     model_name: String,
     obsidian_api_key: String,
+    #[serde(default)]
+    on_duplicate: DuplicatePolicy,
+    /// Ask Ollama again even if an identical request was answered before
+    #[serde(default)]
+    bypass_llm_cache: bool,
+    /// Save OpenAPI specs as a folder with one note per tag
+    #[serde(default)]
+    split_api_by_tag: bool,
+}
+
+/// What `process_selection` does when the URL is already saved in the vault
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum DuplicatePolicy {
+    /// Log a warning and save a new note anyway
+    Warn,
+    /// Return the existing note without fetching
+    #[default]
+    Skip,
+    /// Re-fetch and overwrite the existing note
+    Refresh,
+}
+
+/// One page of `perform_search` results
+#[derive(Serialize)]
+struct SearchPage {
+    results: Vec<SearXNGResult>,
+    /// Pass back as `cursor` to get the following results
+    next_cursor: Option<String>,
 }
 
 /// Step 1: Search Only
 #[tauri::command]
 async fn perform_search(
     window: tauri::WebviewWindow,
+    graph: tauri::State<'_, RwLock<VaultGraph>>,
     query: String,
-) -> Result<Vec<SearXNGResult>, String> {
+    api_key: Option<String>,
+    options: Option<SearchOptions>,
+    cursor: Option<String>,
+) -> Result<SearchPage, String> {
     emit_log(
         &window,
         "discovery",
@@ -69,21 +213,106 @@ async fn perform_search(
         .build()
         .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
 
-    let results = call_searxng_search(&client, &query).await?;
+    let options = options.unwrap_or_default();
+    let start = match &cursor {
+        Some(cursor) => search::parse_cursor(cursor)?,
+        None => options.pageno.unwrap_or(1).max(1),
+    };
+    let (mut results, next_cursor, errors) = providers::run_search(
+        window.app_handle(),
+        &client,
+        api_key.as_deref(),
+        &query,
+        &options,
+        start,
+    )
+    .await?;
+    for error in errors {
+        emit_log(
+            &window,
+            "discovery",
+            "running",
+            &format!("Provider failed: {}", error),
+        )?;
+    }
+
+    // Flag results we already saved; a missing vault is not an error here
+    if let Some(api_key) = api_key.filter(|k| !k.is_empty()) {
+        let _ = ensure_vault_graph(&graph, &client, &api_key).await;
+    }
+    let saved = mark_saved_results(&graph, &mut results);
+
+    emit_log(
+        &window,
+        "discovery",
+        "complete",
+        &format!(
+            "Found {} results ({} already in vault)",
+            results.len(),
+            saved
+        ),
+    )?;
+    Ok(SearchPage {
+        results,
+        next_cursor,
+    })
+}
+
+/// List a site's pages from its sitemaps and RSS/Atom feeds, as search
+/// results that can be selected like any other
+#[tauri::command]
+async fn discover_sources(
+    app: tauri::AppHandle,
+    graph: tauri::State<'_, RwLock<VaultGraph>>,
+    site: String,
+    limit: Option<usize>,
+    api_key: Option<String>,
+) -> Result<Vec<SearXNGResult>, String> {
+    let window = app.get_webview_window("main").ok_or("No main window")?;
+    emit_log(
+        &window,
+        "discovery",
+        "running",
+        &format!("Reading sitemaps and feeds of {}...", site),
+    )?;
+    let client = reqwest::Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let mut results = discover::discover(&app, &client, &site, limit.unwrap_or(200)).await?;
+    if let Some(api_key) = api_key.filter(|k| !k.is_empty()) {
+        let _ = ensure_vault_graph(&graph, &client, &api_key).await;
+    }
+    let saved = mark_saved_results(&graph, &mut results);
 
     emit_log(
         &window,
         "discovery",
         "complete",
-        &format!("Found {} results", results.len()),
+        &format!("Found {} pages ({} already in vault)", results.len(), saved),
     )?;
     Ok(results)
 }
 
+/// Sets `in_vault` on results whose URL is already a note's `source:`
+fn mark_saved_results(graph: &RwLock<VaultGraph>, results: &mut [SearXNGResult]) -> usize {
+    let graph = graph.read().unwrap();
+    let mut saved = 0;
+    for r in results.iter_mut() {
+        r.in_vault = graph.find_by_source(&r.url);
+        if r.in_vault.is_some() {
+            saved += 1;
+        }
+    }
+    saved
+}
+
 /// Step 2-4: Process Selected Result
 #[tauri::command]
 async fn process_selection(
     app: tauri::AppHandle,
+    graph: tauri::State<'_, RwLock<VaultGraph>>,
     query: String,
     url: String,
     title: String,
@@ -95,6 +324,53 @@ async fn process_selection(
         .build()
         .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
 
+    // Step 1b: Deduplication
+    if let Err(e) = ensure_vault_graph(&graph, &client, &options.obsidian_api_key).await {
+        emit_log(
+            &window,
+            "discovery",
+            "running",
+            &format!("Duplicate check unavailable: {}", e),
+        )?;
+    }
+    let existing = graph.read().unwrap().find_by_source(&url);
+    if let Some(existing_path) = &existing {
+        match options.on_duplicate {
+            DuplicatePolicy::Skip => {
+                emit_log(
+                    &window,
+                    "discovery",
+                    "skipped",
+                    &format!("Already in vault: {}", existing_path),
+                )?;
+                return Ok(FetchResult {
+                    success: false,
+                    title,
+                    summary: String::new(),
+                    file_path: existing_path.clone(),
+                    error: Some(format!("Already saved as {}", existing_path)),
+                    session_id: None,
+                    duplicate_of: existing,
+                });
+            }
+            DuplicatePolicy::Warn => emit_log(
+                &window,
+                "discovery",
+                "running",
+                &format!("Warning: already saved as {}", existing_path),
+            )?,
+            DuplicatePolicy::Refresh => emit_log(
+                &window,
+                "discovery",
+                "running",
+                &format!("Refreshing existing note {}", existing_path),
+            )?,
+        }
+    }
+    let overwrite_path = existing
+        .as_deref()
+        .filter(|_| options.on_duplicate == DuplicatePolicy::Refresh);
+
     // Step 2: Acquisition
     emit_log(
         &window,
@@ -102,28 +378,55 @@ async fn process_selection(
         "running",
         &format!("Fetching: {}...", url),
     )?;
-    let content = fetch_url_content(&client, &url).await?;
+    let mut document =
+        fetch_url_content(&app, &client, &url, options.headless, CacheMode::Default).await?;
     emit_log(
         &window,
         "acquisition",
         "complete",
-        &format!("Retrieved {} chars", content.len()),
+        &format!(
+            "Retrieved {} chars ({})",
+            document.content.len(),
+            document.describe()
+        ),
     )?;
 
+    let fields = source_fields(&app, &url, &document);
+
     // Step 3: Synthesis
-    let summary = if options.use_ai {
+    let api_spec = document.api_spec.take();
+    let summary = if let Some(spec) = &api_spec {
+        emit_log(
+            &window,
+            "synthesis",
+            "skipped",
+            &format!(
+                "{} spec: building structured reference for {} endpoints",
+                spec.spec_version,
+                spec.endpoint_count()
+            ),
+        )?;
+        document.content.clone()
+    } else if options.use_ai {
         emit_log(
             &window,
             "synthesis",
             "running",
             &format!("Summarizing with {}...", options.model_name),
         )?;
-        let sum = call_ollama_summarize(&client, &content, &options.model_name).await?;
+        let sum = call_ollama_summarize(
+            &app,
+            &client,
+            &document,
+            &options.model_name,
+            options.bypass_llm_cache,
+        )
+        .await?;
         emit_log(&window, "synthesis", "complete", "Summary generated")?;
         sum
     } else {
         emit_log(&window, "synthesis", "skipped", "Using raw content")?;
-        content
+        document.content.clone()
     };
 
     // Step 4: Persistence
@@ -133,15 +436,35 @@ async fn process_selection(
         "running",
         "Saving to Obsidian vault...",
     )?;
-    let file_path = save_to_obsidian(
-        &client,
-        &query,
-        &summary,
-        &url,
-        &title,
-        &options.obsidian_api_key,
-    )
-    .await?;
+    let file_path = match &api_spec {
+        Some(spec) => {
+            openapi::save_api_notes(
+                &client,
+                &graph,
+                spec,
+                &url,
+                &title,
+                &options.obsidian_api_key,
+                overwrite_path,
+                options.split_api_by_tag,
+                &fields,
+            )
+            .await?
+        }
+        None => {
+            save_to_obsidian(
+                &client,
+                &query,
+                &summary,
+                &url,
+                &title,
+                &options.obsidian_api_key,
+                overwrite_path,
+                &fields,
+            )
+            .await?
+        }
+    };
     emit_log(
         &window,
         "persistence",
@@ -149,15 +472,422 @@ async fn process_selection(
         &format!("Saved: {}", file_path),
     )?;
 
+    record_baseline(&app, &url, &document);
+    index_saved_note(&graph, &client, &file_path, &options.obsidian_api_key).await;
+
     Ok(FetchResult {
         success: true,
-        title: title, // Use the title passed from selection
+        title, // Use the title passed from selection
         summary: summary.chars().take(200).collect(),
         file_path,
         error: None,
+        session_id: None,
+        duplicate_of: if overwrite_path.is_some() {
+            None
+        } else {
+            existing
+        },
     })
 }
 
+/// Fetch, summarize and save many selections with bounded concurrency.
+/// Per-item progress is emitted as `batch_progress` events.
+#[tauri::command]
+async fn process_batch(
+    app: tauri::AppHandle,
+    query: String,
+    selections: Vec<BatchSelection>,
+    options: WorkflowOptions,
+    limits: Option<BatchLimits>,
+) -> Result<BatchSummary, String> {
+    let window = app.get_webview_window("main").ok_or("No main window")?;
+    emit_log(
+        &window,
+        "acquisition",
+        "running",
+        &format!("Processing {} selections...", selections.len()),
+    )?;
+
+    let summary = batch::run_batch(
+        app.clone(),
+        query,
+        selections,
+        options,
+        limits.unwrap_or_default(),
+    )
+    .await?;
+
+    emit_log(
+        &window,
+        "persistence",
+        "complete",
+        &format!(
+            "Batch finished: {} saved, {} failed",
+            summary.succeeded, summary.failed
+        ),
+    )?;
+    Ok(summary)
+}
+
+/// Crawl a documentation site from a start page and save it as a linked
+/// folder of notes with an index note
+#[tauri::command]
+async fn crawl_docs(
+    app: tauri::AppHandle,
+    start_url: String,
+    options: WorkflowOptions,
+    limits: Option<CrawlLimits>,
+) -> Result<CrawlResult, String> {
+    let window = app.get_webview_window("main").ok_or("No main window")?;
+    let client = reqwest::Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let result = crawl::crawl_docs(
+        &app,
+        &window,
+        &client,
+        &start_url,
+        &options,
+        &limits.unwrap_or_default(),
+    )
+    .await?;
+    emit_log(
+        &window,
+        "persistence",
+        "complete",
+        &format!(
+            "Saved {} of {} pages, index: {}",
+            result
+                .pages
+                .iter()
+                .filter(|p| p.file_path.is_some())
+                .count(),
+            result.pages.len(),
+            result.index_path
+        ),
+    )?;
+    Ok(result)
+}
+
+/// Save a repository's README and docs Markdown, from a GitHub URL or a
+/// local checkout, as one merged note or a folder of linked notes
+#[tauri::command]
+async fn ingest_repo(
+    app: tauri::AppHandle,
+    graph: tauri::State<'_, RwLock<VaultGraph>>,
+    source: String,
+    api_key: String,
+    mode: Option<RepoMode>,
+    docs_path: Option<String>,
+    on_duplicate: Option<DuplicatePolicy>,
+) -> Result<RepoIngestResult, String> {
+    let window = app.get_webview_window("main").ok_or("No main window")?;
+    let client = reqwest::Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    if let Err(e) = ensure_vault_graph(&graph, &client, &api_key).await {
+        emit_log(
+            &window,
+            "discovery",
+            "running",
+            &format!("Duplicate check unavailable: {}", e),
+        )?;
+    }
+    let on_duplicate = on_duplicate.unwrap_or_default();
+    let existing = graph.read().unwrap().find_by_source(source.trim());
+    if let Some(existing_path) = &existing {
+        match on_duplicate {
+            DuplicatePolicy::Skip => {
+                emit_log(
+                    &window,
+                    "discovery",
+                    "skipped",
+                    &format!("Already in vault: {}", existing_path),
+                )?;
+                return Ok(RepoIngestResult {
+                    file_path: existing_path.clone(),
+                    notes: vec![existing_path.clone()],
+                    files: 0,
+                });
+            }
+            DuplicatePolicy::Warn => emit_log(
+                &window,
+                "discovery",
+                "running",
+                &format!("Warning: already saved as {}", existing_path),
+            )?,
+            DuplicatePolicy::Refresh => emit_log(
+                &window,
+                "discovery",
+                "running",
+                &format!("Refreshing existing note {}", existing_path),
+            )?,
+        }
+    }
+    let overwrite_path = existing
+        .as_deref()
+        .filter(|_| on_duplicate == DuplicatePolicy::Refresh);
+
+    emit_log(
+        &window,
+        "acquisition",
+        "running",
+        &format!("Reading docs from {}...", source),
+    )?;
+    let result = repo::ingest(
+        &app,
+        &client,
+        &source,
+        &api_key,
+        mode.unwrap_or_default(),
+        docs_path.as_deref(),
+        overwrite_path,
+    )
+    .await?;
+    emit_log(
+        &window,
+        "persistence",
+        "complete",
+        &format!("Saved {} files to {}", result.files, result.file_path),
+    )?;
+    Ok(result)
+}
+
+#[tauri::command]
+fn get_repo_config(state: tauri::State<'_, RepoSettings>) -> RepoConfig {
+    state.config()
+}
+
+/// Sets the folders `ingest_repo` may read local checkouts from
+#[tauri::command]
+fn set_repo_config(
+    state: tauri::State<'_, RepoSettings>,
+    config: RepoConfig,
+) -> Result<(), String> {
+    state.set_config(config)
+}
+
+// ----------------------
+// Background Jobs
+// ----------------------
+
+/// Queue a batch or agent run; it survives restarts until it finishes
+#[tauri::command]
+fn enqueue_job(queue: tauri::State<'_, JobQueue>, payload: JobPayload) -> Result<i64, String> {
+    queue.enqueue(&payload)
+}
+
+#[tauri::command]
+fn list_jobs(
+    queue: tauri::State<'_, JobQueue>,
+    status: Option<JobStatus>,
+) -> Result<Vec<Job>, String> {
+    queue.list(status)
+}
+
+#[tauri::command]
+fn retry_job(queue: tauri::State<'_, JobQueue>, id: i64) -> Result<(), String> {
+    queue.retry(id)
+}
+
+#[tauri::command]
+fn cancel_job(queue: tauri::State<'_, JobQueue>, id: i64) -> Result<(), String> {
+    queue.cancel(id)
+}
+
+/// Supplies the API key to jobs resumed after a restart
+#[tauri::command]
+fn set_job_api_key(queue: tauri::State<'_, JobQueue>, api_key: String) -> Result<(), String> {
+    queue.set_api_key(&api_key)
+}
+
+// ----------------------
+// Scheduled Refresh
+// ----------------------
+
+#[tauri::command]
+fn get_refresh_config(state: tauri::State<'_, RefreshState>) -> RefreshConfig {
+    state.config()
+}
+
+#[tauri::command]
+fn set_refresh_config(
+    state: tauri::State<'_, RefreshState>,
+    config: RefreshConfig,
+) -> Result<(), String> {
+    state.set_config(config)
+}
+
+/// Check every fetched note now, regardless of when it was last checked
+#[tauri::command]
+async fn refresh_sources_now(app: tauri::AppHandle) -> Result<RefreshReport, String> {
+    let config = app.state::<RefreshState>().config();
+    if config.obsidian_api_key.is_empty() {
+        return Err("Obsidian API Key required for refresh".to_string());
+    }
+    refresh::run_cycle(&app, &config, true).await
+}
+
+#[derive(Serialize)]
+struct SourceDiff {
+    url: String,
+    /// False when the page was never fetched before; `diff` is then empty
+    has_previous: bool,
+    diff: DocumentDiff,
+    summary: Option<String>,
+}
+
+/// Compare the live page with the text stored when its note was saved or
+/// last refreshed, without updating it
+#[tauri::command]
+async fn diff_source(
+    app: tauri::AppHandle,
+    url: String,
+    summarize: Option<bool>,
+    model: Option<String>,
+    bypass_cache: Option<bool>,
+) -> Result<SourceDiff, String> {
+    let client = reqwest::Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let previous = app.state::<RefreshState>().snapshot(&url);
+    // A cached copy could hide changes for as long as its max-age allows
+    let current = fetch_url_content(&app, &client, &url, true, CacheMode::Revalidate)
+        .await?
+        .content;
+    let Some(previous) = previous else {
+        return Ok(SourceDiff {
+            url,
+            has_previous: false,
+            diff: DocumentDiff::default(),
+            summary: None,
+        });
+    };
+
+    let diff = diff::diff_documents(&previous, &current);
+    let model = model.unwrap_or_else(|| app.state::<RefreshState>().config().model_name);
+    let summary = if summarize.unwrap_or(false) && !diff.is_empty() && !model.is_empty() {
+        let prompt = Message {
+            role: "user".to_string(),
+            content: diff::summary_prompt(&url, &diff),
+        };
+        Some(
+            call_ollama_chat(
+                &app,
+                &client,
+                &[prompt],
+                &model,
+                bypass_cache.unwrap_or(false),
+            )
+            .await?,
+        )
+    } else {
+        None
+    };
+
+    Ok(SourceDiff {
+        url,
+        has_previous: true,
+        diff,
+        summary,
+    })
+}
+
+// ----------------------
+// Politeness
+// ----------------------
+
+#[tauri::command]
+fn get_politeness_config(state: tauri::State<'_, Politeness>) -> PolitenessConfig {
+    state.config()
+}
+
+#[tauri::command]
+fn set_politeness_config(
+    state: tauri::State<'_, Politeness>,
+    config: PolitenessConfig,
+) -> Result<(), String> {
+    state.set_config(config)
+}
+
+#[tauri::command]
+fn get_header_config(state: tauri::State<'_, HeaderProfiles>) -> HeaderConfig {
+    state.config()
+}
+
+#[tauri::command]
+fn set_header_config(
+    state: tauri::State<'_, HeaderProfiles>,
+    config: HeaderConfig,
+) -> Result<(), String> {
+    state.set_config(config)
+}
+
+/// Built-in and custom profiles, as they will be sent
+#[tauri::command]
+fn list_header_profiles(state: tauri::State<'_, HeaderProfiles>) -> Vec<HeaderProfile> {
+    state.config().profiles()
+}
+
+#[tauri::command]
+fn get_search_provider_config(state: tauri::State<'_, SearchProviders>) -> ProviderConfig {
+    state.config()
+}
+
+#[tauri::command]
+fn set_search_provider_config(
+    state: tauri::State<'_, SearchProviders>,
+    config: ProviderConfig,
+) -> Result<(), String> {
+    state.set_config(config)
+}
+
+/// Names accepted in `SearchOptions.providers`
+#[tauri::command]
+fn list_search_providers(state: tauri::State<'_, SearchProviders>) -> Vec<String> {
+    state.names()
+}
+
+// ----------------------
+// Caches
+// ----------------------
+
+/// Drop every cached response; returns the number of bytes freed
+#[tauri::command]
+fn clear_cache(cache: tauri::State<'_, HttpCache>) -> Result<u64, String> {
+    cache.clear()
+}
+
+/// Drop every cached Ollama response; returns the number of entries removed
+#[tauri::command]
+fn clear_llm_cache(cache: tauri::State<'_, LlmCache>) -> Result<usize, String> {
+    cache.clear()
+}
+
+/// Keep the source index current without a full rebuild
+async fn index_saved_note(
+    graph: &RwLock<VaultGraph>,
+    client: &reqwest::Client,
+    file_path: &str,
+    api_key: &str,
+) {
+    if !graph.read().unwrap().is_built() {
+        return;
+    }
+    if let Ok(saved) = fetch_vault_file(client, file_path, api_key).await {
+        graph
+            .write()
+            .unwrap()
+            .upsert(vault::parse_note(file_path, &saved));
+    }
+}
+
 fn emit_log(
     window: &tauri::WebviewWindow,
     step: &str,
@@ -176,19 +906,16 @@ fn emit_log(
         .map_err(|e: tauri::Error| e.to_string())
 }
 
-/// Search using SearXNG (localhost:8080)
+/// Search using SearXNG at `base_url` (localhost:8080 unless configured)
 async fn call_searxng_search(
     client: &reqwest::Client,
+    base_url: &str,
     query: &str,
+    options: &SearchOptions,
 ) -> Result<Vec<SearXNGResult>, String> {
-    // Changed return type
-    let search_url = format!(
-        "http://localhost:8080/search?q={}&format=json&categories=general",
-        urlencoding::encode(&format!("{} documentation", query))
-    );
-
     let response = client
-        .get(&search_url)
+        .get(format!("{}/search", base_url.trim_end_matches('/')))
+        .query(&options.query_params(query))
         .send()
         .await
         .map_err(|e| e.to_string())?;
@@ -205,14 +932,136 @@ async fn call_searxng_search(
         )
     })?;
 
-    let results: Vec<SearXNGResult> = searx_response
-        .results
-        .into_iter()
-        .filter(|r| !r.url.is_empty())
-        .take(20) // Return top 20 results (requested > 5)
-        .collect();
+    let results: Vec<SearXNGResult> = searx_response
+        .results
+        .into_iter()
+        .filter(|r| !r.url.is_empty())
+        .collect();
+
+    Ok(results)
+}
+
+// ----------------------
+// Archive
+// ----------------------
+
+/// Full-text search over every page fetched so far, offline
+#[tauri::command]
+async fn search_archive(
+    archive: tauri::State<'_, Archive>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<ArchiveHit>, String> {
+    let archive = archive.inner().clone();
+    let limit = limit.unwrap_or(20).min(100);
+    tokio::task::spawn_blocking(move || archive.search(&query, limit))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Cutoff for the clear commands: `days` ago, or everything when `None`
+fn clear_cutoff(older_than_days: Option<u32>) -> Option<chrono::DateTime<chrono::Utc>> {
+    older_than_days.map(|days| chrono::Utc::now() - chrono::Duration::days(days.into()))
+}
+
+/// Drop archived pages last fetched more than `older_than_days` ago, or all
+/// of them. Returns how many were removed.
+#[tauri::command]
+async fn clear_archive(
+    archive: tauri::State<'_, Archive>,
+    older_than_days: Option<u32>,
+) -> Result<usize, String> {
+    let archive = archive.inner().clone();
+    let before = clear_cutoff(older_than_days).map(|time| time.timestamp());
+    tokio::task::spawn_blocking(move || archive.clear(before))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Delete WARC snapshots captured more than `older_than_days` ago, or all
+/// of them. Returns how many were deleted.
+#[tauri::command]
+async fn clear_snapshots(
+    snapshots: tauri::State<'_, Snapshots>,
+    older_than_days: Option<u32>,
+) -> Result<usize, String> {
+    let snapshots = snapshots.inner().clone();
+    let before = clear_cutoff(older_than_days);
+    tokio::task::spawn_blocking(move || snapshots.clear(before))
+        .await
+        .map_err(|e| e.to_string())
+}
 
-    Ok(results)
+/// A page re-extracted from its WARC snapshot
+#[derive(Serialize)]
+struct SnapshotView {
+    snapshot: Snapshot,
+    title: String,
+    /// e.g. "web page"
+    kind: String,
+    /// The whole extracted text, as Markdown
+    content: String,
+    /// The note rewritten from the snapshot, when an API key was given
+    file_path: Option<String>,
+}
+
+/// Re-extract a page from its snapshot (the `snapshot` frontmatter field),
+/// for when the live page is gone. With an API key the note of that
+/// source is rewritten from the archived copy, without the LLM.
+#[tauri::command]
+async fn view_snapshot(
+    app: tauri::AppHandle,
+    graph: tauri::State<'_, RwLock<VaultGraph>>,
+    snapshot_id: String,
+    api_key: Option<String>,
+) -> Result<SnapshotView, String> {
+    let snapshots = app.state::<Snapshots>().inner().clone();
+    let (snapshot, response, rendered) =
+        tokio::task::spawn_blocking(move || snapshots.read(snapshot_id.trim()))
+            .await
+            .map_err(|e| e.to_string())??;
+    let document = match &rendered {
+        Some(html) => rendered_document(html, &snapshot.url)?,
+        None => response_document(&response, &snapshot.url).await?,
+    };
+    let title = document
+        .title
+        .clone()
+        .unwrap_or_else(|| snapshot.url.clone());
+
+    let file_path = match api_key.filter(|k| !k.is_empty()) {
+        Some(api_key) => {
+            let client = reqwest::Client::builder()
+                .danger_accept_invalid_certs(true)
+                .build()
+                .map_err(|e| e.to_string())?;
+            // Without the graph a new note is written instead of the old one
+            let _ = ensure_vault_graph(&graph, &client, &api_key).await;
+            let existing = graph.read().unwrap().find_by_source(&snapshot.url);
+            let file_path = save_to_obsidian(
+                &client,
+                &title,
+                &document.content,
+                &snapshot.url,
+                &title,
+                &api_key,
+                existing.as_deref(),
+                &warc::frontmatter_fields(&snapshot),
+            )
+            .await?;
+            index_saved_note(&graph, &client, &file_path, &api_key).await;
+            Some(file_path)
+        }
+        None => None,
+    };
+
+    Ok(SnapshotView {
+        kind: document.describe().to_string(),
+        title,
+        content: document.content,
+        snapshot,
+        file_path,
+    })
 }
 
 // ----------------------
@@ -222,21 +1071,65 @@ async fn call_searxng_search(
 #[derive(Deserialize, Debug)]
 struct ObsidianSearchResult {
     filename: String,
-    #[allow(dead_code)]
     score: f64,
 }
 
 #[tauri::command]
-async fn search_vault(query: String, api_key: String) -> Result<Vec<SearXNGResult>, String> {
+async fn search_vault(
+    graph: tauri::State<'_, RwLock<VaultGraph>>,
+    query: String,
+    api_key: String,
+    filter: Option<VaultFilter>,
+) -> Result<Vec<SearXNGResult>, String> {
+    query_vault(&graph, &query, &api_key, filter.unwrap_or_default())
+        .await
+        .map(|(results, _)| results)
+}
+
+/// Free-text search through Obsidian, narrowed by the structured filter.
+/// A query with no text is answered from the local vault graph alone.
+/// Also returns the filter with the query's inline `#tag`/`folder:` terms
+/// merged in.
+async fn query_vault(
+    graph: &RwLock<VaultGraph>,
+    query: &str,
+    api_key: &str,
+    mut filter: VaultFilter,
+) -> Result<(Vec<SearXNGResult>, VaultFilter), String> {
     // Reusing SearXNGResult for UI consistency (url=path, title=filename)
     let client = reqwest::Client::builder()
         .danger_accept_invalid_certs(true)
         .build()
         .map_err(|e| e.to_string())?;
 
+    let (text, inline_filter) = VaultFilter::parse_inline(query);
+    filter.extend(inline_filter);
+    if !filter.is_empty() {
+        ensure_vault_graph(graph, &client, api_key).await?;
+    }
+
+    if text.is_empty() {
+        if filter.is_empty() {
+            return Err("Empty vault query".to_string());
+        }
+        let results = graph
+            .read()
+            .unwrap()
+            .filter_notes(&filter)
+            .into_iter()
+            .take(50)
+            .map(|n| SearXNGResult {
+                title: n.path.clone(),
+                url: n.path.clone(),
+                ..Default::default()
+            })
+            .collect();
+        return Ok((results, filter));
+    }
+
     let search_url = format!(
         "https://127.0.0.1:27124/search/simple?query={}",
-        urlencoding::encode(&query)
+        urlencoding::encode(&text)
     );
 
     let response = client
@@ -252,34 +1145,81 @@ async fn search_vault(query: String, api_key: String) -> Result<Vec<SearXNGResul
     let results: Vec<ObsidianSearchResult> =
         serde_json::from_str(&text).map_err(|e| format!("Obsidian Parse Error: {}", e))?;
 
+    let graph = graph.read().unwrap();
     let ui_results: Vec<SearXNGResult> = results
         .into_iter()
+        .filter(|r| filter.matches(&r.filename, &graph))
         .take(10)
         .map(|r| SearXNGResult {
             title: r.filename.clone(),
             url: r.filename, // Path serves as URL/ID
+            score: Some(r.score),
+            ..Default::default()
         })
         .collect();
 
-    Ok(ui_results)
+    Ok((ui_results, filter))
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn chat_with_vault(
     window: tauri::WebviewWindow,
+    sessions: tauri::State<'_, ChatStore>,
+    graph: tauri::State<'_, RwLock<VaultGraph>>,
     query: String,
     api_key: String,
     model: String,
+    session_id: Option<String>,
+    filter: Option<VaultFilter>,
+    bypass_cache: Option<bool>,
 ) -> Result<FetchResult, String> {
+    let bypass_cache = bypass_cache.unwrap_or(false);
+    let client = reqwest::Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let session = sessions.get_or_create(session_id.as_deref(), &query)?;
+
+    // 0. Condense follow-ups into a standalone query
+    let search_query = if session.messages.is_empty() {
+        query.clone()
+    } else {
+        emit_log(
+            &window,
+            "discovery",
+            "running",
+            "Rewriting follow-up question...",
+        )?;
+        let prompt = chat::condense_question_prompt(&session.messages, &query);
+        match call_ollama_chat(window.app_handle(), &client, &prompt, &model, bypass_cache).await {
+            Ok(rewritten) if !rewritten.trim().is_empty() => {
+                rewritten.trim().trim_matches('"').to_string()
+            }
+            _ => query.clone(),
+        }
+    };
+
     emit_log(
         &window,
         "discovery",
         "running",
-        &format!("Searching vault for: {}...", query),
+        &format!("Searching vault for: {}...", search_query),
     )?;
 
     // 1. Search
-    let results = search_vault(query.clone(), api_key.clone()).await?;
+    if let Err(e) = ensure_vault_graph(&graph, &client, &api_key).await {
+        emit_log(
+            &window,
+            "discovery",
+            "running",
+            &format!("Link graph unavailable: {}", e),
+        )?;
+    }
+    // Neighbours get the same filter, including `#tag`/`folder:` from the query
+    let (results, filter) =
+        query_vault(&graph, &search_query, &api_key, filter.unwrap_or_default()).await?;
     if results.is_empty() {
         return Err("No matching notes found.".to_string());
     }
@@ -290,11 +1230,21 @@ async fn chat_with_vault(
         &format!("Found {} notes", results.len()),
     )?;
 
-    // 2. Read Top 3
-    let client = reqwest::Client::builder()
-        .danger_accept_invalid_certs(true)
-        .build()
-        .map_err(|e| e.to_string())?;
+    // 2. Read Top 3, then their linked neighbours
+    let top: Vec<String> = results.iter().take(3).map(|r| r.url.clone()).collect();
+    let neighbours: Vec<String> = {
+        let graph = graph.read().unwrap();
+        let mut linked = Vec::new();
+        for path in &top {
+            for n in graph.neighbours(path) {
+                if !top.contains(&n) && !linked.contains(&n) && filter.matches(&n, &graph) {
+                    linked.push(n);
+                }
+            }
+        }
+        linked.into_iter().take(3).collect()
+    };
+
     let mut context = String::new();
 
     emit_log(
@@ -303,51 +1253,170 @@ async fn chat_with_vault(
         "running",
         "Reading note contents...",
     )?;
-    for (i, res) in results.iter().take(3).enumerate() {
-        let path = &res.url;
-        let url = format!(
-            "https://127.0.0.1:27124/vault/{}",
-            urlencoding::encode(path)
-        );
-
-        if let Ok(resp) = client
-            .get(&url)
-            .header("Authorization", format!("Bearer {}", api_key))
-            .send()
-            .await
-        {
-            if let Ok(content) = resp.text().await {
-                context.push_str(&format!("\n--- Note: {} ---\n{}\n", res.title, content));
-            }
+    for (i, path) in top.iter().enumerate() {
+        if let Ok(content) = fetch_vault_file(&client, path, &api_key).await {
+            context.push_str(&format!(
+                "\n--- Note: {} ---\n{}\n",
+                vault::note_name(path),
+                content
+            ));
         }
         emit_log(
             &window,
             "acquisition",
             "running",
-            &format!("Read {}/3 notes...", i + 1),
+            &format!("Read {}/{} notes...", i + 1, top.len()),
+        )?;
+    }
+    if !neighbours.is_empty() {
+        emit_log(
+            &window,
+            "acquisition",
+            "running",
+            &format!("Following {} linked notes...", neighbours.len()),
         )?;
+        for path in &neighbours {
+            if let Ok(content) = fetch_vault_file(&client, path, &api_key).await {
+                context.push_str(&format!(
+                    "\n--- Linked Note: {} ---\n{}\n",
+                    vault::note_name(path),
+                    content.chars().take(1500).collect::<String>()
+                ));
+            }
+        }
     }
     emit_log(&window, "acquisition", "complete", "Context loaded")?;
 
-    // 3. Synthesize
+    // 3. Synthesize with the prior turns in the message history
     emit_log(&window, "synthesis", "running", "Generating answer...")?;
-    let prompt = format!(
-        "You are an assistant with access to the user's notes. Answer the question based ONLY on the provided context.\n\nContext:\n{}\n\nQuestion: {}\n\nAnswer:",
-        context, query
-    );
+    let mut messages = vec![Message {
+        role: "system".to_string(),
+        content: "You are an assistant with access to the user's notes. Answer the question based ONLY on the provided context and the conversation so far.".to_string(),
+    }];
+    messages.extend(session.messages.iter().cloned());
+    messages.push(Message {
+        role: "user".to_string(),
+        content: format!("Context:\n{}\n\nQuestion: {}", context, query),
+    });
 
-    let answer = call_ollama_summarize(&client, &prompt, &model).await?; // Reusing summarize helper for generic chat
+    let answer = call_ollama_chat(
+        window.app_handle(),
+        &client,
+        &messages,
+        &model,
+        bypass_cache,
+    )
+    .await?;
     emit_log(&window, "synthesis", "complete", "Answer ready")?;
 
+    // 4. Remember the turn (without the bulky context)
+    sessions.append_turn(
+        &session,
+        vec![
+            Message {
+                role: "user".to_string(),
+                content: query.clone(),
+            },
+            Message {
+                role: "assistant".to_string(),
+                content: answer.clone(),
+            },
+        ],
+    )?;
+
     Ok(FetchResult {
         success: true,
         title: format!("Chat: {}", query),
         summary: answer,
         file_path: "In Memory".to_string(),
         error: None,
+        session_id: Some(session.id),
+        duplicate_of: None,
     })
 }
 
+#[tauri::command]
+fn list_chat_sessions(sessions: tauri::State<'_, ChatStore>) -> Vec<ChatSessionSummary> {
+    sessions.list()
+}
+
+#[tauri::command]
+fn get_chat_session(
+    sessions: tauri::State<'_, ChatStore>,
+    session_id: String,
+) -> Result<ChatSession, String> {
+    sessions
+        .get(&session_id)
+        .ok_or(format!("Unknown chat session: {}", session_id))
+}
+
+#[tauri::command]
+fn delete_chat_session(
+    sessions: tauri::State<'_, ChatStore>,
+    session_id: String,
+) -> Result<(), String> {
+    sessions.delete(&session_id)
+}
+
+/// Rebuilt on next use after this long, to pick up edits made in Obsidian
+const GRAPH_MAX_AGE: std::time::Duration = std::time::Duration::from_secs(15 * 60);
+
+/// Held while the vault is scanned, so concurrent callers share one build
+static GRAPH_BUILD: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Builds the link graph on first use, and again once it is stale
+async fn ensure_vault_graph(
+    graph: &RwLock<VaultGraph>,
+    client: &reqwest::Client,
+    api_key: &str,
+) -> Result<(), String> {
+    if graph.read().unwrap().is_fresh(GRAPH_MAX_AGE) {
+        return Ok(());
+    }
+    let _building = GRAPH_BUILD.lock().await;
+    // Built by whoever held the lock before us
+    if graph.read().unwrap().is_fresh(GRAPH_MAX_AGE) {
+        return Ok(());
+    }
+    let built = vault::build_graph(client, api_key).await?;
+    *graph.write().unwrap() = built;
+    Ok(())
+}
+
+#[tauri::command]
+async fn refresh_vault_graph(
+    graph: tauri::State<'_, RwLock<VaultGraph>>,
+    api_key: String,
+) -> Result<usize, String> {
+    let client = reqwest::Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let _building = GRAPH_BUILD.lock().await;
+    let built = vault::build_graph(&client, &api_key).await?;
+    let count = built.len();
+    *graph.write().unwrap() = built;
+    Ok(count)
+}
+
+#[tauri::command]
+async fn get_note_links(
+    graph: tauri::State<'_, RwLock<VaultGraph>>,
+    path: String,
+    api_key: String,
+) -> Result<NoteLinks, String> {
+    let client = reqwest::Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()
+        .map_err(|e| e.to_string())?;
+    ensure_vault_graph(&graph, &client, &api_key).await?;
+    let graph = graph.read().unwrap();
+    let resolved = graph.resolve(&path).unwrap_or(path);
+    graph
+        .links_for(&resolved)
+        .ok_or(format!("Note not found in vault graph: {}", resolved))
+}
+
 // ----------------------
 // Agentic Loop (Autonomous)
 // ----------------------
@@ -361,11 +1430,33 @@ struct Message {
 #[tauri::command]
 async fn run_agent_loop(
     window: tauri::WebviewWindow,
+    graph: tauri::State<'_, RwLock<VaultGraph>>,
     query: String,
     model: String,
     api_key: String,
+    bypass_cache: Option<bool>,
+) -> Result<FetchResult, String> {
+    agent_loop(
+        &window,
+        &graph,
+        &query,
+        &model,
+        &api_key,
+        bypass_cache.unwrap_or(false),
+    )
+    .await
+}
+
+/// The ReAct loop behind `run_agent_loop`, shared with queued agent jobs
+async fn agent_loop(
+    window: &tauri::WebviewWindow,
+    graph: &RwLock<VaultGraph>,
+    query: &str,
+    model: &str,
+    api_key: &str,
+    bypass_cache: bool,
 ) -> Result<FetchResult, String> {
-    emit_log(&window, "discovery", "running", "Initializing Agent...")?;
+    emit_log(window, "discovery", "running", "Initializing Agent...")?;
 
     let client = reqwest::Client::builder()
         .danger_accept_invalid_certs(true)
@@ -379,9 +1470,13 @@ async fn run_agent_loop(
                 "You are an autonomous research agent. Your goal is to answer the user's question by searching and fetching information.\n\n\
                 TOOLS:\n\
                 - [TOOL: search(\"query\")] -> Returns a list of URLs and Titles from the web.\n\
+                - [TOOL: search(\"query\", {{\"preset\": \"news\", \"timeRange\": \"week\"}})] -> Same, narrowed by a preset (docs, news, code, papers) or by categories, engines, language, timeRange (day/week/month/year) and pageno; deep (2-10) merges several result pages.\n\
+                - [TOOL: search(\"query\", {{\"providers\": [\"searxng\", \"fetched\"]}})] -> Searches several sources at once and merges their rankings: searxng (the web), vault (your notes), fetched (pages fetched before) and any configured JSON APIs.\n\
                 - [TOOL: fetch(\"url\")] -> Returns the content of a web URL.\n\
-                - [TOOL: vault_search(\"query\")] -> Searches your local Obsidian notes.\n\
-                - [TOOL: vault_read(\"filename\")] -> Reads the full content of a local note.\n\n\
+                - [TOOL: vault_search(\"query\")] -> Searches your local Obsidian notes. Add #tag or folder:Path to the query to filter.\n\
+                - [TOOL: vault_search(\"query\", {{\"fields\": [{{\"field\": \"fetched_date\", \"op\": \"gte\", \"value\": \"2024-01-01\"}}], \"modifiedAfter\": \"2024-05-01\"}})] -> Same, with structured filters (tags, folder, fields with op eq/contains/gte/lte/exists, modifiedAfter, modifiedBefore). The query may be empty.\n\
+                - [TOOL: vault_read(\"filename\")] -> Reads the full content of a local note.\n\
+                - [TOOL: archive_search(\"query\")] -> Searches the full text of every page fetched before, offline. Supports \"exact phrases\", +required and -excluded words.\n\n\
                 INSTRUCTIONS:\n\
                 1. Analyze the user's request.\n\
                 2. DECIDE if you need to search (web/vault) or fetch/read (web/vault).\n\
@@ -400,14 +1495,15 @@ async fn run_agent_loop(
 
     for turn in 0..max_turns {
         emit_log(
-            &window,
+            window,
             "synthesis",
             "running",
             &format!("Agent thinking (Turn {}/{})", turn + 1, max_turns),
         )?;
 
         // 1. Get LLM Response
-        let response = call_ollama_chat(&client, &history, &model).await?;
+        let response =
+            call_ollama_chat(window.app_handle(), &client, &history, model, bypass_cache).await?;
         history.push(Message {
             role: "assistant".to_string(),
             content: response.clone(),
@@ -417,7 +1513,7 @@ async fn run_agent_loop(
         let thought = response.split("[TOOL:").next().unwrap_or(&response).trim();
         if !thought.is_empty() {
             emit_log(
-                &window,
+                window,
                 "synthesis",
                 "running",
                 &format!("Agent: {}", thought),
@@ -427,53 +1523,147 @@ async fn run_agent_loop(
         // 2. Parse Tool Call
         if let Some(tool_call) = parse_tool_call(&response) {
             emit_log(
-                &window,
+                window,
                 "acquisition",
                 "running",
                 &format!("Executing: {}", tool_call),
             )?;
 
-            let result = if tool_call.to_lowercase().contains("search")
-                && !tool_call.to_lowercase().contains("vault")
-            {
-                let q = extract_arg(&tool_call);
-                match call_searxng_search(&client, &q).await {
-                    Ok(urls) => {
-                        let formatted = urls
-                            .iter()
-                            .map(|u| format!("- {} ({})", u.title, u.url))
-                            .collect::<Vec<_>>()
-                            .join("\n");
-                        format!("Web Search Results for '{}':\n{}", q, formatted)
-                    }
+            let name = tool_name(&tool_call);
+            let result = if name == "search" {
+                let (q, options_json) = extract_args(&tool_call);
+                match tool_options::<SearchOptions>(options_json) {
                     Err(e) => format!("Error searching web: {}", e),
+                    Ok(options) => {
+                        let start = options.pageno.unwrap_or(1).max(1);
+                        match providers::run_search(
+                            window.app_handle(),
+                            &client,
+                            Some(api_key),
+                            &q,
+                            &options,
+                            start,
+                        )
+                        .await
+                        {
+                            Ok((mut urls, next_cursor, errors)) => {
+                                mark_saved_results(graph, &mut urls);
+                                let formatted = urls
+                                    .iter()
+                                    .map(SearXNGResult::agent_line)
+                                    .collect::<Vec<_>>()
+                                    .join("\n");
+                                let next_page =
+                                    next_cursor.and_then(|c| search::parse_cursor(&c).ok());
+                                let more = match next_page {
+                                    Some(page) => format!(
+                                        "\n(More results: pass {{\"pageno\": {}}}, or {{\"deep\": N}} to merge N pages)",
+                                        page
+                                    ),
+                                    None => String::new(),
+                                };
+                                let failed = if errors.is_empty() {
+                                    String::new()
+                                } else {
+                                    format!("\n(Failed providers: {})", errors.join("; "))
+                                };
+                                format!(
+                                    "Web Search Results for '{}':\n{}{}{}",
+                                    q, formatted, more, failed
+                                )
+                            }
+                            Err(e) => format!("Error searching web: {}", e),
+                        }
+                    }
                 }
-            } else if tool_call.to_lowercase().contains("fetch") {
+            } else if name == "fetch" {
                 let url = extract_arg(&tool_call);
-                match fetch_url_content(&client, &url).await {
-                    Ok(content) => format!(
-                        "Content of {}:\n{}",
+                match fetch_url_content(
+                    window.app_handle(),
+                    &client,
+                    &url,
+                    true,
+                    CacheMode::Default,
+                )
+                .await
+                {
+                    Ok(document) => format!(
+                        "Content of {} ({}):\n{}",
                         url,
-                        content.chars().take(2000).collect::<String>()
+                        document.describe(),
+                        document.content.chars().take(2000).collect::<String>()
                     ),
                     Err(e) => format!("Error fetching web {}: {}", url, e),
                 }
-            } else if tool_call.to_lowercase().contains("vault_search") {
+            } else if name == "vault_search" {
+                let (q, filter_json) = extract_args(&tool_call);
+                match tool_options::<VaultFilter>(filter_json) {
+                    Err(e) => format!("Error searching vault: {}", e),
+                    Ok(filter) => {
+                        // The graph is only an enhancement here; search still works without it
+                        let _ = ensure_vault_graph(graph, &client, api_key).await;
+                        match query_vault(graph, &q, api_key, filter).await {
+                            Ok((notes, filter)) => {
+                                let graph = graph.read().unwrap();
+                                let formatted = notes
+                                    .iter()
+                                    .map(|n| {
+                                        let linked = graph
+                                            .neighbours(&n.url)
+                                            .iter()
+                                            .filter(|p| filter.matches(p, &graph))
+                                            .take(5)
+                                            .map(|p| vault::note_name(p))
+                                            .collect::<Vec<_>>();
+                                        if linked.is_empty() {
+                                            format!("- {} (Filename: {})", n.title, n.url)
+                                        } else {
+                                            format!(
+                                                "- {} (Filename: {}) Linked: {}",
+                                                n.title,
+                                                n.url,
+                                                linked.join(", ")
+                                            )
+                                        }
+                                    })
+                                    .collect::<Vec<_>>()
+                                    .join("\n");
+                                format!("Vault Search Results for '{}':\n{}", q, formatted)
+                            }
+                            Err(e) => format!("Error searching vault: {}", e),
+                        }
+                    }
+                }
+            } else if name == "archive_search" {
                 let q = extract_arg(&tool_call);
-                match search_vault(q.clone(), api_key.clone()).await {
-                    Ok(notes) => {
-                        let formatted = notes
+                let archive = window.state::<Archive>().inner().clone();
+                let query = q.clone();
+                let hits = tokio::task::spawn_blocking(move || archive.search(&query, 10))
+                    .await
+                    .map_err(|e| e.to_string())
+                    .and_then(|hits| hits);
+                match hits {
+                    Ok(hits) => {
+                        let formatted = hits
                             .iter()
-                            .map(|n| format!("- {} (Filename: {})", n.title, n.url))
+                            .map(|h| {
+                                format!(
+                                    "- {} ({}, fetched {}) {}",
+                                    h.title,
+                                    h.url,
+                                    h.fetched_at.get(..10).unwrap_or_default(),
+                                    h.snippet
+                                )
+                            })
                             .collect::<Vec<_>>()
                             .join("\n");
-                        format!("Vault Search Results for '{}':\n{}", q, formatted)
+                        format!("Archive Search Results for '{}':\n{}", q, formatted)
                     }
-                    Err(e) => format!("Error searching vault: {}", e),
+                    Err(e) => format!("Error searching archive: {}", e),
                 }
-            } else if tool_call.to_lowercase().contains("vault_read") {
+            } else if name == "vault_read" {
                 let filename = extract_arg(&tool_call);
-                match fetch_vault_file(&client, &filename, &api_key).await {
+                match fetch_vault_file(&client, &filename, api_key).await {
                     Ok(content) => format!(
                         "Content of note {}:\n{}",
                         filename,
@@ -482,7 +1672,7 @@ async fn run_agent_loop(
                     Err(e) => format!("Error reading note {}: {}", filename, e),
                 }
             } else {
-                "Unknown tool".to_string()
+                format!("Unknown tool: {}", name)
             };
 
             history.push(Message {
@@ -492,7 +1682,7 @@ async fn run_agent_loop(
         } else {
             // No tool call -> Final Answer
             final_answer = response;
-            emit_log(&window, "synthesis", "complete", "Agent finished task.")?;
+            emit_log(window, "synthesis", "complete", "Agent finished task.")?;
             break;
         }
     }
@@ -503,14 +1693,29 @@ async fn run_agent_loop(
         summary: final_answer,
         file_path: "In Memory".to_string(),
         error: None,
+        session_id: None,
+        duplicate_of: None,
     })
 }
 
+/// The tool name of a parsed call, e.g. `vault_search` for
+/// `vault_search("query")`
+fn tool_name(tool_call: &str) -> String {
+    tool_call
+        .split('(')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase()
+}
+
 fn parse_tool_call(response: &str) -> Option<String> {
     // Look for [TOOL: name("arg")] or [TOOL: name(arg)]
     let start_pattern = "[TOOL: ";
     if let Some(start) = response.find(start_pattern) {
-        if let Some(end) = response[start..].find("]") {
+        // Prefer ")]" so JSON arguments containing "]" stay intact
+        let rest = &response[start..];
+        if let Some(end) = rest.find(")]").map(|i| i + 1).or(rest.find("]")) {
             return Some(
                 response[start + start_pattern.len()..start + end]
                     .trim()
@@ -535,10 +1740,48 @@ fn extract_arg(tool_call: &str) -> String {
     tool_call.trim().to_string()
 }
 
+/// Splits `name("text", {json})` into the quoted text and the optional JSON argument
+fn extract_args(tool_call: &str) -> (String, Option<String>) {
+    let arg = extract_arg(tool_call);
+    let inner = match (tool_call.find('('), tool_call.rfind(')')) {
+        (Some(start), Some(end)) if start < end => tool_call[start + 1..end].trim(),
+        _ => return (arg, None),
+    };
+    if let Some(quoted) = inner.strip_prefix('"') {
+        if let Some(close) = quoted.find('"') {
+            let text = quoted[..close].to_string();
+            let json = quoted[close + 1..].trim().trim_start_matches(',').trim();
+            if json.starts_with('{') {
+                return (text, Some(json.to_string()));
+            }
+            return (text, None);
+        }
+    }
+    if inner.starts_with('{') {
+        return (String::new(), Some(inner.to_string()));
+    }
+    (arg, None)
+}
+
+/// The JSON options of a tool call, or their defaults when there are none.
+/// Malformed options are an error, so the model learns to fix them.
+fn tool_options<T: serde::de::DeserializeOwned + Default>(
+    json: Option<String>,
+) -> Result<T, String> {
+    match json {
+        Some(json) => {
+            serde_json::from_str(&json).map_err(|e| format!("invalid options {}: {}", json, e))
+        }
+        None => Ok(T::default()),
+    }
+}
+
 async fn call_ollama_chat(
+    app: &tauri::AppHandle,
     client: &reqwest::Client,
     messages: &[Message],
     model: &str,
+    bypass_cache: bool,
 ) -> Result<String, String> {
     let body = serde_json::json!({
         "model": model,
@@ -546,6 +1789,14 @@ async fn call_ollama_chat(
         "stream": false
     });
 
+    let cache = app.state::<LlmCache>();
+    let key = LlmCache::key("chat", &body);
+    if !bypass_cache {
+        if let Some(cached) = cache.get(&key) {
+            return Ok(cached);
+        }
+    }
+
     let res = client
         .post("http://localhost:11434/api/chat")
         .json(&body)
@@ -556,59 +1807,33 @@ async fn call_ollama_chat(
     let txt = res.text().await.map_err(|e| e.to_string())?;
     let json: serde_json::Value = serde_json::from_str(&txt).map_err(|e| e.to_string())?;
 
-    json["message"]["content"]
+    let content = json["message"]["content"]
         .as_str()
         .map(|s| s.to_string())
-        .ok_or("No content".to_string())
-}
-
-/// Simple HTML to plain text converter
-fn html_to_text(html: &str) -> String {
-    let mut result = String::new();
-    let mut in_tag = false;
-    let mut in_script = false;
-    let mut in_style = false;
-
-    for c in html.chars() {
-        match c {
-            '<' => {
-                in_tag = true;
-                let lower = html.to_lowercase();
-                if lower.contains("<script") {
-                    in_script = true;
-                }
-                if lower.contains("<style") {
-                    in_style = true;
-                }
-            }
-            '>' => {
-                in_tag = false;
-                if html.to_lowercase().contains("</script") {
-                    in_script = false;
-                }
-                if html.to_lowercase().contains("</style") {
-                    in_style = false;
-                }
-            }
-            _ if !in_tag && !in_script && !in_style => {
-                result.push(c);
-            }
-            _ => {}
-        }
-    }
-
-    // Clean up whitespace
-    result.split_whitespace().collect::<Vec<_>>().join(" ")
+        .ok_or("No content".to_string())?;
+    cache.put(&key, model, &content);
+    Ok(content)
 }
 
+/// Characters of page text handed to the summarizer
+const SUMMARY_INPUT_CHARS: usize = 8000;
+
 /// Summarize using Ollama
 async fn call_ollama_summarize(
+    app: &tauri::AppHandle,
     client: &reqwest::Client,
-    content: &str,
+    document: &Document,
     model: &str,
+    bypass_cache: bool,
 ) -> Result<String, String> {
+    let source = match &document.title {
+        Some(title) => format!("{} \"{}\"", document.describe(), title),
+        None => document.describe().to_string(),
+    };
+    // Hashes and diffs see the whole page; only the model gets a cut
+    let content: String = document.content.chars().take(SUMMARY_INPUT_CHARS).collect();
     let prompt = format!(
-        r#"Summarize the following documentation into a Reference Note format.
+        r#"Summarize the following documentation ({}) into a Reference Note format.
 
 Include:
 - Executive Summary (2-3 sentences)
@@ -619,7 +1844,7 @@ Documentation:
 {}
 
 Provide a clean, structured markdown summary:"#,
-        content
+        source, content
     );
 
     let body = serde_json::json!({
@@ -628,6 +1853,14 @@ Provide a clean, structured markdown summary:"#,
         "stream": false
     });
 
+    let cache = app.state::<LlmCache>();
+    let key = LlmCache::key("generate", &body);
+    if !bypass_cache {
+        if let Some(cached) = cache.get(&key) {
+            return Ok(cached);
+        }
+    }
+
     let response = client
         .post("http://localhost:11434/api/generate")
         .json(&body)
@@ -649,10 +1882,18 @@ Provide a clean, structured markdown summary:"#,
         )
     })?;
 
+    cache.put(&key, model, &ollama_response.response);
     Ok(ollama_response.response)
 }
 
+/// Where `save_to_obsidian` puts a new note titled `title`
+fn new_note_path(title: &str) -> String {
+    let safe_title = title.replace(['/', '\\', ':', '*', '?', '"', '<', '>', '|'], "_");
+    format!("Reference/Docs/{}.md", safe_title)
+}
+
 /// Save to Obsidian via Local REST API
+#[allow(clippy::too_many_arguments)]
 async fn save_to_obsidian(
     client: &reqwest::Client,
     title: &str,
@@ -660,10 +1901,14 @@ async fn save_to_obsidian(
     source_url: &str,
     source_title: &str,
     api_key: &str,
+    existing_path: Option<&str>,
+    fields: &[(&str, String)],
 ) -> Result<String, String> {
     let today = chrono::Local::now().format("%Y-%m-%d").to_string();
-    let safe_title = title.replace(['/', '\\', ':', '*', '?', '"', '<', '>', '|'], "_");
-    let file_path = format!("Reference/Docs/{}.md", safe_title);
+    let file_path = match existing_path {
+        Some(path) => path.to_string(),
+        None => new_note_path(title),
+    };
 
     let note_content = format!(
         r#"---
@@ -671,27 +1916,79 @@ tags: [reference, documentation, fetched]
 source: {}
 source_title: "{}"
 fetched_date: {}
-model: llama3.2
+{}model: llama3.2
 ---
 
 # {}
 
 {}
 "#,
-        source_url, source_title, today, title, content
+        source_url,
+        source_title,
+        today,
+        fields
+            .iter()
+            .map(|(key, value)| format!("{}: {}\n", key, value))
+            .collect::<String>(),
+        title,
+        content
     );
 
+    write_vault_file(client, &file_path, &note_content, api_key).await?;
+    Ok(file_path)
+}
+
+/// Stores the text of a saved note's source as the baseline that
+/// `diff_source` and the next refresh diff against. Call it only once the
+/// note is saved.
+fn record_baseline(app: &tauri::AppHandle, url: &str, document: &Document) {
+    if let Err(e) = app
+        .state::<RefreshState>()
+        .store_snapshot(url, &document.content)
+    {
+        let _ = app.emit(
+            "workflow_log",
+            WorkflowLog {
+                step: "persistence".to_string(),
+                status: "running".to_string(),
+                message: format!("Could not record baseline for {}: {}", url, e),
+            },
+        );
+    }
+}
+
+/// Frontmatter tying a note to the fetch it was saved from: the content
+/// hash refresh compares against and the WARC snapshot
+fn source_fields(
+    app: &tauri::AppHandle,
+    url: &str,
+    document: &Document,
+) -> Vec<(&'static str, String)> {
+    let mut fields = vec![("content_hash", refresh::text_hash(&document.content))];
+    if let Some(snapshot) = app.state::<Snapshots>().latest(url) {
+        fields.extend(warc::frontmatter_fields(&snapshot));
+    }
+    fields
+}
+
+/// Create or overwrite a note via the Local REST API
+async fn write_vault_file(
+    client: &reqwest::Client,
+    file_path: &str,
+    note_content: &str,
+    api_key: &str,
+) -> Result<(), String> {
     // Obsidian Local REST API endpoint
     let api_url = format!(
         "https://127.0.0.1:27124/vault/{}",
-        urlencoding::encode(&file_path)
+        urlencoding::encode(file_path)
     );
 
     let response = client
         .put(&api_url)
         .header("Authorization", format!("Bearer {}", api_key))
         .header("Content-Type", "text/markdown")
-        .body(note_content)
+        .body(note_content.to_string())
         .send()
         .await
         .map_err(|e| {
@@ -708,7 +2005,7 @@ model: llama3.2
         ));
     }
 
-    Ok(file_path)
+    Ok(())
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -716,35 +2013,225 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_opener::init())
+        .setup(|app| {
+            let data_dir = app.path().app_data_dir()?;
+            std::fs::create_dir_all(&data_dir)?;
+            app.manage(ChatStore::load(data_dir.join("chat_sessions"))?);
+            app.manage(RwLock::new(VaultGraph::default()));
+            app.manage(JobQueue::open(&data_dir.join("thefetcher.db"))?);
+            app.manage(LlmCache::open(&data_dir.join("thefetcher.db"))?);
+            app.manage(RefreshState::load(data_dir.clone())?);
+            app.manage(Politeness::load(data_dir.clone()));
+            app.manage(HeaderProfiles::load(data_dir.clone()));
+            app.manage(RepoSettings::load(data_dir.clone()));
+            app.manage(SearchProviders::load(data_dir.clone()));
+            app.manage(Archive::open(&data_dir.join("archive"))?);
+            app.manage(Snapshots::open(data_dir.join("warc"))?);
+            app.manage(HttpCache::open(app.path().app_cache_dir()?.join("http"))?);
+            // Background tasks may touch any state above, so they start last
+            tauri::async_runtime::spawn(jobs::run_worker(app.handle().clone()));
+            tauri::async_runtime::spawn(refresh::run_scheduler(app.handle().clone()));
+            tauri::async_runtime::spawn(archive::run_committer(app.handle().clone()));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             perform_search,
+            discover_sources,
             process_selection,
+            process_batch,
+            crawl_docs,
+            ingest_repo,
+            get_repo_config,
+            set_repo_config,
+            search_archive,
+            clear_archive,
+            view_snapshot,
+            clear_snapshots,
+            enqueue_job,
+            list_jobs,
+            retry_job,
+            cancel_job,
+            set_job_api_key,
+            get_refresh_config,
+            set_refresh_config,
+            refresh_sources_now,
+            diff_source,
+            clear_cache,
+            clear_llm_cache,
+            get_politeness_config,
+            set_politeness_config,
+            get_header_config,
+            set_header_config,
+            list_header_profiles,
+            get_search_provider_config,
+            set_search_provider_config,
+            list_search_providers,
             search_vault,
             chat_with_vault,
+            list_chat_sessions,
+            get_chat_session,
+            delete_chat_session,
+            refresh_vault_graph,
+            get_note_links,
             run_agent_loop
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Pages staged since the last archive commit would be lost
+            if let tauri::RunEvent::Exit = event {
+                let _ = app.state::<Archive>().flush();
+            }
+        });
 }
 
-async fn fetch_url_content(client: &reqwest::Client, url: &str) -> Result<String, String> {
-    let response = client
-        .get(url)
-        .header(
-            "User-Agent",
-            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36",
-        )
-        .send()
-        .await
-        .map_err(|e| format!("Failed to fetch URL: {}", e))?;
+async fn fetch_url_content(
+    app: &tauri::AppHandle,
+    client: &reqwest::Client,
+    url: &str,
+    headless: bool,
+    cache: CacheMode,
+) -> Result<Document, String> {
+    Ok(fetch_page(app, client, url, headless, cache)
+        .await?
+        .document)
+}
 
-    let html = response
-        .text()
-        .await
-        .map_err(|e| format!("Failed to read response: {}", e))?;
+/// A fetched page and the whole document extracted from it
+#[derive(Clone)]
+struct FetchedPage {
+    /// The HTTP response as the server sent it
+    response: fetch::RawResponse,
+    /// The DOM after rendering, when the static HTML had too little text;
+    /// the document is then extracted from it
+    rendered: Option<String>,
+    document: Document,
+}
+
+impl FetchedPage {
+    /// The HTML the document was extracted from
+    fn html(&self, url: &str) -> String {
+        match &self.rendered {
+            Some(dom) => dom.clone(),
+            None => charset::decode(
+                &self.response.body,
+                self.response.header("content-type"),
+                url,
+            ),
+        }
+    }
+}
+
+/// Fetches and extracts a page, and stores a snapshot of the response
+/// (plus the rendered DOM) and the text in the archive
+async fn fetch_page(
+    app: &tauri::AppHandle,
+    client: &reqwest::Client,
+    url: &str,
+    headless: bool,
+    cache: CacheMode,
+) -> Result<FetchedPage, String> {
+    let page = fetch_full_page(app, client, url, headless, cache).await?;
+
+    // The raw response goes to a WARC snapshot, the text to the archive
+    let snapshots = app.state::<Snapshots>().inner().clone();
+    let archive = app.state::<Archive>().inner().clone();
+    let (page_url, stored) = (url.to_string(), page.clone());
+    let errors = tokio::task::spawn_blocking(move || {
+        let snapshot = snapshots
+            .write(&page_url, &stored.response, stored.rendered.as_deref())
+            .err();
+        let text = archive.add(&page_url, &stored.document).err();
+        snapshot.into_iter().chain(text).collect::<Vec<_>>()
+    })
+    .await
+    .unwrap_or_else(|e| vec![e.to_string()]);
+    for e in errors {
+        let _ = app.emit(
+            "workflow_log",
+            WorkflowLog {
+                step: "acquisition".to_string(),
+                status: "running".to_string(),
+                message: format!("Could not archive {}: {}", url, e),
+            },
+        );
+    }
+
+    Ok(page)
+}
+
+/// Static fetch first; HTML that yields almost no text (a JavaScript app
+/// shell) is rendered in a local Chromium when one is installed
+async fn fetch_full_page(
+    app: &tauri::AppHandle,
+    client: &reqwest::Client,
+    url: &str,
+    headless: bool,
+    cache: CacheMode,
+) -> Result<FetchedPage, String> {
+    let response = fetch::fetch_raw(app, client, url, cache).await?;
+    let document = response_document(&response, url).await?;
+    if document.kind != DocumentKind::Html
+        || document.content.trim().chars().count() >= render::MIN_STATIC_CHARS
+        || render::find_browser().is_none()
+    {
+        return Ok(FetchedPage {
+            response,
+            rendered: None,
+            document,
+        });
+    }
+
+    let _ = app.emit(
+        "workflow_log",
+        WorkflowLog {
+            step: "acquisition".to_string(),
+            status: "running".to_string(),
+            message: format!("Little static text, rendering {} in Chromium...", url),
+        },
+    );
+    let rendered = {
+        let profile = app.state::<HeaderProfiles>().profile_for(url);
+        let _permit = app
+            .state::<Politeness>()
+            .acquire(client, url, &profile)
+            .await?;
+        render::render_page(url, headless, &profile.user_agent).await
+    };
+    match rendered {
+        Ok(html) => Ok(FetchedPage {
+            document: rendered_document(&html, url)?,
+            rendered: Some(html),
+            response,
+        }),
+        // The static result is still better than nothing
+        Err(e) => {
+            let _ = app.emit(
+                "workflow_log",
+                WorkflowLog {
+                    step: "acquisition".to_string(),
+                    status: "running".to_string(),
+                    message: format!("Rendering failed, using static HTML: {}", e),
+                },
+            );
+            Ok(FetchedPage {
+                response,
+                rendered: None,
+                document,
+            })
+        }
+    }
+}
+
+fn rendered_document(html: &str, url: &str) -> Result<Document, String> {
+    extract::extract_document(html.as_bytes(), Some("text/html; charset=utf-8"), url)
+}
 
-    // Simple HTML to plain text conversion
-    Ok(html_to_text(&html).chars().take(8000).collect())
+async fn response_document(response: &fetch::RawResponse, url: &str) -> Result<Document, String> {
+    if response.status >= 400 {
+        return Err(format!("Failed to fetch URL: HTTP {}", response.status));
+    }
+    extract::extract_document_async(&response.body, response.header("content-type"), url).await
 }
 
 async fn fetch_vault_file(
@@ -771,3 +2258,35 @@ async fn fetch_vault_file(
         .await
         .map_err(|e| format!("Failed to read vault response: {}", e))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tool_calls_dispatch_on_the_name_only() {
+        let call = |response: &str| tool_name(&parse_tool_call(response).unwrap());
+        assert_eq!(call(r#"[TOOL: search("vault setup")]"#), "search");
+        assert_eq!(
+            call(r#"[TOOL: fetch("https://x.example/search")]"#),
+            "fetch"
+        );
+        assert_eq!(
+            call(r#"I'll check notes. [TOOL: vault_search("fetch archive")]"#),
+            "vault_search"
+        );
+        assert_eq!(call(r#"[TOOL: Archive_Search("x")]"#), "archive_search");
+        assert_eq!(call(r#"[TOOL: vault_read ("Note.md")]"#), "vault_read");
+        assert_eq!(call("[TOOL: searchweb(x)]"), "searchweb");
+    }
+
+    #[test]
+    fn malformed_tool_options_are_reported() {
+        let options: SearchOptions = tool_options(None).unwrap();
+        assert_eq!(options.pageno, None);
+        let options: SearchOptions = tool_options(Some(r#"{"pageno": 2}"#.into())).unwrap();
+        assert_eq!(options.pageno, Some(2));
+        let err = tool_options::<SearchOptions>(Some(r#"{"pageno": "two"}"#.into())).unwrap_err();
+        assert!(err.contains("invalid options"));
+    }
+}
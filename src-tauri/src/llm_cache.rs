@@ -0,0 +1,191 @@
+use std::path::Path;
+use std::sync::Mutex;
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::refresh::content_hash;
+
+/// Entries older than this are dropped on the next write
+const MAX_AGE_DAYS: i64 = 30;
+/// Total stored response text before least-recently-used entries go
+const MAX_BYTES: i64 = 64 * 1024 * 1024;
+
+/// Ollama responses keyed by a hash of the endpoint and the full request body
+/// (model, prompt or messages, options). Shares `<app data>/thefetcher.db`
+/// with the job queue.
+pub struct LlmCache {
+    conn: Mutex<Connection>,
+}
+
+impl LlmCache {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let conn =
+            Connection::open(path).map_err(|e| format!("Failed to open LLM cache: {}", e))?;
+        conn.busy_timeout(crate::jobs::BUSY_TIMEOUT)
+            .map_err(|e| format!("Failed to open LLM cache: {}", e))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS llm_cache (
+                key TEXT PRIMARY KEY,
+                model TEXT NOT NULL,
+                response TEXT NOT NULL,
+                size INTEGER NOT NULL,
+                created_at INTEGER NOT NULL,
+                last_used INTEGER NOT NULL
+            );",
+        )
+        .map_err(|e| format!("Failed to initialise LLM cache: {}", e))?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// serde_json maps are sorted, so equal requests serialize identically
+    pub fn key(endpoint: &str, body: &serde_json::Value) -> String {
+        content_hash(&format!("{}\n{}", endpoint, body))
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        let conn = self.conn.lock().unwrap();
+        let response = conn
+            .query_row(
+                "SELECT response FROM llm_cache WHERE key = ?1",
+                params![key],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()
+            .ok()
+            .flatten()?;
+        let _ = conn.execute(
+            "UPDATE llm_cache SET last_used = ?2 WHERE key = ?1",
+            params![key, chrono::Utc::now().timestamp()],
+        );
+        Some(response)
+    }
+
+    pub fn put(&self, key: &str, model: &str, response: &str) {
+        let now = chrono::Utc::now().timestamp();
+        let conn = self.conn.lock().unwrap();
+        let _ = conn.execute(
+            "INSERT OR REPLACE INTO llm_cache (key, model, response, size, created_at, last_used)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
+            params![key, model, response, response.len() as i64, now],
+        );
+        let _ = evict(&conn, now, MAX_BYTES);
+    }
+
+    /// Removes every entry; returns how many were dropped
+    pub fn clear(&self) -> Result<usize, String> {
+        self.conn
+            .lock()
+            .unwrap()
+            .execute("DELETE FROM llm_cache", [])
+            .map_err(|e| format!("Failed to clear LLM cache: {}", e))
+    }
+}
+
+fn evict(conn: &Connection, now: i64, max_bytes: i64) -> rusqlite::Result<()> {
+    conn.execute(
+        "DELETE FROM llm_cache WHERE created_at < ?1",
+        params![now - MAX_AGE_DAYS * 24 * 3600],
+    )?;
+
+    let total: i64 = conn.query_row("SELECT COALESCE(SUM(size), 0) FROM llm_cache", [], |r| {
+        r.get(0)
+    })?;
+    if total <= max_bytes {
+        return Ok(());
+    }
+    let mut excess = total - max_bytes;
+    let mut stale = Vec::new();
+    {
+        let mut stmt = conn.prepare("SELECT key, size FROM llm_cache ORDER BY last_used")?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            if excess <= 0 {
+                break;
+            }
+            stale.push(row.get::<_, String>(0)?);
+            excess -= row.get::<_, i64>(1)?;
+        }
+    }
+    for key in stale {
+        conn.execute("DELETE FROM llm_cache WHERE key = ?1", params![key])?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache() -> LlmCache {
+        LlmCache::open(Path::new(":memory:")).unwrap()
+    }
+
+    fn keys(cache: &LlmCache) -> Vec<String> {
+        let conn = cache.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT key FROM llm_cache ORDER BY key")
+            .unwrap();
+        let keys = stmt
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<Vec<String>, _>>()
+            .unwrap();
+        keys
+    }
+
+    #[test]
+    fn keys_ignore_option_order() {
+        let a: serde_json::Value = serde_json::from_str(
+            r#"{"model": "m", "prompt": "p", "options": {"temperature": 0, "seed": 1}}"#,
+        )
+        .unwrap();
+        let b: serde_json::Value = serde_json::from_str(
+            r#"{"options": {"seed": 1, "temperature": 0}, "prompt": "p", "model": "m"}"#,
+        )
+        .unwrap();
+        assert_eq!(LlmCache::key("generate", &a), LlmCache::key("generate", &b));
+        assert_ne!(LlmCache::key("generate", &a), LlmCache::key("chat", &a));
+
+        let c = serde_json::json!({"model": "m", "prompt": "p", "options": {"seed": 2, "temperature": 0}});
+        assert_ne!(LlmCache::key("generate", &a), LlmCache::key("generate", &c));
+    }
+
+    #[test]
+    fn old_entries_are_dropped() {
+        let cache = cache();
+        cache.put("old", "m", "response");
+        let now = chrono::Utc::now().timestamp();
+        cache
+            .conn
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE llm_cache SET created_at = ?1",
+                params![now - MAX_AGE_DAYS * 24 * 3600 - 1],
+            )
+            .unwrap();
+        assert_eq!(cache.get("old").as_deref(), Some("response"));
+
+        cache.put("new", "m", "response");
+        assert_eq!(keys(&cache), vec!["new"]);
+    }
+
+    #[test]
+    fn least_recently_used_entries_go_first_when_full() {
+        let cache = cache();
+        let conn = cache.conn.lock().unwrap();
+        for (key, last_used) in [("a", 3), ("b", 1), ("c", 2)] {
+            conn.execute(
+                "INSERT INTO llm_cache (key, model, response, size, created_at, last_used)
+                 VALUES (?1, 'm', 'xxxx', 4, ?2, ?2)",
+                params![key, chrono::Utc::now().timestamp() + last_used],
+            )
+            .unwrap();
+        }
+        evict(&conn, chrono::Utc::now().timestamp(), 8).unwrap();
+        drop(conn);
+        assert_eq!(keys(&cache), vec!["a", "c"]);
+    }
+}
@@ -0,0 +1,796 @@
+use std::sync::RwLock;
+
+use serde_json::Value;
+
+use crate::vault::VaultGraph;
+
+const METHODS: &[&str] = &[
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+#[derive(Clone, Debug)]
+pub struct Param {
+    pub name: String,
+    pub location: String,
+    pub required: bool,
+    pub ty: String,
+    pub description: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct Endpoint {
+    pub method: String,
+    pub path: String,
+    pub summary: String,
+    pub description: String,
+    pub operation_id: Option<String>,
+    pub deprecated: bool,
+    pub params: Vec<Param>,
+    /// (media type, schema type)
+    pub request_body: Vec<(String, String)>,
+    /// (status, description, schema type)
+    pub responses: Vec<(String, String, Option<String>)>,
+    pub security: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TagGroup {
+    pub name: String,
+    pub description: String,
+    pub endpoints: Vec<Endpoint>,
+}
+
+#[derive(Clone, Debug)]
+pub struct AuthScheme {
+    pub name: String,
+    pub kind: String,
+    pub detail: String,
+}
+
+/// The parts of an OpenAPI 3 / Swagger 2 document a reference note needs
+#[derive(Clone, Debug)]
+pub struct ApiSpec {
+    pub title: String,
+    pub version: String,
+    /// "OpenAPI 3.0.3" or "Swagger 2.0"
+    pub spec_version: String,
+    pub description: String,
+    pub servers: Vec<String>,
+    pub auth: Vec<AuthScheme>,
+    pub tags: Vec<TagGroup>,
+    /// (name, schema)
+    schemas: Vec<(String, Value)>,
+}
+
+fn text(value: &Value) -> String {
+    value.as_str().unwrap_or_default().trim().to_string()
+}
+
+/// Follows a local `$ref` (`#/components/...`); external refs are left as-is
+fn resolve<'a>(root: &'a Value, value: &'a Value) -> &'a Value {
+    let mut current = value;
+    // Bounded so reference cycles can't loop forever
+    for _ in 0..8 {
+        match current["$ref"].as_str().and_then(|r| r.strip_prefix('#')) {
+            Some(pointer) => match root.pointer(pointer) {
+                Some(target) => current = target,
+                None => break,
+            },
+            None => break,
+        }
+    }
+    current
+}
+
+/// Short type description: `Pet`, `array<Pet>`, `string(date-time)`, `A | B`
+fn schema_type(schema: &Value) -> String {
+    if let Some(r) = schema["$ref"].as_str() {
+        return r.rsplit('/').next().unwrap_or(r).to_string();
+    }
+    for (key, sep) in [("allOf", " & "), ("oneOf", " | "), ("anyOf", " | ")] {
+        if let Some(parts) = schema[key].as_array() {
+            return parts.iter().map(schema_type).collect::<Vec<_>>().join(sep);
+        }
+    }
+    let ty = match &schema["type"] {
+        Value::String(t) => t.clone(),
+        // OpenAPI 3.1 allows ["string", "null"]
+        Value::Array(types) => types
+            .iter()
+            .filter_map(|t| t.as_str())
+            .collect::<Vec<_>>()
+            .join(" | "),
+        _ if schema.get("properties").is_some() => "object".to_string(),
+        _ => return "any".to_string(),
+    };
+    match ty.as_str() {
+        "array" => format!("array<{}>", schema_type(&schema["items"])),
+        _ => {
+            let mut out = match schema["format"].as_str() {
+                Some(format) => format!("{}({})", ty, format),
+                None => ty,
+            };
+            if let Some(values) = schema["enum"].as_array() {
+                let values: Vec<String> = values
+                    .iter()
+                    .take(8)
+                    .map(|v| v.as_str().map(|s| s.to_string()).unwrap_or(v.to_string()))
+                    .collect();
+                out = format!("{} ({})", out, values.join(" | "));
+            }
+            out
+        }
+    }
+}
+
+fn table_cell(s: &str) -> String {
+    s.replace('|', "\\|").replace('\n', " ")
+}
+
+impl ApiSpec {
+    /// None when the document is not an OpenAPI 3 or Swagger 2 spec
+    pub fn parse(root: &Value) -> Option<ApiSpec> {
+        let (spec_version, v3) = if let Some(v) = root["openapi"].as_str() {
+            (format!("OpenAPI {}", v), true)
+        } else if let Some(v) = root["swagger"].as_str() {
+            (format!("Swagger {}", v), false)
+        } else {
+            return None;
+        };
+        root.get("paths")?.as_object()?;
+
+        let servers = if v3 {
+            root["servers"]
+                .as_array()
+                .map(|s| s.iter().map(|s| text(&s["url"])).collect())
+                .unwrap_or_default()
+        } else {
+            let host = text(&root["host"]);
+            let base = text(&root["basePath"]);
+            let schemes: Vec<String> = root["schemes"]
+                .as_array()
+                .map(|s| s.iter().map(text).collect())
+                .unwrap_or_else(|| vec!["https".to_string()]);
+            if host.is_empty() {
+                vec![base].into_iter().filter(|b| !b.is_empty()).collect()
+            } else {
+                schemes
+                    .iter()
+                    .map(|scheme| format!("{}://{}{}", scheme, host, base))
+                    .collect()
+            }
+        };
+
+        let schemes = if v3 {
+            &root["components"]["securitySchemes"]
+        } else {
+            &root["securityDefinitions"]
+        };
+        let auth = schemes
+            .as_object()
+            .map(|map| {
+                map.iter()
+                    .map(|(name, scheme)| {
+                        let scheme = resolve(root, scheme);
+                        let kind = text(&scheme["type"]);
+                        let detail = match kind.as_str() {
+                            "apiKey" => {
+                                format!("`{}` in {}", text(&scheme["name"]), text(&scheme["in"]))
+                            }
+                            "http" => {
+                                let mut d = text(&scheme["scheme"]);
+                                if let Some(f) = scheme["bearerFormat"].as_str() {
+                                    d = format!("{} ({})", d, f);
+                                }
+                                d
+                            }
+                            "oauth2" => {
+                                let flows: Vec<String> = if v3 {
+                                    scheme["flows"]
+                                        .as_object()
+                                        .map(|f| f.keys().cloned().collect())
+                                        .unwrap_or_default()
+                                } else {
+                                    vec![text(&scheme["flow"])]
+                                };
+                                format!("flows: {}", flows.join(", "))
+                            }
+                            "openIdConnect" => text(&scheme["openIdConnectUrl"]),
+                            _ => String::new(),
+                        };
+                        AuthScheme {
+                            name: name.clone(),
+                            kind,
+                            detail,
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        // Declared tag order first, then tags as they appear on operations
+        let mut tags: Vec<TagGroup> = root["tags"]
+            .as_array()
+            .map(|tags| {
+                tags.iter()
+                    .map(|t| TagGroup {
+                        name: text(&t["name"]),
+                        description: text(&t["description"]),
+                        endpoints: Vec::new(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let global_security = &root["security"];
+        for (path, item) in root["paths"].as_object()? {
+            let item = resolve(root, item);
+            let shared_params = item["parameters"].as_array().cloned().unwrap_or_default();
+            for method in METHODS {
+                let Some(op) = item.get(*method) else {
+                    continue;
+                };
+                let endpoint =
+                    parse_operation(root, v3, method, path, op, &shared_params, global_security);
+                let tag = op["tags"]
+                    .as_array()
+                    .and_then(|t| t.first())
+                    .map(text)
+                    .filter(|t| !t.is_empty())
+                    .unwrap_or_else(|| "default".to_string());
+                match tags.iter_mut().find(|t| t.name == tag) {
+                    Some(group) => group.endpoints.push(endpoint),
+                    None => tags.push(TagGroup {
+                        name: tag,
+                        description: String::new(),
+                        endpoints: vec![endpoint],
+                    }),
+                }
+            }
+        }
+        tags.retain(|t| !t.endpoints.is_empty());
+
+        let definitions = if v3 {
+            &root["components"]["schemas"]
+        } else {
+            &root["definitions"]
+        };
+        let schemas = definitions
+            .as_object()
+            .map(|m| m.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+            .unwrap_or_default();
+
+        Some(ApiSpec {
+            title: text(&root["info"]["title"]),
+            version: text(&root["info"]["version"]),
+            spec_version,
+            description: text(&root["info"]["description"]),
+            servers,
+            auth,
+            tags,
+            schemas,
+        })
+    }
+
+    fn overview(&self) -> String {
+        let mut out = Vec::new();
+        out.push(format!(
+            "**Version:** {} · **Spec:** {}",
+            if self.version.is_empty() {
+                "n/a"
+            } else {
+                &self.version
+            },
+            self.spec_version
+        ));
+        if !self.description.is_empty() {
+            out.push(String::new());
+            out.push(self.description.clone());
+        }
+        if !self.servers.is_empty() {
+            out.push(String::new());
+            out.push("## Servers".to_string());
+            out.extend(self.servers.iter().map(|s| format!("- `{}`", s)));
+        }
+        if !self.auth.is_empty() {
+            out.push(String::new());
+            out.push("## Authentication".to_string());
+            out.extend(self.auth.iter().map(|a| {
+                if a.detail.is_empty() {
+                    format!("- **{}** ({})", a.name, a.kind)
+                } else {
+                    format!("- **{}** ({}): {}", a.name, a.kind, a.detail)
+                }
+            }));
+        }
+        out.join("\n")
+    }
+
+    fn schemas_markdown(&self, only: Option<&[String]>) -> String {
+        let mut out = Vec::new();
+        for (name, schema) in &self.schemas {
+            if only.is_some_and(|names| !names.contains(name)) {
+                continue;
+            }
+            out.push(format!("### {}", name));
+            let description = text(&schema["description"]);
+            if !description.is_empty() {
+                out.push(description);
+            }
+            let required: Vec<&str> = schema["required"]
+                .as_array()
+                .map(|r| r.iter().filter_map(|v| v.as_str()).collect())
+                .unwrap_or_default();
+            match schema["properties"].as_object() {
+                Some(props) if !props.is_empty() => {
+                    out.push("| Property | Type | Required | Description |".to_string());
+                    out.push("|---|---|---|---|".to_string());
+                    for (prop, def) in props {
+                        out.push(format!(
+                            "| `{}` | {} | {} | {} |",
+                            prop,
+                            table_cell(&schema_type(def)),
+                            if required.contains(&prop.as_str()) {
+                                "yes"
+                            } else {
+                                ""
+                            },
+                            table_cell(&text(&def["description"]))
+                        ));
+                    }
+                }
+                _ => out.push(format!("Type: `{}`", schema_type(schema))),
+            }
+            out.push(String::new());
+        }
+        out.join("\n").trim_end().to_string()
+    }
+
+    /// Schema names referenced by the endpoints of one tag (one level deep)
+    fn schemas_used(&self, group: &TagGroup) -> Vec<String> {
+        let mut names = Vec::new();
+        let mut consider = |ty: &str| {
+            for (name, _) in &self.schemas {
+                let mentioned = ty
+                    .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.' || c == '-'))
+                    .any(|part| part == name);
+                if mentioned && !names.contains(name) {
+                    names.push(name.clone());
+                }
+            }
+        };
+        for e in &group.endpoints {
+            e.params.iter().for_each(|p| consider(&p.ty));
+            e.request_body.iter().for_each(|(_, ty)| consider(ty));
+            e.responses
+                .iter()
+                .filter_map(|(_, _, ty)| ty.as_deref())
+                .for_each(&mut consider);
+        }
+        names
+    }
+
+    pub fn endpoint_count(&self) -> usize {
+        self.tags.iter().map(|t| t.endpoints.len()).sum()
+    }
+
+    /// Everything in one note: overview, endpoints grouped by tag, schemas
+    pub fn to_markdown(&self) -> String {
+        let mut out = vec![self.overview()];
+        for group in &self.tags {
+            out.push(tag_markdown(group, "##"));
+        }
+        if !self.schemas.is_empty() {
+            out.push(format!("## Schemas\n\n{}", self.schemas_markdown(None)));
+        }
+        out.join("\n\n")
+    }
+
+    /// One note per tag: (tag, body) with the schemas that tag uses
+    pub fn tag_notes(&self) -> Vec<(String, String)> {
+        self.tags
+            .iter()
+            .map(|group| {
+                let mut body = tag_markdown(group, "#");
+                let used = self.schemas_used(group);
+                if !used.is_empty() {
+                    body.push_str(&format!(
+                        "\n\n## Schemas\n\n{}",
+                        self.schemas_markdown(Some(&used))
+                    ));
+                }
+                (group.name.clone(), body)
+            })
+            .collect()
+    }
+
+    /// Overview note linking to the per-tag notes
+    pub fn index_markdown(&self, links: &[(String, String)]) -> String {
+        let mut out = vec![self.overview(), "## Endpoints by tag".to_string()];
+        for (tag, path) in links {
+            let count = self
+                .tags
+                .iter()
+                .find(|t| &t.name == tag)
+                .map_or(0, |t| t.endpoints.len());
+            out.push(format!(
+                "- [[{}|{}]] ({} endpoints)",
+                path.trim_end_matches(".md"),
+                tag,
+                count
+            ));
+        }
+        out.join("\n\n")
+    }
+}
+
+fn tag_markdown(group: &TagGroup, level: &str) -> String {
+    let mut out = vec![format!("{} {}", level, group.name)];
+    if !group.description.is_empty() {
+        out.push(group.description.clone());
+    }
+    for e in &group.endpoints {
+        out.push(endpoint_markdown(e, &format!("{}#", level)));
+    }
+    out.join("\n\n")
+}
+
+fn endpoint_markdown(e: &Endpoint, level: &str) -> String {
+    let mut out = vec![format!(
+        "{} `{} {}`{}",
+        level,
+        e.method.to_uppercase(),
+        e.path,
+        if e.deprecated { " (deprecated)" } else { "" }
+    )];
+    if !e.summary.is_empty() {
+        out.push(format!("**{}**", e.summary));
+    }
+    if !e.description.is_empty() && e.description != e.summary {
+        out.push(e.description.clone());
+    }
+    if let Some(id) = &e.operation_id {
+        out.push(format!("Operation ID: `{}`", id));
+    }
+    if !e.security.is_empty() {
+        out.push(format!("Auth: {}", e.security.join(", ")));
+    }
+    if !e.params.is_empty() {
+        let mut table = vec![
+            "| Parameter | In | Type | Required | Description |".to_string(),
+            "|---|---|---|---|---|".to_string(),
+        ];
+        table.extend(e.params.iter().map(|p| {
+            format!(
+                "| `{}` | {} | {} | {} | {} |",
+                p.name,
+                p.location,
+                table_cell(&p.ty),
+                if p.required { "yes" } else { "" },
+                table_cell(&p.description)
+            )
+        }));
+        out.push(table.join("\n"));
+    }
+    if !e.request_body.is_empty() {
+        let bodies: Vec<String> = e
+            .request_body
+            .iter()
+            .map(|(media, ty)| format!("- `{}`: {}", media, ty))
+            .collect();
+        out.push(format!("Request body:\n{}", bodies.join("\n")));
+    }
+    if !e.responses.is_empty() {
+        let responses: Vec<String> = e
+            .responses
+            .iter()
+            .map(|(code, description, ty)| match ty {
+                Some(ty) => format!("- `{}` {} → {}", code, description, ty),
+                None => format!("- `{}` {}", code, description),
+            })
+            .collect();
+        out.push(format!("Responses:\n{}", responses.join("\n")));
+    }
+    out.join("\n\n")
+}
+
+fn parse_operation(
+    root: &Value,
+    v3: bool,
+    method: &str,
+    path: &str,
+    op: &Value,
+    shared_params: &[Value],
+    global_security: &Value,
+) -> Endpoint {
+    let mut params: Vec<Param> = Vec::new();
+    let mut request_body = Vec::new();
+
+    // Operation parameters override path-level ones with the same name/location
+    let own: Vec<&Value> = op["parameters"]
+        .as_array()
+        .map(|p| p.iter().map(|p| resolve(root, p)).collect())
+        .unwrap_or_default();
+    let inherited = shared_params.iter().map(|p| resolve(root, p)).filter(|p| {
+        !own.iter()
+            .any(|o| o["name"] == p["name"] && o["in"] == p["in"])
+    });
+    for p in inherited.chain(own.iter().copied()) {
+        let location = text(&p["in"]);
+        if !v3 && location == "body" {
+            let consumes = op["consumes"]
+                .as_array()
+                .or(root["consumes"].as_array())
+                .and_then(|c| c.first())
+                .map(text)
+                .unwrap_or_else(|| "application/json".to_string());
+            request_body.push((consumes, schema_type(&p["schema"])));
+            continue;
+        }
+        // Swagger 2 keeps the type on the parameter, OpenAPI 3 in `schema`
+        let ty = if p.get("schema").is_some() {
+            schema_type(&p["schema"])
+        } else {
+            schema_type(p)
+        };
+        params.push(Param {
+            name: text(&p["name"]),
+            location,
+            required: p["required"].as_bool().unwrap_or(false),
+            ty,
+            description: text(&p["description"]),
+        });
+    }
+
+    if v3 {
+        let body = resolve(root, &op["requestBody"]);
+        if let Some(content) = body["content"].as_object() {
+            for (media, def) in content {
+                request_body.push((media.clone(), schema_type(&def["schema"])));
+            }
+        }
+    }
+
+    let responses = op["responses"]
+        .as_object()
+        .map(|map| {
+            map.iter()
+                .map(|(code, response)| {
+                    let response = resolve(root, response);
+                    let schema = if v3 {
+                        response["content"]
+                            .as_object()
+                            .and_then(|c| c.values().next())
+                            .map(|media| &media["schema"])
+                    } else {
+                        response.get("schema")
+                    };
+                    (
+                        code.clone(),
+                        text(&response["description"]),
+                        schema.filter(|s| !s.is_null()).map(schema_type),
+                    )
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let security = op
+        .get("security")
+        .unwrap_or(global_security)
+        .as_array()
+        .map(|reqs| {
+            reqs.iter()
+                .filter_map(|r| r.as_object())
+                .flat_map(|r| r.keys().cloned())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Endpoint {
+        method: method.to_string(),
+        path: path.to_string(),
+        summary: text(&op["summary"]),
+        description: text(&op["description"]),
+        operation_id: op["operationId"].as_str().map(|s| s.to_string()),
+        deprecated: op["deprecated"].as_bool().unwrap_or(false),
+        params,
+        request_body,
+        responses,
+        security,
+    }
+}
+
+/// Writes an API reference: one note, or a folder with a note per tag plus
+/// an index note that carries the `source` (so dedupe and refresh see one note).
+/// Tag notes are added to the vault graph here. Returns the path of the main note.
+#[allow(clippy::too_many_arguments)]
+pub async fn save_api_notes(
+    client: &reqwest::Client,
+    graph: &RwLock<VaultGraph>,
+    spec: &ApiSpec,
+    source_url: &str,
+    source_title: &str,
+    api_key: &str,
+    existing_path: Option<&str>,
+    split_by_tag: bool,
+    fields: &[(&str, String)],
+) -> Result<String, String> {
+    let title = if spec.title.is_empty() {
+        source_title.to_string()
+    } else {
+        format!("{} API", spec.title.trim_end_matches(" API"))
+    };
+    if !split_by_tag || spec.tags.len() < 2 {
+        return crate::save_to_obsidian(
+            client,
+            &title,
+            &spec.to_markdown(),
+            source_url,
+            source_title,
+            api_key,
+            existing_path,
+            fields,
+        )
+        .await;
+    }
+
+    let folder = format!(
+        "Reference/Docs/{}",
+        title.replace(['/', '\\', ':', '*', '?', '"', '<', '>', '|'], "_")
+    );
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    // Quoted and escaped as YAML needs; titles may hold quotes or colons
+    let api_field = serde_yaml::to_string(&title).map_err(|e| e.to_string())?;
+    let api_field = api_field.trim_end();
+    let mut links = Vec::new();
+    for (tag, body) in spec.tag_notes() {
+        let safe_tag = tag.replace(['/', '\\', ':', '*', '?', '"', '<', '>', '|', '#'], "_");
+        let path = format!("{}/{}.md", folder, safe_tag);
+        let note = format!(
+            "---\ntags: [reference, api]\napi: {}\nfetched_date: {}\n---\n\n{}\n",
+            api_field, today, body
+        );
+        crate::write_vault_file(client, &path, &note, api_key).await?;
+        crate::index_saved_note(graph, client, &path, api_key).await;
+        links.push((tag, path));
+    }
+
+    crate::save_to_obsidian(
+        client,
+        &title,
+        &spec.index_markdown(&links),
+        source_url,
+        source_title,
+        api_key,
+        existing_path,
+        fields,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn openapi_3_specs_are_parsed() {
+        let root = json!({
+            "openapi": "3.0.3",
+            "info": { "title": "Pets", "version": "1.2", "description": "Pet store" },
+            "servers": [{ "url": "https://api.pets.example/v1" }],
+            "tags": [{ "name": "pets", "description": "Pet operations" }, { "name": "unused" }],
+            "security": [{ "bearer": [] }],
+            "components": {
+                "securitySchemes": {
+                    "bearer": { "type": "http", "scheme": "bearer", "bearerFormat": "JWT" }
+                },
+                "parameters": {
+                    "Limit": { "name": "limit", "in": "query", "schema": { "type": "integer", "format": "int32" } }
+                },
+                "schemas": {
+                    "Pet": { "type": "object", "properties": { "name": { "type": "string" } } }
+                }
+            },
+            "paths": {
+                "/pets": {
+                    "parameters": [{ "name": "limit", "in": "query", "schema": { "type": "string" } }],
+                    "get": {
+                        "tags": ["pets"],
+                        "summary": "List pets",
+                        "parameters": [{ "$ref": "#/components/parameters/Limit" }],
+                        "responses": {
+                            "200": {
+                                "description": "OK",
+                                "content": { "application/json": { "schema": { "type": "array", "items": { "$ref": "#/components/schemas/Pet" } } } }
+                            }
+                        }
+                    },
+                    "post": {
+                        "tags": ["pets"],
+                        "security": [],
+                        "requestBody": { "content": { "application/json": { "schema": { "$ref": "#/components/schemas/Pet" } } } },
+                        "responses": { "201": { "description": "Created" } }
+                    }
+                },
+                "/health": { "get": { "deprecated": true, "responses": {} } }
+            }
+        });
+        let spec = ApiSpec::parse(&root).unwrap();
+        assert_eq!(spec.title, "Pets");
+        assert_eq!(spec.spec_version, "OpenAPI 3.0.3");
+        assert_eq!(spec.servers, ["https://api.pets.example/v1"]);
+        assert_eq!(spec.auth[0].kind, "http");
+        assert_eq!(spec.auth[0].detail, "bearer (JWT)");
+
+        // Declared tags keep their order, tags without operations go
+        let names: Vec<_> = spec.tags.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["pets", "default"]);
+        assert_eq!(spec.tags[0].description, "Pet operations");
+
+        let list = &spec.tags[0].endpoints[0];
+        assert_eq!((list.method.as_str(), list.path.as_str()), ("get", "/pets"));
+        // The operation's own parameter replaces the path-level one
+        assert_eq!(list.params.len(), 1);
+        assert_eq!(list.params[0].ty, "integer(int32)");
+        assert_eq!(list.responses[0].2.as_deref(), Some("array<Pet>"));
+        assert_eq!(list.security, ["bearer"]);
+
+        let create = &spec.tags[0].endpoints[1];
+        assert_eq!(
+            create.request_body,
+            [("application/json".to_string(), "Pet".to_string())]
+        );
+        assert!(create.security.is_empty());
+        assert_eq!(create.responses[0].2, None);
+        assert!(spec.tags[1].endpoints[0].deprecated);
+    }
+
+    #[test]
+    fn swagger_2_specs_are_parsed() {
+        let root = json!({
+            "swagger": "2.0",
+            "info": { "title": "Legacy", "version": "1" },
+            "host": "legacy.example",
+            "basePath": "/api",
+            "schemes": ["http", "https"],
+            "securityDefinitions": { "key": { "type": "apiKey", "name": "X-Key", "in": "header" } },
+            "paths": {
+                "/items": {
+                    "post": {
+                        "consumes": ["application/xml"],
+                        "parameters": [
+                            { "name": "body", "in": "body", "schema": { "$ref": "#/definitions/Item" } },
+                            { "name": "dry", "in": "query", "type": "boolean", "required": true }
+                        ],
+                        "responses": { "200": { "description": "OK", "schema": { "type": "string" } } }
+                    }
+                }
+            }
+        });
+        let spec = ApiSpec::parse(&root).unwrap();
+        assert_eq!(spec.spec_version, "Swagger 2.0");
+        assert_eq!(
+            spec.servers,
+            ["http://legacy.example/api", "https://legacy.example/api"]
+        );
+        assert_eq!(spec.auth[0].detail, "`X-Key` in header");
+
+        let post = &spec.tags[0].endpoints[0];
+        assert_eq!(
+            post.request_body,
+            [("application/xml".to_string(), "Item".to_string())]
+        );
+        assert_eq!(post.params.len(), 1);
+        assert_eq!(post.params[0].ty, "boolean");
+        assert!(post.params[0].required);
+        assert_eq!(post.responses[0].2.as_deref(), Some("string"));
+    }
+
+    #[test]
+    fn other_json_is_not_a_spec() {
+        assert!(ApiSpec::parse(&json!({ "name": "package" })).is_none());
+        assert!(ApiSpec::parse(&json!({ "openapi": "3.1.0", "info": {} })).is_none());
+    }
+}
@@ -0,0 +1,572 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OnceCell, OwnedSemaphorePermit, Semaphore};
+
+use crate::profiles::HeaderProfile;
+
+/// robots.txt files are re-fetched after this long
+const ROBOTS_TTL: Duration = Duration::from_secs(24 * 3600);
+
+/// Server and network errors block a site for this long before retrying
+const ROBOTS_ERROR_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Relaxed rules for a domain (and its subdomains), e.g. sites we own
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DomainOverride {
+    pub domain: String,
+    #[serde(default)]
+    pub ignore_robots: bool,
+    #[serde(default)]
+    pub delay_ms: Option<u64>,
+    #[serde(default)]
+    pub max_concurrent: Option<usize>,
+}
+
+/// Politeness settings, persisted to `<app data>/politeness.json`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PolitenessConfig {
+    #[serde(default = "default_true")]
+    pub respect_robots: bool,
+    /// Product token matched against robots.txt `User-agent` lines
+    #[serde(default = "default_robots_agent")]
+    pub robots_user_agent: String,
+    /// Minimum gap between requests to one host; a larger Crawl-delay wins
+    #[serde(default = "default_delay_ms")]
+    pub delay_ms: u64,
+    #[serde(default = "default_max_concurrent")]
+    pub max_concurrent_per_host: usize,
+    #[serde(default)]
+    pub overrides: Vec<DomainOverride>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_robots_agent() -> String {
+    "TheFetcher".to_string()
+}
+
+fn default_delay_ms() -> u64 {
+    1000
+}
+
+fn default_max_concurrent() -> usize {
+    2
+}
+
+impl Default for PolitenessConfig {
+    fn default() -> Self {
+        Self {
+            respect_robots: true,
+            robots_user_agent: default_robots_agent(),
+            delay_ms: default_delay_ms(),
+            max_concurrent_per_host: default_max_concurrent(),
+            overrides: Vec::new(),
+        }
+    }
+}
+
+impl PolitenessConfig {
+    fn override_for(&self, host: &str) -> Option<&DomainOverride> {
+        self.overrides
+            .iter()
+            .find(|o| crate::urls::domain_matches(host, &o.domain))
+    }
+}
+
+/// Rules of the robots.txt group that applies to us
+#[derive(Default, Debug, Clone)]
+pub struct Robots {
+    /// (allow, pattern)
+    rules: Vec<(bool, String)>,
+    crawl_delay: Option<f64>,
+}
+
+impl Robots {
+    fn disallow_all() -> Self {
+        Self {
+            rules: vec![(false, "/".to_string())],
+            crawl_delay: None,
+        }
+    }
+
+    /// Parses robots.txt, keeping the most specific group for `agent`,
+    /// or the `*` group when none names us.
+    pub fn parse(text: &str, agent: &str) -> Self {
+        let agent = product_token(agent);
+        let mut specific: Option<Robots> = None;
+        let mut wildcard: Option<Robots> = None;
+
+        // User-agent lines of the group being read, and whether rules followed
+        let mut agents: Vec<String> = Vec::new();
+        let mut group = Robots::default();
+        let mut in_rules = false;
+
+        let mut flush = |agents: &[String], group: &Robots| {
+            if agents.iter().any(|a| a != "*" && *a == agent) {
+                specific.get_or_insert_with(Robots::default).merge(group);
+            } else if agents.iter().any(|a| a == "*") {
+                wildcard.get_or_insert_with(Robots::default).merge(group);
+            }
+        };
+
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let Some((field, value)) = line.split_once(':') else {
+                continue;
+            };
+            let field = field.trim().to_lowercase();
+            let value = value.trim();
+            match field.as_str() {
+                "user-agent" => {
+                    if in_rules {
+                        flush(&agents, &group);
+                        agents.clear();
+                        group = Robots::default();
+                        in_rules = false;
+                    }
+                    agents.push(product_token(value));
+                }
+                "allow" | "disallow" => {
+                    in_rules = true;
+                    // An empty Disallow allows everything
+                    if !value.is_empty() {
+                        group.rules.push((field == "allow", value.to_string()));
+                    }
+                }
+                "crawl-delay" => {
+                    in_rules = true;
+                    group.crawl_delay = value.parse().ok();
+                }
+                _ => {}
+            }
+        }
+        flush(&agents, &group);
+
+        specific.or(wildcard).unwrap_or_default()
+    }
+
+    fn merge(&mut self, other: &Robots) {
+        self.rules.extend(other.rules.iter().cloned());
+        if other.crawl_delay.is_some() {
+            self.crawl_delay = other.crawl_delay;
+        }
+    }
+
+    /// Longest matching rule wins; Allow wins ties
+    pub fn allows(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .filter(|(_, pattern)| pattern_matches(pattern, path))
+            .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+            .is_none_or(|(allow, _)| *allow)
+    }
+}
+
+/// Fetches and parses robots.txt for `origin` and returns when to fetch it
+/// again. As in RFC 9309, a missing file (4xx) allows everything while a
+/// server or network error disallows everything.
+async fn fetch_robots(
+    client: &reqwest::Client,
+    origin: &str,
+    agent: &str,
+    profile: &HeaderProfile,
+) -> (Instant, Robots) {
+    let response = profile
+        .apply(client.get(format!("{}/robots.txt", origin)))
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await;
+    let robots = match response {
+        Ok(res) if res.status().is_success() => match res.text().await {
+            Ok(text) => Some(Robots::parse(&text, agent)),
+            Err(_) => None,
+        },
+        Ok(res) if res.status().is_client_error() => Some(Robots::default()),
+        _ => None,
+    };
+    match robots {
+        Some(robots) => (Instant::now() + ROBOTS_TTL, robots),
+        None => (Instant::now() + ROBOTS_ERROR_TTL, Robots::disallow_all()),
+    }
+}
+
+/// Lowercased product token of a user agent, `thefetcher` for
+/// `TheFetcher/1.2 (+https://example.com)`; robots.txt groups name only that
+fn product_token(agent: &str) -> String {
+    agent
+        .split(['/', ' '])
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase()
+}
+
+/// robots.txt pattern match with `*` wildcards and a `$` end anchor
+pub fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(p) => (p, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    if !path.starts_with(first) {
+        return false;
+    }
+    let mut pos = first.len();
+    let rest: Vec<&str> = parts.collect();
+    for (i, part) in rest.iter().enumerate() {
+        // The last segment of an anchored pattern must end the path
+        if anchored && i == rest.len() - 1 {
+            return path.len() >= pos + part.len() && path.ends_with(part);
+        }
+        match path[pos..].find(part) {
+            Some(found) => pos += found + part.len(),
+            None => return false,
+        }
+    }
+    !anchored || pos == path.len()
+}
+
+/// robots.txt rules and when they expire
+type RobotsCell = OnceCell<(Instant, Robots)>;
+
+struct HostSlot {
+    permits: Arc<Semaphore>,
+    next_allowed: tokio::sync::Mutex<Instant>,
+}
+
+impl HostSlot {
+    /// No request holds or waits for a permit and the delay has run out,
+    /// so a fresh slot would behave the same
+    fn is_idle(slot: &Arc<HostSlot>) -> bool {
+        Arc::strong_count(slot) == 1
+            && Arc::strong_count(&slot.permits) == 1
+            && slot
+                .next_allowed
+                .try_lock()
+                .is_ok_and(|next| *next <= Instant::now())
+    }
+}
+
+/// Held for the duration of one request to a host
+pub struct HostPermit {
+    _permit: OwnedSemaphorePermit,
+}
+
+/// robots.txt cache plus per-host concurrency and rate limits
+pub struct Politeness {
+    dir: PathBuf,
+    config: Mutex<PolitenessConfig>,
+    /// Per origin; the cell also makes concurrent requests share one fetch
+    robots: Mutex<HashMap<String, Arc<RobotsCell>>>,
+    hosts: Mutex<HashMap<String, Arc<HostSlot>>>,
+}
+
+impl Politeness {
+    pub fn load(dir: PathBuf) -> Self {
+        let config = std::fs::read_to_string(dir.join("politeness.json"))
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+        Self {
+            dir,
+            config: Mutex::new(config),
+            robots: Mutex::new(HashMap::new()),
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn config(&self) -> PolitenessConfig {
+        self.config.lock().unwrap().clone()
+    }
+
+    pub fn set_config(&self, config: PolitenessConfig) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+        std::fs::write(self.dir.join("politeness.json"), json)
+            .map_err(|e| format!("Failed to save politeness config: {}", e))?;
+        *self.config.lock().unwrap() = config;
+        // Limits are baked into the slots and the robots group depends on the agent
+        self.hosts.lock().unwrap().clear();
+        self.robots.lock().unwrap().clear();
+        Ok(())
+    }
+
+    async fn robots_for(
+        &self,
+        client: &reqwest::Client,
+        url: &reqwest::Url,
+        agent: &str,
+        profile: &HeaderProfile,
+    ) -> Robots {
+        let origin = url.origin().ascii_serialization();
+        let cell = {
+            let mut robots = self.robots.lock().unwrap();
+            let cell = robots.entry(origin.clone()).or_default();
+            if cell
+                .get()
+                .is_some_and(|(expires, _)| *expires <= Instant::now())
+            {
+                *cell = Arc::default();
+            }
+            cell.clone()
+        };
+        let (_, robots) = cell
+            .get_or_init(|| fetch_robots(client, &origin, agent, profile))
+            .await;
+        robots.clone()
+    }
+
+    /// Checks robots.txt for `url` (fetched with `profile`'s headers), then
+    /// waits for a free slot and the host's request delay. The permit must
+    /// be held until the response is read.
+    pub async fn acquire(
+        &self,
+        client: &reqwest::Client,
+        url: &str,
+        profile: &HeaderProfile,
+    ) -> Result<HostPermit, String> {
+        let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid URL: {}", e))?;
+        let host = parsed
+            .host_str()
+            .unwrap_or_default()
+            .trim_start_matches("www.")
+            .to_lowercase();
+        let config = self.config();
+        let domain_override = config.override_for(&host).cloned();
+
+        let mut delay = Duration::from_millis(config.delay_ms);
+        if config.respect_robots && !domain_override.as_ref().is_some_and(|o| o.ignore_robots) {
+            let robots = self
+                .robots_for(client, &parsed, &config.robots_user_agent, profile)
+                .await;
+            let path = match parsed.query() {
+                Some(q) => format!("{}?{}", parsed.path(), q),
+                None => parsed.path().to_string(),
+            };
+            if !robots.allows(&path) {
+                return Err(format!("Blocked by robots.txt: {}", url));
+            }
+            if let Some(secs) = robots.crawl_delay {
+                delay = delay.max(Duration::from_secs_f64(secs.clamp(0.0, 60.0)));
+            }
+        }
+        if let Some(ms) = domain_override.as_ref().and_then(|o| o.delay_ms) {
+            delay = Duration::from_millis(ms);
+        }
+
+        let slot = {
+            let mut hosts = self.hosts.lock().unwrap();
+            hosts.retain(|_, slot| !HostSlot::is_idle(slot));
+            hosts
+                .entry(host)
+                .or_insert_with(|| {
+                    let limit = domain_override
+                        .as_ref()
+                        .and_then(|o| o.max_concurrent)
+                        .unwrap_or(config.max_concurrent_per_host)
+                        .max(1);
+                    Arc::new(HostSlot {
+                        permits: Arc::new(Semaphore::new(limit)),
+                        next_allowed: tokio::sync::Mutex::new(Instant::now()),
+                    })
+                })
+                .clone()
+        };
+
+        let permit = slot
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|e| e.to_string())?;
+        let wait = {
+            let mut next = slot.next_allowed.lock().await;
+            let now = Instant::now();
+            let start = (*next).max(now);
+            *next = start + delay;
+            start - now
+        };
+        tokio::time::sleep(wait).await;
+
+        Ok(HostPermit { _permit: permit })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiles::HeaderConfig;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Answers every request with `status` and a robots.txt disallowing
+    /// `/private`, slowly enough for requests to overlap. Returns the base
+    /// URL and the request heads seen so far.
+    async fn robots_server(status: &'static str) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let heads = Arc::new(Mutex::new(Vec::new()));
+        let seen = heads.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut head = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    if n == 0 {
+                        break;
+                    }
+                    head.extend_from_slice(&buf[..n]);
+                }
+                seen.lock()
+                    .unwrap()
+                    .push(String::from_utf8_lossy(&head).into_owned());
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                let body = "User-agent: *\nDisallow: /private\n";
+                let response = format!(
+                    "HTTP/1.1 {}\r\ncontent-type: text/plain\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (base, heads)
+    }
+
+    async fn robots(status: &'static str) -> Robots {
+        let (base, _) = robots_server(status).await;
+        let politeness = Politeness::load(std::env::temp_dir());
+        let url = reqwest::Url::parse(&base).unwrap();
+        let profile = HeaderConfig::default().profile_for(&base);
+        politeness
+            .robots_for(&reqwest::Client::new(), &url, "TheFetcher", &profile)
+            .await
+    }
+
+    #[tokio::test]
+    async fn only_missing_robots_files_allow_everything() {
+        let ok = robots("200 OK").await;
+        assert!(ok.allows("/public") && !ok.allows("/private"));
+        assert!(robots("404 Not Found").await.allows("/private"));
+        assert!(!robots("503 Service Unavailable").await.allows("/public"));
+
+        // Nothing listens on a port we just released
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url =
+            reqwest::Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        drop(listener);
+        let profile = HeaderConfig::default().profile_for(url.as_str());
+        let unreachable = Politeness::load(std::env::temp_dir())
+            .robots_for(&reqwest::Client::new(), &url, "TheFetcher", &profile)
+            .await;
+        assert!(!unreachable.allows("/public"));
+    }
+
+    #[tokio::test]
+    async fn concurrent_requests_share_one_robots_fetch() {
+        let (base, heads) = robots_server("200 OK").await;
+        let politeness = Politeness::load(std::env::temp_dir());
+        let client = reqwest::Client::new();
+        let url = reqwest::Url::parse(&base).unwrap();
+        let profile = HeaderConfig::default().profile_for(&base);
+        let (a, b) = tokio::join!(
+            politeness.robots_for(&client, &url, "TheFetcher", &profile),
+            politeness.robots_for(&client, &url, "TheFetcher", &profile),
+        );
+        assert!(!a.allows("/private") && !b.allows("/private"));
+
+        let heads = heads.lock().unwrap();
+        assert_eq!(heads.len(), 1);
+        assert!(heads[0].contains(&profile.user_agent));
+    }
+
+    #[test]
+    fn groups_match_the_product_token_only() {
+        let text = "User-agent: Fetcher\nDisallow: /a\n\nUser-agent: *\nDisallow: /b\n";
+        let robots = Robots::parse(text, "TheFetcher/1.0");
+        assert!(robots.allows("/a"));
+        assert!(!robots.allows("/b"));
+
+        let text = "User-agent: THEFETCHER/2.0\nDisallow: /a\n\nUser-agent: *\nDisallow: /b\n";
+        let robots = Robots::parse(text, "TheFetcher/1.0 (+https://example.com)");
+        assert!(!robots.allows("/a"));
+        assert!(robots.allows("/b"));
+    }
+
+    #[tokio::test]
+    async fn idle_hosts_are_forgotten() {
+        let dir = std::env::temp_dir().join(format!("politeness-{}", uuid::Uuid::new_v4()));
+        let politeness = Politeness::load(dir);
+        {
+            let mut config = politeness.config.lock().unwrap();
+            config.respect_robots = false;
+            config.delay_ms = 0;
+        }
+        let client = reqwest::Client::new();
+        let profile = HeaderConfig::default().profile_for("https://a.example/");
+
+        let a = politeness
+            .acquire(&client, "https://a.example/", &profile)
+            .await
+            .unwrap();
+        politeness
+            .acquire(&client, "https://b.example/", &profile)
+            .await
+            .unwrap();
+        assert_eq!(politeness.hosts.lock().unwrap().len(), 2);
+        drop(a);
+        politeness
+            .acquire(&client, "https://c.example/", &profile)
+            .await
+            .unwrap();
+        let hosts = politeness.hosts.lock().unwrap();
+        assert_eq!(hosts.keys().collect::<Vec<_>>(), vec!["c.example"]);
+    }
+
+    #[test]
+    fn the_specific_group_wins_over_the_wildcard() {
+        let text = "# comment\nUser-agent: *\nDisallow: /\n\nUser-agent: other\nUser-agent: thefetcher\nAllow: /public\nDisallow: /private # inline\nCrawl-delay: 2.5\n";
+        let robots = Robots::parse(text, "TheFetcher");
+        assert!(robots.allows("/public/page"));
+        assert!(robots.allows("/elsewhere"));
+        assert!(!robots.allows("/private/page"));
+        assert_eq!(robots.crawl_delay, Some(2.5));
+
+        let robots = Robots::parse(text, "SomeoneElse");
+        assert!(!robots.allows("/public"));
+        assert_eq!(robots.crawl_delay, None);
+    }
+
+    #[test]
+    fn longest_rule_wins_and_allow_wins_ties() {
+        let text = "User-agent: *\nDisallow: /docs\nAllow: /docs/public\nDisallow: /a\nAllow: /a\nDisallow:\n";
+        let robots = Robots::parse(text, "TheFetcher");
+        assert!(!robots.allows("/docs/private"));
+        assert!(robots.allows("/docs/public/x"));
+        assert!(robots.allows("/a/b"));
+        assert!(Robots::parse("", "TheFetcher").allows("/anything"));
+    }
+
+    #[test]
+    fn patterns_support_wildcards_and_anchors() {
+        assert!(pattern_matches("/docs", "/docs/intro"));
+        assert!(!pattern_matches("/docs", "/api/docs"));
+        assert!(pattern_matches("/*.pdf$", "/files/a.pdf"));
+        assert!(!pattern_matches("/*.pdf$", "/files/a.pdf?dl=1"));
+        assert!(pattern_matches("/*/edit", "/wiki/page/edit/history"));
+        assert!(!pattern_matches("/*/edit", "/wiki/page"));
+        assert!(pattern_matches("/exact$", "/exact"));
+        assert!(!pattern_matches("/exact$", "/exact/more"));
+        assert!(pattern_matches("*", "/anything"));
+    }
+}
@@ -0,0 +1,295 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_CONTACT_URL: &str = "https://github.com/ElMoorish/Thefetcher";
+
+/// Request headers sent with every page fetch
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HeaderProfile {
+    pub name: String,
+    pub user_agent: String,
+    #[serde(default = "default_accept")]
+    pub accept: String,
+    #[serde(default = "default_accept_language")]
+    pub accept_language: String,
+    /// Any other headers, e.g. `From` or `DNT`
+    #[serde(default)]
+    pub extra_headers: Vec<(String, String)>,
+}
+
+fn default_accept() -> String {
+    "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8".to_string()
+}
+
+fn default_accept_language() -> String {
+    "en-US,en;q=0.9".to_string()
+}
+
+impl HeaderProfile {
+    /// Identifies the app and how to reach its maintainers
+    fn bot(contact_url: &str) -> Self {
+        Self {
+            name: "bot".to_string(),
+            user_agent: format!(
+                "TheFetcher/{} (+{})",
+                env!("CARGO_PKG_VERSION"),
+                contact_url
+            ),
+            accept: default_accept(),
+            accept_language: default_accept_language(),
+            extra_headers: Vec::new(),
+        }
+    }
+
+    /// For sites that refuse anything that doesn't look like a desktop browser
+    fn browser() -> Self {
+        Self {
+            name: "browser".to_string(),
+            user_agent: "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36".to_string(),
+            accept: "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,*/*;q=0.8".to_string(),
+            accept_language: default_accept_language(),
+            extra_headers: vec![("Upgrade-Insecure-Requests".to_string(), "1".to_string())],
+        }
+    }
+
+    pub fn apply(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        let mut request = request
+            .header("User-Agent", &self.user_agent)
+            .header("Accept", &self.accept)
+            .header("Accept-Language", &self.accept_language);
+        for (name, value) in &self.extra_headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        request
+    }
+}
+
+/// Uses a named profile for a domain and its subdomains
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DomainProfile {
+    pub domain: String,
+    pub profile: String,
+}
+
+/// Header settings, persisted to `<app data>/headers.json`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HeaderConfig {
+    #[serde(default = "default_profile")]
+    pub default_profile: String,
+    /// Included in the `bot` profile's User-Agent
+    #[serde(default = "default_contact_url")]
+    pub contact_url: String,
+    /// User-defined profiles; a custom profile named `bot` or `browser`
+    /// replaces the built-in one
+    #[serde(default)]
+    pub custom: Vec<HeaderProfile>,
+    #[serde(default)]
+    pub domains: Vec<DomainProfile>,
+}
+
+fn default_profile() -> String {
+    "bot".to_string()
+}
+
+fn default_contact_url() -> String {
+    DEFAULT_CONTACT_URL.to_string()
+}
+
+impl Default for HeaderConfig {
+    fn default() -> Self {
+        Self {
+            default_profile: default_profile(),
+            contact_url: default_contact_url(),
+            custom: Vec::new(),
+            domains: Vec::new(),
+        }
+    }
+}
+
+impl HeaderConfig {
+    /// Built-in profiles followed by custom ones
+    pub fn profiles(&self) -> Vec<HeaderProfile> {
+        let mut profiles = vec![
+            HeaderProfile::bot(&self.contact_url),
+            HeaderProfile::browser(),
+        ];
+        for custom in &self.custom {
+            match profiles.iter_mut().find(|p| p.name == custom.name) {
+                Some(existing) => *existing = custom.clone(),
+                None => profiles.push(custom.clone()),
+            }
+        }
+        profiles
+    }
+
+    /// Profile for a URL: the first matching domain rule, else the default.
+    /// Unknown names fall back to the bot profile.
+    pub fn profile_for(&self, url: &str) -> HeaderProfile {
+        let host = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(|h| h.to_string()))
+            .unwrap_or_default();
+        let name = self
+            .domains
+            .iter()
+            .find(|d| crate::urls::domain_matches(&host, &d.domain))
+            .map(|d| d.profile.as_str())
+            .unwrap_or(&self.default_profile);
+        let mut profiles = self.profiles();
+        match profiles.iter().position(|p| p.name == name) {
+            Some(i) => profiles.swap_remove(i),
+            None => profiles.swap_remove(0),
+        }
+    }
+}
+
+pub struct HeaderProfiles {
+    dir: PathBuf,
+    config: Mutex<HeaderConfig>,
+}
+
+impl HeaderProfiles {
+    pub fn load(dir: PathBuf) -> Self {
+        let config = std::fs::read_to_string(dir.join("headers.json"))
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+        Self {
+            dir,
+            config: Mutex::new(config),
+        }
+    }
+
+    pub fn config(&self) -> HeaderConfig {
+        self.config.lock().unwrap().clone()
+    }
+
+    pub fn set_config(&self, config: HeaderConfig) -> Result<(), String> {
+        let names = config.profiles();
+        let known = |name: &str| names.iter().any(|p| p.name == name);
+        if !known(&config.default_profile) {
+            return Err(format!(
+                "Unknown header profile: {}",
+                config.default_profile
+            ));
+        }
+        if let Some(rule) = config.domains.iter().find(|d| !known(&d.profile)) {
+            return Err(format!(
+                "Unknown header profile for {}: {}",
+                rule.domain, rule.profile
+            ));
+        }
+        let json = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+        std::fs::write(self.dir.join("headers.json"), json)
+            .map_err(|e| format!("Failed to save header config: {}", e))?;
+        *self.config.lock().unwrap() = config;
+        Ok(())
+    }
+
+    pub fn profile_for(&self, url: &str) -> HeaderProfile {
+        self.config.lock().unwrap().profile_for(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn custom(name: &str, user_agent: &str) -> HeaderProfile {
+        HeaderProfile {
+            name: name.to_string(),
+            user_agent: user_agent.to_string(),
+            accept: default_accept(),
+            accept_language: default_accept_language(),
+            extra_headers: Vec::new(),
+        }
+    }
+
+    fn domain(domain: &str, profile: &str) -> DomainProfile {
+        DomainProfile {
+            domain: domain.to_string(),
+            profile: profile.to_string(),
+        }
+    }
+
+    #[test]
+    fn domain_rules_cover_subdomains() {
+        let config = HeaderConfig {
+            domains: vec![domain("example.com", "browser")],
+            ..HeaderConfig::default()
+        };
+        let name = |url| config.profile_for(url).name;
+        assert_eq!(name("https://example.com/"), "browser");
+        assert_eq!(name("https://docs.example.com/a"), "browser");
+        assert_eq!(name("https://www.example.com/"), "browser");
+        assert_eq!(name("https://notexample.com/"), "bot");
+        assert_eq!(name("not a url"), "bot");
+    }
+
+    #[test]
+    fn unknown_profile_names_fall_back_to_bot() {
+        let config = HeaderConfig {
+            default_profile: "missing".to_string(),
+            domains: vec![domain("example.com", "gone")],
+            ..HeaderConfig::default()
+        };
+        assert_eq!(config.profile_for("https://example.com/").name, "bot");
+        assert_eq!(config.profile_for("https://other.org/").name, "bot");
+    }
+
+    #[test]
+    fn custom_profiles_replace_built_in_ones() {
+        let config = HeaderConfig {
+            default_profile: "browser".to_string(),
+            custom: vec![
+                custom("bot", "MyBot/1.0"),
+                custom("browser", "MyBrowser/2.0"),
+            ],
+            ..HeaderConfig::default()
+        };
+        let profiles = config.profiles();
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(
+            config.profile_for("https://a.dev/").user_agent,
+            "MyBrowser/2.0"
+        );
+        assert_eq!(profiles[0].user_agent, "MyBot/1.0");
+    }
+
+    #[test]
+    fn set_config_rejects_unknown_profiles() {
+        let dir = std::env::temp_dir().join(format!("profiles-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let profiles = HeaderProfiles::load(dir.clone());
+
+        let bad_default = HeaderConfig {
+            default_profile: "missing".to_string(),
+            ..HeaderConfig::default()
+        };
+        assert!(profiles.set_config(bad_default).is_err());
+        let bad_domain = HeaderConfig {
+            domains: vec![domain("example.com", "missing")],
+            ..HeaderConfig::default()
+        };
+        assert!(profiles.set_config(bad_domain).is_err());
+        assert!(!dir.join("headers.json").exists());
+
+        let good = HeaderConfig {
+            custom: vec![custom("polite", "Polite/1.0")],
+            domains: vec![domain("example.com", "polite")],
+            ..HeaderConfig::default()
+        };
+        profiles.set_config(good).unwrap();
+        let reloaded = HeaderProfiles::load(dir.clone());
+        assert_eq!(
+            reloaded.profile_for("https://docs.example.com/").user_agent,
+            "Polite/1.0"
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,733 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, RwLock};
+
+use futures_util::future::{join_all, BoxFuture};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::Manager;
+
+use crate::archive::Archive;
+use crate::search::SearchOptions;
+use crate::vault::{VaultFilter, VaultGraph};
+use crate::SearXNGResult;
+
+/// Reciprocal rank fusion constant; larger values flatten rank differences
+const RRF_K: f64 = 60.0;
+
+/// Results taken from each provider before merging
+const PER_PROVIDER_LIMIT: usize = 30;
+
+/// Built-in provider names; JSON APIs use their configured name
+pub const SEARXNG: &str = "searxng";
+pub const VAULT: &str = "vault";
+pub const FETCHED: &str = "fetched";
+
+/// Everything a provider may need to answer a query
+pub struct SearchContext<'a> {
+    pub app: &'a tauri::AppHandle,
+    pub client: &'a reqwest::Client,
+    /// Obsidian API key, for the vault provider
+    pub api_key: Option<&'a str>,
+    pub options: &'a SearchOptions,
+}
+
+/// A source of search results. Results are ranked best first; the URL is
+/// the identity used when merging providers.
+pub trait SearchProvider: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn search<'a>(
+        &'a self,
+        ctx: &'a SearchContext<'a>,
+        query: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<SearXNGResult>, String>>;
+}
+
+/// The SearXNG instance; one result page, as selected by `options.pageno`
+pub struct Searxng {
+    pub base_url: String,
+}
+
+impl SearchProvider for Searxng {
+    fn name(&self) -> &str {
+        SEARXNG
+    }
+
+    fn search<'a>(
+        &'a self,
+        ctx: &'a SearchContext<'a>,
+        query: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<SearXNGResult>, String>> {
+        Box::pin(crate::call_searxng_search(
+            ctx.client,
+            &self.base_url,
+            query,
+            ctx.options,
+        ))
+    }
+}
+
+/// Obsidian's full-text search over the vault; URLs are note paths
+pub struct Vault;
+
+impl SearchProvider for Vault {
+    fn name(&self) -> &str {
+        VAULT
+    }
+
+    fn search<'a>(
+        &'a self,
+        ctx: &'a SearchContext<'a>,
+        query: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<SearXNGResult>, String>> {
+        Box::pin(async move {
+            let api_key = ctx
+                .api_key
+                .filter(|k| !k.is_empty())
+                .ok_or("Vault search needs an Obsidian API key")?;
+            let graph = ctx.app.state::<RwLock<VaultGraph>>();
+            crate::query_vault(&graph, query, api_key, VaultFilter::default())
+                .await
+                .map(|(results, _)| results)
+        })
+    }
+}
+
+/// Pages fetched earlier, from the full-text archive
+pub struct Fetched;
+
+impl SearchProvider for Fetched {
+    fn name(&self) -> &str {
+        FETCHED
+    }
+
+    fn search<'a>(
+        &'a self,
+        ctx: &'a SearchContext<'a>,
+        query: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<SearXNGResult>, String>> {
+        let archive = ctx.app.state::<Archive>().inner().clone();
+        let query = query.to_string();
+        Box::pin(async move {
+            let hits =
+                tokio::task::spawn_blocking(move || archive.search(&query, PER_PROVIDER_LIMIT))
+                    .await
+                    .map_err(|e| e.to_string())??;
+            Ok(hits
+                .into_iter()
+                .map(|hit| SearXNGResult {
+                    engine: Some(FETCHED.to_string()),
+                    ..hit.into()
+                })
+                .collect())
+        })
+    }
+}
+
+/// A JSON search API described by a URL template and JSON paths
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonApiConfig {
+    pub name: String,
+    /// `{query}`, `{page}` and `{language}` are substituted (URL-encoded)
+    pub url_template: String,
+    /// Dotted path to the result array, e.g. `data.items`; empty for a
+    /// top-level array. Array indexes are written as numbers (`hits.0`).
+    #[serde(default)]
+    pub results_path: String,
+    /// Paths below each result
+    pub url_path: String,
+    pub title_path: String,
+    #[serde(default)]
+    pub snippet_path: Option<String>,
+    #[serde(default)]
+    pub date_path: Option<String>,
+    #[serde(default)]
+    pub score_path: Option<String>,
+    /// Sent with every request, e.g. an API key header
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+}
+
+/// Value at a dotted path; a leading `$` or `$.` is ignored
+fn json_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.trim_start_matches('$')
+        .split('.')
+        .filter(|key| !key.is_empty())
+        .try_fold(value, |value, key| match key.parse::<usize>() {
+            Ok(i) if value.is_array() => value.get(i),
+            _ => value.get(key),
+        })
+}
+
+fn json_string(value: &Value, path: &str) -> Option<String> {
+    match json_path(value, path)? {
+        Value::String(s) => Some(s.clone()),
+        Value::Null => None,
+        other => Some(other.to_string()),
+    }
+}
+
+pub struct JsonApi {
+    pub config: JsonApiConfig,
+}
+
+impl JsonApi {
+    /// One request to the API, with `options.pageno` and `options.language`
+    /// filled into the URL template
+    pub async fn query(
+        &self,
+        client: &reqwest::Client,
+        query: &str,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearXNGResult>, String> {
+        let config = &self.config;
+        let url = config
+            .url_template
+            .replace("{query}", &urlencoding::encode(query))
+            .replace("{page}", &options.pageno.unwrap_or(1).max(1).to_string())
+            .replace(
+                "{language}",
+                &urlencoding::encode(options.language.as_deref().unwrap_or("")),
+            );
+        let mut request = client.get(&url).header("Accept", "application/json");
+        for (name, value) in &config.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("HTTP {}", response.status()));
+        }
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid JSON: {}", e))?;
+
+        let items = json_path(&body, &config.results_path)
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| format!("No result array at '{}'", config.results_path))?;
+        Ok(items
+            .iter()
+            .filter_map(|item| {
+                let url = json_string(item, &config.url_path)?;
+                Some(SearXNGResult {
+                    title: json_string(item, &config.title_path).unwrap_or_else(|| url.clone()),
+                    url,
+                    snippet: config
+                        .snippet_path
+                        .as_deref()
+                        .and_then(|p| json_string(item, p)),
+                    published_date: config
+                        .date_path
+                        .as_deref()
+                        .and_then(|p| json_string(item, p)),
+                    score: config
+                        .score_path
+                        .as_deref()
+                        .and_then(|p| json_path(item, p))
+                        .and_then(|v| v.as_f64()),
+                    engine: Some(config.name.clone()),
+                    ..Default::default()
+                })
+            })
+            .collect())
+    }
+}
+
+impl SearchProvider for JsonApi {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn search<'a>(
+        &'a self,
+        ctx: &'a SearchContext<'a>,
+        query: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<SearXNGResult>, String>> {
+        Box::pin(self.query(ctx.client, query, ctx.options))
+    }
+}
+
+/// Provider settings, persisted to `<app data>/providers.json`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderConfig {
+    #[serde(default = "default_searxng_url")]
+    pub searxng_url: String,
+    #[serde(default)]
+    pub json_apis: Vec<JsonApiConfig>,
+}
+
+fn default_searxng_url() -> String {
+    "http://localhost:8080".to_string()
+}
+
+impl Default for ProviderConfig {
+    fn default() -> Self {
+        Self {
+            searxng_url: default_searxng_url(),
+            json_apis: Vec::new(),
+        }
+    }
+}
+
+pub struct SearchProviders {
+    dir: PathBuf,
+    config: Mutex<ProviderConfig>,
+}
+
+impl SearchProviders {
+    pub fn load(dir: PathBuf) -> Self {
+        let config = std::fs::read_to_string(dir.join("providers.json"))
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+        Self {
+            dir,
+            config: Mutex::new(config),
+        }
+    }
+
+    pub fn config(&self) -> ProviderConfig {
+        self.config.lock().unwrap().clone()
+    }
+
+    pub fn set_config(&self, config: ProviderConfig) -> Result<(), String> {
+        let mut names = vec![SEARXNG, VAULT, FETCHED];
+        for api in &config.json_apis {
+            if api.name.trim().is_empty() || names.contains(&api.name.as_str()) {
+                return Err(format!("Duplicate or empty provider name: '{}'", api.name));
+            }
+            if !api.url_template.contains("{query}") {
+                return Err(format!("URL template of {} has no {{query}}", api.name));
+            }
+            names.push(&api.name);
+        }
+        let json = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+        std::fs::write(self.dir.join("providers.json"), json)
+            .map_err(|e| format!("Failed to save provider config: {}", e))?;
+        *self.config.lock().unwrap() = config;
+        Ok(())
+    }
+
+    pub fn searxng_url(&self) -> String {
+        self.config.lock().unwrap().searxng_url.clone()
+    }
+
+    /// Names of every available provider
+    pub fn names(&self) -> Vec<String> {
+        let config = self.config.lock().unwrap();
+        [SEARXNG, VAULT, FETCHED]
+            .iter()
+            .map(|n| n.to_string())
+            .chain(config.json_apis.iter().map(|api| api.name.clone()))
+            .collect()
+    }
+
+    pub fn resolve(&self, names: &[String]) -> Result<Vec<Box<dyn SearchProvider>>, String> {
+        let config = self.config.lock().unwrap();
+        names
+            .iter()
+            .map(|name| -> Result<Box<dyn SearchProvider>, String> {
+                Ok(match name.as_str() {
+                    SEARXNG => Box::new(Searxng {
+                        base_url: config.searxng_url.clone(),
+                    }),
+                    VAULT => Box::new(Vault),
+                    FETCHED => Box::new(Fetched),
+                    _ => Box::new(JsonApi {
+                        config: config
+                            .json_apis
+                            .iter()
+                            .find(|api| api.name == *name)
+                            .cloned()
+                            .ok_or_else(|| format!("Unknown search provider: {}", name))?,
+                    }),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Queries every provider concurrently and merges the results with
+/// reciprocal rank fusion: a URL scores `1 / (60 + rank)` per provider that
+/// returned it, so agreement between providers outranks a single top hit.
+/// Failing providers are reported by name, unless all of them fail.
+pub async fn fan_out(
+    ctx: &SearchContext<'_>,
+    providers: &[Box<dyn SearchProvider>],
+    query: &str,
+) -> Result<(Vec<SearXNGResult>, Vec<String>), String> {
+    let runs = join_all(
+        providers
+            .iter()
+            .map(|p| async move { (p.name().to_string(), p.search(ctx, query).await) }),
+    )
+    .await;
+    fuse(runs)
+}
+
+/// Merges per-provider result lists, best first, as described on `fan_out`
+fn fuse(
+    runs: Vec<(String, Result<Vec<SearXNGResult>, String>)>,
+) -> Result<(Vec<SearXNGResult>, Vec<String>), String> {
+    let attempted = runs.len();
+    let mut merged: Vec<(f64, SearXNGResult)> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut errors = Vec::new();
+    for (name, outcome) in runs {
+        let results = match outcome {
+            Ok(results) => results,
+            Err(e) => {
+                errors.push(format!("{}: {}", name, e));
+                continue;
+            }
+        };
+        for (rank, mut result) in results.into_iter().take(PER_PROVIDER_LIMIT).enumerate() {
+            let fused = 1.0 / (RRF_K + rank as f64 + 1.0);
+            if result.engine.is_none() && result.engines.is_empty() {
+                result.engine = Some(name.clone());
+            }
+            let key = crate::urls::normalize_url(&result.url);
+            match positions.get(&key) {
+                Some(&i) => {
+                    let (score, first) = &mut merged[i];
+                    *score += fused;
+                    if first.engines.is_empty() {
+                        first.engines.extend(first.engine.clone());
+                    }
+                    for engine in result.engines.into_iter().chain(result.engine) {
+                        if !first.engines.contains(&engine) {
+                            first.engines.push(engine);
+                        }
+                    }
+                    if first.snippet.is_none() {
+                        first.snippet = result.snippet;
+                    }
+                }
+                None => {
+                    positions.insert(key, merged.len());
+                    merged.push((fused, result));
+                }
+            }
+        }
+    }
+    if attempted > 0 && errors.len() == attempted {
+        return Err(errors.join("; "));
+    }
+
+    merged.sort_by(|a, b| b.0.total_cmp(&a.0));
+    Ok((
+        merged
+            .into_iter()
+            .map(|(score, mut result)| {
+                result.score = Some(score);
+                result
+            })
+            .collect(),
+        errors,
+    ))
+}
+
+/// Runs a search as `options` asks: SearXNG pages from `start` (merging
+/// `deep` pages) when no providers are named, otherwise one fan-out over
+/// the named providers, which has no further pages. Returns the results,
+/// the next page's cursor and the errors of providers that failed.
+pub async fn run_search(
+    app: &tauri::AppHandle,
+    client: &reqwest::Client,
+    api_key: Option<&str>,
+    query: &str,
+    options: &SearchOptions,
+    start: u32,
+) -> Result<(Vec<SearXNGResult>, Option<String>, Vec<String>), String> {
+    let state = app.state::<SearchProviders>();
+    if options.providers.is_empty() {
+        let pages = options
+            .deep
+            .unwrap_or(1)
+            .clamp(1, crate::search::MAX_DEEP_PAGES);
+        let (results, next) =
+            crate::search::search_pages(client, &state.searxng_url(), query, options, start, pages)
+                .await?;
+        return Ok((results, next, Vec::new()));
+    }
+
+    let providers = state.resolve(&options.providers)?;
+    let options = SearchOptions {
+        pageno: Some(start),
+        ..options.clone()
+    };
+    let ctx = SearchContext {
+        app,
+        client,
+        api_key,
+        options: &options,
+    };
+    let (results, errors) = fan_out(&ctx, &providers, query).await?;
+    Ok((results, None, errors))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn result(url: &str) -> SearXNGResult {
+        SearXNGResult {
+            url: url.to_string(),
+            title: url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn api(name: &str, url_template: &str) -> JsonApiConfig {
+        JsonApiConfig {
+            name: name.to_string(),
+            url_template: url_template.to_string(),
+            results_path: String::new(),
+            url_path: "url".to_string(),
+            title_path: "title".to_string(),
+            snippet_path: None,
+            date_path: None,
+            score_path: None,
+            headers: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn json_path_follows_keys_and_indexes() {
+        let value = json!({"data": {"items": [{"id": 1}, {"id": 2}]}});
+        assert_eq!(json_path(&value, "data.items.1.id"), Some(&json!(2)));
+        assert_eq!(json_path(&value, "$.data.items.0.id"), Some(&json!(1)));
+        assert_eq!(json_path(&value, ""), Some(&value));
+        assert_eq!(json_path(&value, "data.missing"), None);
+        assert_eq!(json_path(&value, "data.items.5"), None);
+    }
+
+    #[test]
+    fn json_path_treats_numeric_keys_of_objects_as_keys() {
+        let value = json!({"2024": "year"});
+        assert_eq!(json_path(&value, "2024"), Some(&json!("year")));
+    }
+
+    #[test]
+    fn json_string_renders_scalars_and_skips_null() {
+        let value = json!({"s": "text", "n": 4.5, "b": true, "z": null});
+        assert_eq!(json_string(&value, "s").as_deref(), Some("text"));
+        assert_eq!(json_string(&value, "n").as_deref(), Some("4.5"));
+        assert_eq!(json_string(&value, "b").as_deref(), Some("true"));
+        assert_eq!(json_string(&value, "z"), None);
+        assert_eq!(json_string(&value, "missing"), None);
+    }
+
+    #[test]
+    fn fuse_ranks_agreement_above_a_single_top_hit() {
+        let runs = vec![
+            (
+                "a".to_string(),
+                Ok(vec![
+                    result("https://one.example/"),
+                    result("https://two.example/"),
+                ]),
+            ),
+            (
+                "b".to_string(),
+                Ok(vec![
+                    result("https://three.example/"),
+                    result("https://two.example"),
+                ]),
+            ),
+        ];
+        let (merged, errors) = fuse(runs).unwrap();
+        assert!(errors.is_empty());
+        let urls: Vec<_> = merged.iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(urls.len(), 3, "the two spellings of two.example merge");
+        assert_eq!(urls[0], "https://two.example/");
+        assert_eq!(merged[0].engines, vec!["a", "b"]);
+        let expected = 2.0 / (RRF_K + 2.0);
+        assert!((merged[0].score.unwrap() - expected).abs() < 1e-12);
+        // Equal first ranks keep provider order
+        assert_eq!(urls[1], "https://one.example/");
+        assert_eq!(merged[1].engine.as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn fuse_keeps_the_first_snippet_and_fills_a_missing_one() {
+        let with_snippet = |snippet: &str| SearXNGResult {
+            snippet: Some(snippet.to_string()),
+            ..result("https://x.example/")
+        };
+        let runs = vec![
+            ("a".to_string(), Ok(vec![result("https://x.example/")])),
+            ("b".to_string(), Ok(vec![with_snippet("from b")])),
+            ("c".to_string(), Ok(vec![with_snippet("from c")])),
+        ];
+        let (merged, _) = fuse(runs).unwrap();
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].snippet.as_deref(), Some("from b"));
+    }
+
+    #[test]
+    fn fuse_reports_failures_unless_all_fail() {
+        let (merged, errors) = fuse(vec![
+            ("a".to_string(), Ok(vec![result("https://x.example/")])),
+            ("b".to_string(), Err("down".to_string())),
+        ])
+        .unwrap();
+        assert_eq!(merged.len(), 1);
+        assert_eq!(errors, vec!["b: down"]);
+
+        let all_failed = fuse(vec![
+            ("a".to_string(), Err("timeout".to_string())),
+            ("b".to_string(), Err("down".to_string())),
+        ]);
+        assert_eq!(all_failed.unwrap_err(), "a: timeout; b: down");
+    }
+
+    #[test]
+    fn set_config_rejects_bad_provider_names() {
+        let dir = std::env::temp_dir().join(format!("providers-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let providers = SearchProviders::load(dir.clone());
+        let with_apis = |apis: Vec<JsonApiConfig>| ProviderConfig {
+            json_apis: apis,
+            ..Default::default()
+        };
+
+        for name in ["", "  ", SEARXNG, VAULT, FETCHED] {
+            let config = with_apis(vec![api(name, "https://api.example/?q={query}")]);
+            assert!(providers.set_config(config).is_err(), "accepted '{}'", name);
+        }
+        let twice = with_apis(vec![
+            api("docs", "https://a.example/?q={query}"),
+            api("docs", "https://b.example/?q={query}"),
+        ]);
+        assert!(providers.set_config(twice).is_err());
+        let no_query = with_apis(vec![api("docs", "https://a.example/")]);
+        assert!(providers.set_config(no_query).is_err());
+        assert!(providers.config().json_apis.is_empty());
+
+        let valid = with_apis(vec![api("docs", "https://a.example/?q={query}")]);
+        providers.set_config(valid).unwrap();
+        assert_eq!(providers.names(), vec![SEARXNG, VAULT, FETCHED, "docs"]);
+        let reloaded = SearchProviders::load(dir.clone());
+        assert_eq!(reloaded.config().json_apis.len(), 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    /// Serves one HTTP response and returns the request head it received
+    async fn serve_once(
+        status: &'static str,
+        body: String,
+    ) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut head = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = socket.read(&mut buf).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                head.extend_from_slice(&buf[..n]);
+            }
+            let response = format!(
+                "HTTP/1.1 {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&head).into_owned()
+        });
+        (base, handle)
+    }
+
+    #[tokio::test]
+    async fn json_api_fills_the_template_and_maps_results() {
+        let body = json!({"data": {"hits": [
+            {"link": "https://a.example/", "name": "A", "text": "first", "rank": 0.9},
+            {"name": "no url, skipped"},
+            {"link": "https://b.example/", "when": "2024-05-01"},
+        ]}});
+        let (base, server) = serve_once("200 OK", body.to_string()).await;
+        let provider = JsonApi {
+            config: JsonApiConfig {
+                results_path: "data.hits".to_string(),
+                url_path: "link".to_string(),
+                title_path: "name".to_string(),
+                snippet_path: Some("text".to_string()),
+                date_path: Some("when".to_string()),
+                score_path: Some("rank".to_string()),
+                headers: vec![("X-Api-Key".to_string(), "secret".to_string())],
+                ..api(
+                    "docs",
+                    &format!("{}/search?q={{query}}&p={{page}}&l={{language}}", base),
+                )
+            },
+        };
+        let options = SearchOptions {
+            pageno: Some(3),
+            language: Some("en-US".to_string()),
+            ..Default::default()
+        };
+
+        let results = provider
+            .query(&reqwest::Client::new(), "rust & tauri", &options)
+            .await
+            .unwrap();
+        let head = server.await.unwrap();
+        assert!(
+            head.starts_with("GET /search?q=rust%20%26%20tauri&p=3&l=en-US HTTP/1.1"),
+            "{}",
+            head
+        );
+        assert!(head.to_lowercase().contains("x-api-key: secret"));
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].url, "https://a.example/");
+        assert_eq!(results[0].title, "A");
+        assert_eq!(results[0].snippet.as_deref(), Some("first"));
+        assert_eq!(results[0].score, Some(0.9));
+        assert_eq!(results[0].engine.as_deref(), Some("docs"));
+        assert_eq!(results[1].title, "https://b.example/");
+        assert_eq!(results[1].published_date.as_deref(), Some("2024-05-01"));
+    }
+
+    #[tokio::test]
+    async fn json_api_reports_http_errors_and_missing_arrays() {
+        let (base, server) = serve_once("503 Service Unavailable", "{}".to_string()).await;
+        let provider = JsonApi {
+            config: api("docs", &format!("{}/?q={{query}}", base)),
+        };
+        let err = provider
+            .query(&reqwest::Client::new(), "x", &SearchOptions::default())
+            .await
+            .unwrap_err();
+        server.await.unwrap();
+        assert!(err.starts_with("HTTP 503"), "{}", err);
+
+        let (base, server) = serve_once("200 OK", json!({"items": []}).to_string()).await;
+        let provider = JsonApi {
+            config: JsonApiConfig {
+                results_path: "results".to_string(),
+                ..api("docs", &format!("{}/?q={{query}}", base))
+            },
+        };
+        let err = provider
+            .query(&reqwest::Client::new(), "x", &SearchOptions::default())
+            .await
+            .unwrap_err();
+        server.await.unwrap();
+        assert_eq!(err, "No result array at 'results'");
+    }
+}
@@ -0,0 +1,431 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{Emitter, Manager};
+
+use crate::diff;
+use crate::fetch::CacheMode;
+use crate::vault::{self, FrontmatterValue, VaultGraph};
+use crate::warc::{self, Snapshots};
+
+/// How a changed page is reflected in its note
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RefreshMode {
+    /// Keep the note and append a "Changes since" section
+    #[default]
+    Append,
+    /// Rewrite the note body from the new content, then append the changes
+    Replace,
+}
+
+/// Scheduler settings, persisted to `<app data>/refresh.json`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_interval_hours")]
+    pub interval_hours: u64,
+    #[serde(default)]
+    pub mode: RefreshMode,
+    /// Summarizes changes and re-summarizes in `replace` mode; raw content is
+    /// used when empty
+    #[serde(default)]
+    pub model_name: String,
+    #[serde(default)]
+    pub obsidian_api_key: String,
+}
+
+fn default_interval_hours() -> u64 {
+    24
+}
+
+impl Default for RefreshConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_hours: default_interval_hours(),
+            mode: RefreshMode::default(),
+            model_name: String::new(),
+            obsidian_api_key: String::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Default)]
+pub struct RefreshReport {
+    pub checked: usize,
+    pub unchanged: usize,
+    pub changed: Vec<String>,
+    pub failed: Vec<(String, String)>,
+}
+
+/// Config plus the last extracted text of every tracked source
+pub struct RefreshState {
+    dir: PathBuf,
+    config: Mutex<RefreshConfig>,
+    /// Held while a cycle runs so manual and scheduled checks don't overlap
+    running: tokio::sync::Mutex<()>,
+}
+
+impl RefreshState {
+    pub fn load(dir: PathBuf) -> Result<Self, String> {
+        std::fs::create_dir_all(dir.join("snapshots"))
+            .map_err(|e| format!("Failed to create snapshot dir: {}", e))?;
+        let config = std::fs::read_to_string(dir.join("refresh.json"))
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+        Ok(Self {
+            dir,
+            config: Mutex::new(config),
+            running: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    pub fn config(&self) -> RefreshConfig {
+        self.config.lock().unwrap().clone()
+    }
+
+    pub fn set_config(&self, config: RefreshConfig) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+        std::fs::write(self.dir.join("refresh.json"), json)
+            .map_err(|e| format!("Failed to save refresh config: {}", e))?;
+        *self.config.lock().unwrap() = config;
+        Ok(())
+    }
+
+    fn snapshot_path(&self, url: &str) -> PathBuf {
+        self.dir.join("snapshots").join(format!(
+            "{}.txt",
+            content_hash(&crate::urls::normalize_url(url))
+        ))
+    }
+
+    /// Last extracted text stored for a source URL
+    pub fn snapshot(&self, url: &str) -> Option<String> {
+        std::fs::read_to_string(self.snapshot_path(url)).ok()
+    }
+
+    pub fn store_snapshot(&self, url: &str, text: &str) -> Result<(), String> {
+        std::fs::write(self.snapshot_path(url), text)
+            .map_err(|e| format!("Failed to store snapshot: {}", e))
+    }
+}
+
+pub fn content_hash(text: &str) -> String {
+    format!("{:x}", Sha256::digest(text.as_bytes()))
+}
+
+/// Prefix of `text_hash`. Bump it when extraction output changes shape (it
+/// became Markdown with `md1`) so older hashes start a new baseline instead
+/// of marking every page as changed.
+const TEXT_HASH_VERSION: &str = "md1:";
+
+/// Hash of extracted page text, as stored in a note's `content_hash`
+pub fn text_hash(text: &str) -> String {
+    format!("{}{}", TEXT_HASH_VERSION, content_hash(text))
+}
+
+fn text_field(fm: &BTreeMap<String, FrontmatterValue>, key: &str) -> Option<String> {
+    match fm.get(key) {
+        Some(FrontmatterValue::Text(v)) if !v.is_empty() => Some(v.clone()),
+        _ => None,
+    }
+}
+
+/// Only web pages can be re-fetched; repository ingests record a local
+/// folder as their source
+fn is_web_source(source: &str) -> bool {
+    reqwest::Url::parse(source).is_ok_and(|u| matches!(u.scheme(), "http" | "https"))
+}
+
+/// Background loop: checks due notes every 15 minutes while enabled
+pub async fn run_scheduler(app: tauri::AppHandle) {
+    loop {
+        tokio::time::sleep(Duration::from_secs(15 * 60)).await;
+        let config = app.state::<RefreshState>().config();
+        if !config.enabled || config.obsidian_api_key.is_empty() {
+            continue;
+        }
+        match run_cycle(&app, &config, false).await {
+            Ok(report) => {
+                let _ = app.emit("refresh_report", report);
+            }
+            Err(e) => {
+                let _ = app.emit(
+                    "workflow_log",
+                    crate::WorkflowLog {
+                        step: "acquisition".to_string(),
+                        status: "error".to_string(),
+                        message: format!("Scheduled refresh failed: {}", e),
+                    },
+                );
+            }
+        }
+    }
+}
+
+/// Re-fetches the `source:` of every fetched note that is due (or all of
+/// them when `force` is set) and records what changed.
+pub async fn run_cycle(
+    app: &tauri::AppHandle,
+    config: &RefreshConfig,
+    force: bool,
+) -> Result<RefreshReport, String> {
+    let state = app.state::<RefreshState>();
+    let _guard = state
+        .running
+        .try_lock()
+        .map_err(|_| "A refresh is already running".to_string())?;
+
+    let client = reqwest::Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let api_key = &config.obsidian_api_key;
+
+    let graph = app.state::<RwLock<VaultGraph>>();
+    crate::ensure_vault_graph(&graph, &client, api_key).await?;
+
+    let now = chrono::Local::now();
+    let due_before = now - chrono::Duration::hours(config.interval_hours as i64);
+    let due: Vec<(String, String)> = graph
+        .read()
+        .unwrap()
+        .notes()
+        .filter(|n| n.tags.iter().any(|t| t == "fetched"))
+        .filter_map(|n| text_field(&n.frontmatter, "source").map(|src| (n, src)))
+        .filter(|(_, src)| is_web_source(src))
+        .filter(|(n, _)| {
+            force
+                || text_field(&n.frontmatter, "last_checked")
+                    .and_then(|d| chrono::DateTime::parse_from_rfc3339(&d).ok())
+                    .is_none_or(|d| d < due_before)
+        })
+        .map(|(n, src)| (n.path.clone(), src))
+        .collect();
+
+    let mut report = RefreshReport::default();
+    for (path, source) in due {
+        report.checked += 1;
+        match refresh_note(app, &client, &state, config, &path, &source).await {
+            Ok((rewritten, changed)) => {
+                // Keeps `last_checked` current so the note isn't due again
+                graph
+                    .write()
+                    .unwrap()
+                    .upsert(vault::parse_note(&path, &rewritten));
+                if changed {
+                    report.changed.push(path);
+                } else {
+                    report.unchanged += 1;
+                }
+            }
+            Err(e) => report.failed.push((path, e)),
+        }
+    }
+    Ok(report)
+}
+
+/// Checks a single note. Returns the rewritten note and whether the page changed.
+async fn refresh_note(
+    app: &tauri::AppHandle,
+    client: &reqwest::Client,
+    state: &RefreshState,
+    config: &RefreshConfig,
+    path: &str,
+    source: &str,
+) -> Result<(String, bool), String> {
+    let api_key = &config.obsidian_api_key;
+    // Revalidated, or an unexpired cache entry would always look unchanged
+    let document =
+        crate::fetch_url_content(app, client, source, true, CacheMode::Revalidate).await?;
+    let text = &document.content;
+    let hash = text_hash(text);
+    let note = crate::fetch_vault_file(client, path, api_key).await?;
+    let (frontmatter, body) = vault::split_frontmatter(&note);
+
+    let now = chrono::Local::now();
+    let mut fields = vec![("last_checked", now.to_rfc3339())];
+
+    let updated = match changed_since(&frontmatter, &hash) {
+        None => None,
+        Some(since) => {
+            let changes = diff::diff_documents(&state.snapshot(source).unwrap_or_default(), text);
+            let details = if changes.is_empty() {
+                "_Content hash changed, but no textual differences were found._".to_string()
+            } else {
+                changes.to_markdown()
+            };
+            // A short "what changed" summary goes above the raw diff when a model is set
+            let overview = if changes.is_empty() || config.model_name.is_empty() {
+                None
+            } else {
+                let prompt = crate::Message {
+                    role: "user".to_string(),
+                    content: diff::summary_prompt(source, &changes),
+                };
+                crate::call_ollama_chat(app, client, &[prompt], &config.model_name, false)
+                    .await
+                    .ok()
+            };
+            let section = changes_section(&since, overview.as_deref(), &details);
+
+            let new_body = match config.mode {
+                RefreshMode::Append => format!("{}{}", body.trim_end(), section),
+                RefreshMode::Replace => {
+                    let content = if config.model_name.is_empty() {
+                        text.clone()
+                    } else {
+                        crate::call_ollama_summarize(
+                            app,
+                            client,
+                            &document,
+                            &config.model_name,
+                            false,
+                        )
+                        .await?
+                    };
+                    let title = vault::note_name(path);
+                    format!("# {}\n\n{}{}", title, content, section)
+                }
+            };
+            Some(new_body)
+        }
+    };
+
+    fields.push(("content_hash", hash));
+    if let Some(snapshot) = app.state::<Snapshots>().latest(source) {
+        fields.extend(warc::frontmatter_fields(&snapshot));
+    }
+    let rewritten = rewrite_note(&note, updated.as_deref(), &fields);
+
+    crate::write_vault_file(client, path, &rewritten, api_key).await?;
+    state.store_snapshot(source, text)?;
+
+    Ok((rewritten, updated.is_some()))
+}
+
+/// The date the note last matched the page when `hash` differs from the
+/// stored `content_hash`; `None` when nothing changed or this check only
+/// sets the baseline
+fn changed_since(frontmatter: &BTreeMap<String, FrontmatterValue>, hash: &str) -> Option<String> {
+    let previous = text_field(frontmatter, "content_hash")?;
+    // First check, or one after an extraction change, only sets the baseline
+    if previous == hash || !previous.starts_with(TEXT_HASH_VERSION) {
+        return None;
+    }
+    Some(
+        text_field(frontmatter, "last_checked")
+            .or(text_field(frontmatter, "fetched_date"))
+            .map(|d| d.chars().take(10).collect())
+            .unwrap_or_else(|| "last check".to_string()),
+    )
+}
+
+/// The section added to a changed note, with the model's overview (if any)
+/// above the detailed diff
+fn changes_section(since: &str, overview: Option<&str>, details: &str) -> String {
+    match overview {
+        Some(overview) => format!(
+            "\n\n## Changes since {}\n\n{}\n\n{}\n",
+            since,
+            overview.trim(),
+            details
+        ),
+        None => format!("\n\n## Changes since {}\n\n{}\n", since, details),
+    }
+}
+
+/// The note as written back after a check: `new_body` (when the page
+/// changed) under the original frontmatter, then `fields` set
+fn rewrite_note(note: &str, new_body: Option<&str>, fields: &[(&str, String)]) -> String {
+    let base = match new_body {
+        Some(new_body) => {
+            let (_, body) = vault::split_frontmatter(note);
+            let head_len = note.len() - body.len();
+            format!("{}\n{}", note[..head_len].trim_end(), new_body)
+        }
+        None => note.to_string(),
+    };
+    vault::set_frontmatter_fields(&base, fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_web_sources_are_refreshed() {
+        assert!(is_web_source("https://docs.rs/serde"));
+        assert!(is_web_source("http://localhost:8080/docs"));
+        assert!(!is_web_source("/home/me/src/project"));
+        assert!(!is_web_source("C:\\src\\project"));
+        assert!(!is_web_source("file:///home/me/src/project"));
+    }
+
+    const NOTE: &str = "---\nsource: https://a.dev/\nlast_checked: 2026-01-02T10:00:00+00:00\ncontent_hash: md1:old\ntags: [fetched]\n---\n# A\n\nBody text\n";
+
+    #[test]
+    fn only_a_changed_current_hash_counts_as_a_change() {
+        let (fm, _) = vault::split_frontmatter(NOTE);
+        assert_eq!(changed_since(&fm, "md1:new").as_deref(), Some("2026-01-02"));
+        assert_eq!(changed_since(&fm, "md1:old"), None);
+
+        // No hash yet, or one from an older extraction: baseline only
+        let (fm, _) = vault::split_frontmatter("---\nsource: https://a.dev/\n---\nBody");
+        assert_eq!(changed_since(&fm, "md1:new"), None);
+        let (fm, _) = vault::split_frontmatter("---\ncontent_hash: abc123\n---\nBody");
+        assert_eq!(changed_since(&fm, "md1:new"), None);
+
+        let (fm, _) =
+            vault::split_frontmatter("---\ncontent_hash: md1:old\nfetched_date: 2025-12-31\n---\n");
+        assert_eq!(changed_since(&fm, "md1:new").as_deref(), Some("2025-12-31"));
+        let (fm, _) = vault::split_frontmatter("---\ncontent_hash: md1:old\n---\n");
+        assert_eq!(changed_since(&fm, "md1:new").as_deref(), Some("last check"));
+    }
+
+    #[test]
+    fn changes_are_appended_below_the_body() {
+        let (_, body) = vault::split_frontmatter(NOTE);
+        let section = changes_section("2026-01-02", Some(" Pricing moved. \n"), "- diff");
+        assert_eq!(
+            section,
+            "\n\n## Changes since 2026-01-02\n\nPricing moved.\n\n- diff\n"
+        );
+        let new_body = format!("{}{}", body.trim_end(), section);
+        let fields = [("content_hash", "md1:new".to_string())];
+        let rewritten = rewrite_note(NOTE, Some(&new_body), &fields);
+
+        let (fm, body) = vault::split_frontmatter(&rewritten);
+        assert_eq!(text_field(&fm, "content_hash").as_deref(), Some("md1:new"));
+        assert_eq!(text_field(&fm, "source").as_deref(), Some("https://a.dev/"));
+        assert!(body.contains("# A\n\nBody text\n\n## Changes since 2026-01-02"));
+        assert_eq!(rewritten.matches("Body text").count(), 1);
+
+        let section = changes_section("2026-01-02", None, "- diff");
+        assert_eq!(section, "\n\n## Changes since 2026-01-02\n\n- diff\n");
+    }
+
+    #[test]
+    fn unchanged_notes_only_get_new_check_fields() {
+        let fields = [
+            ("last_checked", "2026-02-01T09:00:00+00:00".to_string()),
+            ("content_hash", "md1:old".to_string()),
+        ];
+        let rewritten = rewrite_note(NOTE, None, &fields);
+        let (fm, body) = vault::split_frontmatter(&rewritten);
+        assert_eq!(
+            text_field(&fm, "last_checked").as_deref(),
+            Some("2026-02-01T09:00:00+00:00")
+        );
+        assert_eq!(text_field(&fm, "content_hash").as_deref(), Some("md1:old"));
+        assert_eq!(body, vault::split_frontmatter(NOTE).1);
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::{HashSet, VecDeque};
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+/// HTML pages whose static text is shorter than this are rendered
+pub const MIN_STATIC_CHARS: usize = 200;
+
+/// Upper bound for launching the browser and loading one page
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(15);
+const PAGE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The network counts as idle after this long with at most
+/// [`IDLE_MAX_REQUESTS`] requests in flight (Puppeteer's `networkidle2`;
+/// long-polling and analytics beacons never finish)
+const IDLE_TIME: Duration = Duration::from_millis(500);
+const IDLE_MAX_REQUESTS: usize = 2;
+
+/// Executables tried, in order, when `THEFETCHER_CHROME` isn't set
+const BROWSER_NAMES: &[&str] = &[
+    "google-chrome",
+    "google-chrome-stable",
+    "chromium",
+    "chromium-browser",
+    "microsoft-edge",
+    "brave-browser",
+];
+
+#[cfg(target_os = "macos")]
+const INSTALL_PATHS: &[&str] = &[
+    "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+    "/Applications/Chromium.app/Contents/MacOS/Chromium",
+    "/Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+];
+
+#[cfg(target_os = "windows")]
+const INSTALL_PATHS: &[&str] = &[
+    r"C:\Program Files\Google\Chrome\Application\chrome.exe",
+    r"C:\Program Files (x86)\Google\Chrome\Application\chrome.exe",
+    r"C:\Program Files (x86)\Microsoft\Edge\Application\msedge.exe",
+];
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const INSTALL_PATHS: &[&str] = &["/snap/bin/chromium"];
+
+/// A locally installed Chromium-based browser: `THEFETCHER_CHROME`, then
+/// the usual executable names on `PATH`, then well-known install paths
+pub fn find_browser() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("THEFETCHER_CHROME") {
+        return Some(PathBuf::from(path));
+    }
+    let path_dirs: Vec<PathBuf> = std::env::var_os("PATH")
+        .map(|p| std::env::split_paths(&p).collect())
+        .unwrap_or_default();
+    BROWSER_NAMES
+        .iter()
+        .flat_map(|name| {
+            path_dirs
+                .iter()
+                .map(move |dir| dir.join(format!("{}{}", name, std::env::consts::EXE_SUFFIX)))
+        })
+        .chain(INSTALL_PATHS.iter().map(PathBuf::from))
+        .find(|path| path.is_file())
+}
+
+/// A browser process with its own throwaway profile
+struct Browser {
+    child: tokio::process::Child,
+    profile: PathBuf,
+}
+
+impl Drop for Browser {
+    fn drop(&mut self) {
+        let _ = self.child.start_kill();
+        let _ = std::fs::remove_dir_all(&self.profile);
+    }
+}
+
+impl Browser {
+    /// Starts the browser with remote debugging on a free port and returns
+    /// it with the DevTools WebSocket URL it prints on startup
+    async fn launch(headless: bool, user_agent: &str) -> Result<(Self, String), String> {
+        let executable = find_browser().ok_or("No Chromium-based browser found")?;
+        let profile = std::env::temp_dir().join(format!(
+            "thefetcher-render-{}-{}",
+            std::process::id(),
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+
+        let mut command = tokio::process::Command::new(&executable);
+        command
+            .arg("--remote-debugging-port=0")
+            .arg(format!("--user-data-dir={}", profile.display()))
+            .arg(format!("--user-agent={}", user_agent))
+            .args([
+                "--no-first-run",
+                "--no-default-browser-check",
+                "--disable-extensions",
+                "--disable-sync",
+                "--window-size=1280,900",
+            ])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if headless {
+            command.arg("--headless=new");
+        }
+        command.arg("about:blank");
+
+        let mut child = command
+            .spawn()
+            .map_err(|e| format!("Failed to start {}: {}", executable.display(), e))?;
+        let stderr = child.stderr.take().ok_or("No browser stderr")?;
+        let browser = Self { child, profile };
+
+        let mut lines = BufReader::new(stderr).lines();
+        let ws_url = tokio::time::timeout(STARTUP_TIMEOUT, async {
+            while let Ok(Some(line)) = lines.next_line().await {
+                if let Some(url) = line.trim().strip_prefix("DevTools listening on ") {
+                    return Some(url.to_string());
+                }
+            }
+            None
+        })
+        .await
+        .ok()
+        .flatten()
+        .ok_or("Browser did not open a DevTools endpoint")?;
+
+        // Keep draining stderr so a chatty browser never blocks on a full pipe
+        tokio::spawn(async move { while let Ok(Some(_)) = lines.next_line().await {} });
+        Ok((browser, ws_url))
+    }
+}
+
+/// Minimal Chrome DevTools Protocol client over one WebSocket
+struct Cdp {
+    ws: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    next_id: u64,
+    /// Events received while waiting for a command's reply
+    events: VecDeque<Value>,
+}
+
+impl Cdp {
+    async fn connect(url: &str) -> Result<Self, String> {
+        let (ws, _) = tokio_tungstenite::connect_async(url)
+            .await
+            .map_err(|e| format!("DevTools connection failed: {}", e))?;
+        Ok(Self {
+            ws,
+            next_id: 0,
+            events: VecDeque::new(),
+        })
+    }
+
+    async fn receive(&mut self) -> Result<Value, String> {
+        loop {
+            match self.ws.next().await {
+                Some(Ok(Message::Text(text))) => {
+                    return serde_json::from_str(text.as_str())
+                        .map_err(|e| format!("Invalid DevTools message: {}", e))
+                }
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => return Err(format!("DevTools connection lost: {}", e)),
+                None => return Err("Browser closed the DevTools connection".to_string()),
+            }
+        }
+    }
+
+    /// Sends a command (to a page session when given) and waits for its reply
+    async fn call(
+        &mut self,
+        session: Option<&str>,
+        method: &str,
+        params: Value,
+    ) -> Result<Value, String> {
+        self.next_id += 1;
+        let id = self.next_id;
+        let mut message = json!({ "id": id, "method": method, "params": params });
+        if let Some(session) = session {
+            message["sessionId"] = json!(session);
+        }
+        self.ws
+            .send(Message::Text(message.to_string()))
+            .await
+            .map_err(|e| format!("DevTools send failed: {}", e))?;
+
+        loop {
+            let message = self.receive().await?;
+            if message["id"].as_u64() == Some(id) {
+                if let Some(error) = message.get("error") {
+                    return Err(format!("{} failed: {}", method, error["message"]));
+                }
+                return Ok(message["result"].clone());
+            }
+            if message.get("method").is_some() {
+                self.events.push_back(message);
+            }
+        }
+    }
+
+    async fn next_event(&mut self) -> Result<Value, String> {
+        match self.events.pop_front() {
+            Some(event) => Ok(event),
+            None => self.receive().await,
+        }
+    }
+}
+
+/// Loads `url` in a fresh Chromium, waits for the load event and network
+/// idle, and returns the rendered DOM as HTML. With `headless` off the
+/// browser window is shown while the page loads.
+pub async fn render_page(url: &str, headless: bool, user_agent: &str) -> Result<String, String> {
+    let (_browser, ws_url) = Browser::launch(headless, user_agent).await?;
+    let mut cdp = Cdp::connect(&ws_url).await?;
+
+    let target = cdp
+        .call(None, "Target.createTarget", json!({ "url": "about:blank" }))
+        .await?;
+    let session = cdp
+        .call(
+            None,
+            "Target.attachToTarget",
+            json!({ "targetId": target["targetId"], "flatten": true }),
+        )
+        .await?["sessionId"]
+        .as_str()
+        .ok_or("No DevTools session")?
+        .to_string();
+    let session = Some(session.as_str());
+
+    cdp.call(session, "Page.enable", json!({})).await?;
+    cdp.call(session, "Network.enable", json!({})).await?;
+    let navigation = cdp
+        .call(session, "Page.navigate", json!({ "url": url }))
+        .await?;
+    if let Some(error) = navigation["errorText"].as_str() {
+        return Err(format!("Navigation failed: {}", error));
+    }
+
+    let deadline = Instant::now() + PAGE_TIMEOUT;
+    let mut in_flight: HashSet<String> = HashSet::new();
+    let mut loaded = false;
+    let mut last_activity = Instant::now();
+    loop {
+        let idle_at = last_activity + IDLE_TIME;
+        if loaded && in_flight.len() <= IDLE_MAX_REQUESTS && Instant::now() >= idle_at {
+            break;
+        }
+        // Past the deadline the DOM is taken as it is
+        if Instant::now() >= deadline {
+            break;
+        }
+        let wake = if loaded {
+            idle_at.min(deadline)
+        } else {
+            deadline
+        };
+        let event = match tokio::time::timeout_at(wake, cdp.next_event()).await {
+            Ok(event) => event?,
+            Err(_) => continue,
+        };
+        if event["sessionId"].as_str() != session {
+            continue;
+        }
+        let request_id = event["params"]["requestId"].as_str().map(|s| s.to_string());
+        match event["method"].as_str().unwrap_or_default() {
+            "Page.loadEventFired" => loaded = true,
+            "Network.requestWillBeSent" => {
+                in_flight.extend(request_id);
+            }
+            "Network.loadingFinished" | "Network.loadingFailed" => {
+                if let Some(id) = request_id {
+                    in_flight.remove(&id);
+                }
+            }
+            _ => continue,
+        }
+        last_activity = Instant::now();
+    }
+
+    let result = cdp
+        .call(
+            session,
+            "Runtime.evaluate",
+            json!({
+                "expression": "document.documentElement.outerHTML",
+                "returnByValue": true,
+            }),
+        )
+        .await?;
+    let html = result["result"]["value"]
+        .as_str()
+        .ok_or("Could not read the rendered page")?
+        .to_string();
+    let _ = cdp.call(None, "Browser.close", json!({})).await;
+    Ok(html)
+}
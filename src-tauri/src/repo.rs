@@ -0,0 +1,704 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use crate::profiles::HeaderProfiles;
+use crate::vault::VaultGraph;
+
+/// Upper bounds so a huge monorepo can't flood the vault
+const MAX_FILES: usize = 200;
+const MAX_FILE_BYTES: u64 = 512 * 1024;
+
+/// Folders never walked in local checkouts
+const SKIPPED_DIRS: &[&str] = &[".git", "node_modules", "target", "vendor", "dist", "build"];
+
+/// Folders whose Markdown is always ingested, next to README files
+const DOC_DIRS: &[&str] = &["docs", "doc", "documentation"];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RepoMode {
+    /// One note with a section per file
+    #[default]
+    Merged,
+    /// A note per file under `Reference/Docs/<repo>/` plus an index note
+    Folder,
+}
+
+/// Folders local checkouts may be read from, persisted to
+/// `<app data>/repos.json`. Empty means local ingestion is off.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RepoConfig {
+    #[serde(default)]
+    pub local_roots: Vec<PathBuf>,
+}
+
+pub struct RepoSettings {
+    dir: PathBuf,
+    config: Mutex<RepoConfig>,
+}
+
+impl RepoSettings {
+    pub fn load(dir: PathBuf) -> Self {
+        let config = std::fs::read_to_string(dir.join("repos.json"))
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+        Self {
+            dir,
+            config: Mutex::new(config),
+        }
+    }
+
+    pub fn config(&self) -> RepoConfig {
+        self.config.lock().unwrap().clone()
+    }
+
+    /// Roots are stored canonicalized, so later checks compare real paths
+    pub fn set_config(&self, config: RepoConfig) -> Result<(), String> {
+        let local_roots = config
+            .local_roots
+            .iter()
+            .map(|root| {
+                if !root.is_absolute() {
+                    return Err(format!("Not an absolute path: {}", root.display()));
+                }
+                root.canonicalize()
+                    .ok()
+                    .filter(|p| p.is_dir())
+                    .ok_or_else(|| format!("Not a directory: {}", root.display()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let config = RepoConfig { local_roots };
+        let json = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+        std::fs::write(self.dir.join("repos.json"), json)
+            .map_err(|e| format!("Failed to save repository config: {}", e))?;
+        *self.config.lock().unwrap() = config;
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+pub struct RepoIngestResult {
+    /// The merged note, or the index note in folder mode
+    pub file_path: String,
+    pub notes: Vec<String>,
+    pub files: usize,
+}
+
+/// Where the Markdown comes from
+enum RepoSource {
+    GitHub {
+        owner: String,
+        repo: String,
+        branch: Option<String>,
+        /// Optional `tree/<branch>/<subdir>` restriction
+        subdir: String,
+    },
+    Local(PathBuf),
+}
+
+impl RepoSource {
+    /// Local folders are accepted only under one of `local_roots`
+    fn parse(source: &str, local_roots: &[PathBuf]) -> Result<Self, String> {
+        let source = source.trim();
+        let path = Path::new(source);
+        if path.is_dir() {
+            let real = path.canonicalize().map_err(|e| e.to_string())?;
+            if !local_roots.iter().any(|root| real.starts_with(root)) {
+                return Err(format!(
+                    "{} is not inside a folder allowed in the repository settings",
+                    source
+                ));
+            }
+            return Ok(RepoSource::Local(real));
+        }
+        let url = reqwest::Url::parse(source)
+            .map_err(|_| format!("Not a directory or repository URL: {}", source))?;
+        if url.host_str().map(|h| h.trim_start_matches("www.")) != Some("github.com") {
+            return Err("Only GitHub repository URLs and local paths are supported".to_string());
+        }
+        let segments: Vec<&str> = url
+            .path_segments()
+            .map(|s| s.filter(|s| !s.is_empty()).collect())
+            .unwrap_or_default();
+        let [owner, repo, rest @ ..] = segments.as_slice() else {
+            return Err(format!("Not a repository URL: {}", source));
+        };
+        let (branch, subdir) = match rest {
+            ["tree" | "blob", branch, path @ ..] => (Some(branch.to_string()), path.join("/")),
+            _ => (None, String::new()),
+        };
+        Ok(RepoSource::GitHub {
+            owner: owner.to_string(),
+            repo: repo.trim_end_matches(".git").to_string(),
+            branch,
+            subdir,
+        })
+    }
+
+    fn name(&self) -> String {
+        match self {
+            RepoSource::GitHub { repo, .. } => repo.clone(),
+            RepoSource::Local(path) => path
+                .canonicalize()
+                .ok()
+                .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+                .unwrap_or_else(|| "repository".to_string()),
+        }
+    }
+}
+
+fn is_markdown(path: &str) -> bool {
+    let lower = path.to_lowercase();
+    lower.ends_with(".md") || lower.ends_with(".markdown") || lower.ends_with(".mdx")
+}
+
+/// README files anywhere, plus all Markdown under a docs folder. With an
+/// explicit folder (`docs_path` or a `/tree/<branch>/<dir>` URL), every
+/// Markdown file inside it counts.
+fn is_doc_file(path: &str, subdir: &str) -> bool {
+    if !is_markdown(path) {
+        return false;
+    }
+    if !subdir.is_empty() {
+        return path.starts_with(&format!("{}/", subdir));
+    }
+    let file = path.rsplit('/').next().unwrap_or(path);
+    let mut dirs = path.split('/').rev().skip(1);
+    file.to_lowercase().starts_with("readme")
+        || dirs.any(|dir| DOC_DIRS.contains(&dir.to_lowercase().as_str()))
+}
+
+/// README first, then docs in path order
+fn sort_files(files: &mut [(String, String)]) {
+    files.sort_by_key(|(path, _)| {
+        let root_readme = !path.contains('/') && path.to_lowercase().starts_with("readme");
+        (
+            !root_readme,
+            path.matches('/').count() > 0,
+            path.to_lowercase(),
+        )
+    });
+}
+
+/// (repo-relative path, Markdown) for every documentation file
+async fn collect_files(
+    app: &tauri::AppHandle,
+    client: &reqwest::Client,
+    source: &RepoSource,
+    docs_path: Option<&str>,
+) -> Result<(Vec<(String, String)>, Option<String>), String> {
+    match source {
+        RepoSource::Local(root) => {
+            let subdir = docs_path.unwrap_or("").trim_matches('/').to_string();
+            let mut paths = Vec::new();
+            walk_local(root, root, &mut paths);
+            let mut files = Vec::new();
+            for path in paths.into_iter().filter(|p| is_doc_file(p, &subdir)) {
+                if files.len() >= MAX_FILES {
+                    break;
+                }
+                let full = root.join(&path);
+                if std::fs::metadata(&full).map_or(true, |m| m.len() > MAX_FILE_BYTES) {
+                    continue;
+                }
+                if let Ok(bytes) = std::fs::read(&full) {
+                    files.push((path, crate::charset::decode(&bytes, None, "")));
+                }
+            }
+            Ok((files, None))
+        }
+        RepoSource::GitHub {
+            owner,
+            repo,
+            branch,
+            subdir,
+        } => {
+            let headers = app
+                .state::<HeaderProfiles>()
+                .profile_for("https://api.github.com/");
+            let api = format!("https://api.github.com/repos/{}/{}", owner, repo);
+            let get_json = |url: String| {
+                let request = client
+                    .get(url)
+                    .header("User-Agent", &headers.user_agent)
+                    .header("Accept", "application/vnd.github+json");
+                async move {
+                    let res = request
+                        .send()
+                        .await
+                        .map_err(|e| format!("GitHub request failed: {}", e))?;
+                    if !res.status().is_success() {
+                        return Err(format!("GitHub API returned HTTP {}", res.status()));
+                    }
+                    res.json::<serde_json::Value>()
+                        .await
+                        .map_err(|e| format!("Invalid GitHub response: {}", e))
+                }
+            };
+
+            let branch = match branch {
+                Some(b) => b.clone(),
+                None => get_json(api.clone()).await?["default_branch"]
+                    .as_str()
+                    .unwrap_or("main")
+                    .to_string(),
+            };
+            let tree = get_json(format!("{}/git/trees/{}?recursive=1", api, branch)).await?;
+            let subdir = docs_path.unwrap_or(subdir).trim_matches('/').to_string();
+            let paths: Vec<String> = tree["tree"]
+                .as_array()
+                .map(|entries| {
+                    entries
+                        .iter()
+                        .filter(|e| e["type"] == "blob")
+                        .filter(|e| e["size"].as_u64().unwrap_or(0) <= MAX_FILE_BYTES)
+                        .filter_map(|e| e["path"].as_str())
+                        .filter(|p| is_doc_file(p, &subdir))
+                        .take(MAX_FILES)
+                        .map(|p| p.to_string())
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            // Raw files are what the user asked for, not a crawl, so they skip
+            // robots.txt (raw.githubusercontent.com disallows everything)
+            let mut files = Vec::new();
+            for path in paths {
+                let raw = format!(
+                    "https://raw.githubusercontent.com/{}/{}/{}/{}",
+                    owner, repo, branch, path
+                );
+                let res = headers
+                    .apply(client.get(&raw))
+                    .send()
+                    .await
+                    .map_err(|e| format!("Failed to fetch {}: {}", path, e))?;
+                if !res.status().is_success() {
+                    continue;
+                }
+                let content_type = res
+                    .headers()
+                    .get("content-type")
+                    .and_then(|v| v.to_str().ok())
+                    .map(|v| v.to_string());
+                let bytes = res
+                    .bytes()
+                    .await
+                    .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+                files.push((
+                    path,
+                    crate::charset::decode(&bytes, content_type.as_deref(), &raw),
+                ));
+            }
+            Ok((files, Some(branch)))
+        }
+    }
+}
+
+fn walk_local(root: &Path, dir: &Path, out: &mut Vec<String>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        // Symlinks could lead outside the allowed folder
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_symlink() {
+            continue;
+        }
+        if file_type.is_dir() {
+            if !SKIPPED_DIRS.contains(&name.as_str()) && !name.starts_with('.') {
+                walk_local(root, &path, out);
+            }
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            out.push(relative.to_string_lossy().replace('\\', "/"));
+        }
+    }
+}
+
+/// `docs/a/../b.md` -> `docs/b.md`; a leading `/` means the repo root
+fn resolve_path(base_file: &str, target: &str) -> String {
+    let mut parts: Vec<&str> = if target.starts_with('/') {
+        Vec::new()
+    } else {
+        let mut dir: Vec<&str> = base_file.split('/').collect();
+        dir.pop();
+        dir
+    };
+    for segment in target.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            s => parts.push(s),
+        }
+    }
+    parts.join("/")
+}
+
+/// GitHub's heading anchor: lowercase, punctuation dropped, spaces to dashes
+fn github_slug(heading: &str) -> String {
+    heading
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_' || *c == ' ')
+        .map(|c| if c == ' ' { '-' } else { c })
+        .collect()
+}
+
+fn headings(markdown: &str) -> Vec<String> {
+    let mut in_code = false;
+    markdown
+        .lines()
+        .filter_map(|line| {
+            if line.trim_start().starts_with("```") {
+                in_code = !in_code;
+                return None;
+            }
+            if in_code {
+                return None;
+            }
+            let level = line.chars().take_while(|c| *c == '#').count();
+            ((1..=6).contains(&level) && line[level..].starts_with(' '))
+                .then(|| line[level..].trim().to_string())
+        })
+        .collect()
+}
+
+/// How links between ingested files are written
+struct LinkContext<'a> {
+    /// repo path -> wikilink target (`Reference/Docs/x/install` or `#docs/install.md`)
+    targets: &'a HashMap<String, String>,
+    /// repo path -> its headings, to turn GitHub anchors into heading links
+    headings: &'a HashMap<String, Vec<String>>,
+    /// Base for links to files that weren't ingested (GitHub blob URLs)
+    blob_base: Option<String>,
+    raw_base: Option<String>,
+    merged: bool,
+}
+
+impl LinkContext<'_> {
+    fn rewrite_link(&self, file: &str, text: &str, target: &str, image: bool) -> Option<String> {
+        let target = target.split_whitespace().next().unwrap_or(target);
+        let target = target.trim_matches(['<', '>']);
+        if target.is_empty() || target.contains("://") || target.starts_with("mailto:") {
+            return None;
+        }
+        let (path, anchor) = match target.split_once('#') {
+            Some((p, a)) => (p, Some(a)),
+            None => (target, None),
+        };
+        let resolved = if path.is_empty() {
+            file.to_string()
+        } else {
+            resolve_path(file, path)
+        };
+
+        let heading = anchor.and_then(|a| {
+            self.headings
+                .get(&resolved)?
+                .iter()
+                .find(|h| github_slug(h) == a.to_lowercase())
+                .cloned()
+        });
+
+        if !image {
+            if let Some(note) = self.targets.get(&resolved) {
+                let link = match (&heading, self.merged) {
+                    (Some(h), true) => format!("#{}", h),
+                    (Some(h), false) => format!("{}#{}", note, h),
+                    (None, _) => note.clone(),
+                };
+                return Some(format!("[[{}|{}]]", link, text));
+            }
+            // Same-file anchor in folder mode, e.g. a table of contents
+            if path.is_empty() {
+                return heading.map(|h| format!("[[#{}|{}]]", h, text));
+            }
+        }
+
+        let base = if image {
+            &self.raw_base
+        } else {
+            &self.blob_base
+        };
+        base.as_ref().map(|base| {
+            let absolute = format!("{}/{}", base, resolved);
+            let absolute = match anchor {
+                Some(a) => format!("{}#{}", absolute, a),
+                None => absolute,
+            };
+            format!("{}[{}]({})", if image { "!" } else { "" }, text, absolute)
+        })
+    }
+
+    /// Rewrites `[text](relative)` links outside code; headings are shifted
+    /// down by `demote` levels
+    fn convert(&self, file: &str, markdown: &str, demote: usize) -> String {
+        let mut out = Vec::new();
+        let mut in_code = false;
+        for line in markdown.lines() {
+            if line.trim_start().starts_with("```") {
+                in_code = !in_code;
+                out.push(line.to_string());
+                continue;
+            }
+            if in_code {
+                out.push(line.to_string());
+                continue;
+            }
+            let level = line.chars().take_while(|c| *c == '#').count();
+            let line = if demote > 0 && (1..=6).contains(&level) && line[level..].starts_with(' ') {
+                format!("{}{}", "#".repeat((level + demote).min(6)), &line[level..])
+            } else {
+                line.to_string()
+            };
+            out.push(self.convert_line(file, &line));
+        }
+        out.join("\n")
+    }
+
+    fn convert_line(&self, file: &str, line: &str) -> String {
+        let mut result = String::new();
+        let mut rest = line;
+        while let Some(mid) = rest.find("](") {
+            let Some(open) = rest[..mid].rfind('[') else {
+                result.push_str(&rest[..mid + 2]);
+                rest = &rest[mid + 2..];
+                continue;
+            };
+            let Some(close) = rest[mid + 2..].find(')') else {
+                break;
+            };
+            let image = open > 0 && rest[..open].ends_with('!');
+            let start = if image { open - 1 } else { open };
+            let text = &rest[open + 1..mid];
+            let target = &rest[mid + 2..mid + 2 + close];
+            let end = mid + 2 + close + 1;
+            match self.rewrite_link(file, text, target, image) {
+                Some(link) => {
+                    result.push_str(&rest[..start]);
+                    result.push_str(&link);
+                }
+                None => result.push_str(&rest[..end]),
+            }
+            rest = &rest[end..];
+        }
+        result.push_str(rest);
+        result
+    }
+}
+
+/// Leading `# Title` of a file, and the content without it
+fn split_title(markdown: &str) -> (Option<String>, &str) {
+    let trimmed = markdown.trim_start();
+    match trimmed.strip_prefix("# ") {
+        Some(rest) => {
+            let (title, body) = rest.split_once('\n').unwrap_or((rest, ""));
+            (Some(title.trim().to_string()), body)
+        }
+        None => (None, trimmed),
+    }
+}
+
+/// Ingests README and docs Markdown from a GitHub URL or a local checkout.
+/// The merged or index note replaces `existing_path` when given.
+pub async fn ingest(
+    app: &tauri::AppHandle,
+    client: &reqwest::Client,
+    source: &str,
+    api_key: &str,
+    mode: RepoMode,
+    docs_path: Option<&str>,
+    existing_path: Option<&str>,
+) -> Result<RepoIngestResult, String> {
+    let repo = RepoSource::parse(source, &app.state::<RepoSettings>().config().local_roots)?;
+    let name = repo.name();
+    let (mut files, branch) = collect_files(app, client, &repo, docs_path).await?;
+    if files.is_empty() {
+        return Err("No README or docs Markdown found".to_string());
+    }
+    sort_files(&mut files);
+
+    let (blob_base, raw_base) = match (&repo, &branch) {
+        (RepoSource::GitHub { owner, repo, .. }, Some(branch)) => (
+            Some(format!(
+                "https://github.com/{}/{}/blob/{}",
+                owner, repo, branch
+            )),
+            Some(format!(
+                "https://raw.githubusercontent.com/{}/{}/{}",
+                owner, repo, branch
+            )),
+        ),
+        _ => (None, None),
+    };
+    let source_of = |path: &str| match &blob_base {
+        Some(base) => format!("{}/{}", base, path),
+        None => source.trim().to_string(),
+    };
+
+    let folder = format!(
+        "Reference/Docs/{}",
+        name.replace(['/', '\\', ':', '*', '?', '"', '<', '>', '|'], "_")
+    );
+    let note_path = |path: &str| {
+        let stem = path.rsplit_once('.').map_or(path, |(stem, _)| stem);
+        format!("{}/{}", folder, stem)
+    };
+    let merged = mode == RepoMode::Merged;
+    let targets: HashMap<String, String> = files
+        .iter()
+        .map(|(path, _)| {
+            let target = if merged {
+                format!("#{}", path)
+            } else {
+                note_path(path)
+            };
+            (path.clone(), target)
+        })
+        .collect();
+    let heading_map: HashMap<String, Vec<String>> = files
+        .iter()
+        .map(|(path, content)| (path.clone(), headings(content)))
+        .collect();
+    let links = LinkContext {
+        targets: &targets,
+        headings: &heading_map,
+        blob_base: blob_base.clone(),
+        raw_base,
+        merged,
+    };
+
+    let graph = app.state::<RwLock<VaultGraph>>();
+    let repo_title = format!("{} (repository docs)", name);
+    if merged {
+        let sections: Vec<String> = files
+            .iter()
+            .map(|(path, content)| format!("## {}\n\n{}", path, links.convert(path, content, 2)))
+            .collect();
+        let file_path = crate::save_to_obsidian(
+            client,
+            &repo_title,
+            &sections.join("\n\n"),
+            source.trim(),
+            &name,
+            api_key,
+            existing_path,
+            &[],
+        )
+        .await?;
+        crate::index_saved_note(&graph, client, &file_path, api_key).await;
+        return Ok(RepoIngestResult {
+            notes: vec![file_path.clone()],
+            file_path,
+            files: files.len(),
+        });
+    }
+
+    let mut notes = Vec::new();
+    let mut index = Vec::new();
+    for (path, content) in &files {
+        let (title, body) = split_title(content);
+        let title = title.unwrap_or_else(|| path.clone());
+        let target = format!("{}.md", note_path(path));
+        let saved = crate::save_to_obsidian(
+            client,
+            &title,
+            &links.convert(path, body, 0),
+            &source_of(path),
+            path,
+            api_key,
+            Some(&target),
+            &[],
+        )
+        .await?;
+        crate::index_saved_note(&graph, client, &saved, api_key).await;
+        index.push(format!("- [[{}|{}]] — `{}`", note_path(path), title, path));
+        notes.push(saved);
+    }
+    let file_path = crate::save_to_obsidian(
+        client,
+        &repo_title,
+        &index.join("\n"),
+        source.trim(),
+        &name,
+        api_key,
+        existing_path,
+        &[],
+    )
+    .await?;
+    crate::index_saved_note(&graph, client, &file_path, api_key).await;
+    notes.insert(0, file_path.clone());
+
+    Ok(RepoIngestResult {
+        file_path,
+        notes,
+        files: files.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_resolve_against_the_linking_file() {
+        assert_eq!(
+            resolve_path("docs/guide/intro.md", "../api.md"),
+            "docs/api.md"
+        );
+        assert_eq!(
+            resolve_path("README.md", "./docs/setup.md"),
+            "docs/setup.md"
+        );
+        assert_eq!(
+            resolve_path("docs/a.md", "/CONTRIBUTING.md"),
+            "CONTRIBUTING.md"
+        );
+        assert_eq!(resolve_path("a.md", "../../b.md"), "b.md");
+    }
+
+    #[test]
+    fn local_folders_must_be_under_an_allowed_root() {
+        let root = std::env::temp_dir().join(format!("repo-{}", uuid::Uuid::new_v4()));
+        let inside = root.join("project");
+        std::fs::create_dir_all(&inside).unwrap();
+        let allowed = vec![root.canonicalize().unwrap()];
+
+        let source = inside.to_string_lossy();
+        assert!(matches!(
+            RepoSource::parse(&source, &allowed),
+            Ok(RepoSource::Local(path)) if path == inside.canonicalize().unwrap()
+        ));
+        assert!(RepoSource::parse(&source, &[]).is_err());
+        // `..` can't climb out of the root
+        let escape = inside.join("..").join("..");
+        assert!(RepoSource::parse(&escape.to_string_lossy(), &allowed).is_err());
+        assert!(matches!(
+            RepoSource::parse("https://github.com/a/b", &[]),
+            Ok(RepoSource::GitHub { .. })
+        ));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn slugs_follow_github_anchors() {
+        assert_eq!(github_slug("Getting Started!"), "getting-started");
+        assert_eq!(github_slug("  API v2.0 (beta) "), "api-v20-beta");
+        assert_eq!(github_slug("snake_case & co"), "snake_case--co");
+        assert_eq!(github_slug("Über-Setup"), "über-setup");
+    }
+}
@@ -0,0 +1,380 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::SearXNGResult;
+
+/// Most result pages a deep search merges
+pub const MAX_DEEP_PAGES: u32 = 10;
+
+/// Ready-made combinations of categories, engines and time range
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchPreset {
+    Docs,
+    News,
+    Code,
+    Papers,
+}
+
+impl SearchPreset {
+    fn categories(&self) -> &'static [&'static str] {
+        match self {
+            SearchPreset::Docs => &["general", "it"],
+            SearchPreset::News => &["news"],
+            SearchPreset::Code => &["it"],
+            SearchPreset::Papers => &["science"],
+        }
+    }
+
+    fn engines(&self) -> &'static [&'static str] {
+        match self {
+            SearchPreset::Docs | SearchPreset::News => &[],
+            SearchPreset::Code => &["github", "gitlab", "codeberg", "stackoverflow"],
+            SearchPreset::Papers => &[
+                "arxiv",
+                "google scholar",
+                "semantic scholar",
+                "crossref",
+                "pubmed",
+            ],
+        }
+    }
+
+    fn time_range(&self) -> Option<TimeRange> {
+        match self {
+            SearchPreset::News => Some(TimeRange::Month),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SafeSearch {
+    Off,
+    Moderate,
+    Strict,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeRange {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl TimeRange {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TimeRange::Day => "day",
+            TimeRange::Week => "week",
+            TimeRange::Month => "month",
+            TimeRange::Year => "year",
+        }
+    }
+}
+
+/// Everything a SearXNG query can be narrowed by besides the query text.
+/// Explicit categories, engines and time range win over the preset's.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchOptions {
+    #[serde(default)]
+    pub preset: Option<SearchPreset>,
+    #[serde(default)]
+    pub categories: Vec<String>,
+    #[serde(default)]
+    pub engines: Vec<String>,
+    /// e.g. `en`, `de-CH`; the instance default when unset
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub safesearch: Option<SafeSearch>,
+    #[serde(default)]
+    pub time_range: Option<TimeRange>,
+    /// 1-based result page
+    #[serde(default)]
+    pub pageno: Option<u32>,
+    /// Deep search: merge this many consecutive pages, starting at `pageno`
+    #[serde(default)]
+    pub deep: Option<u32>,
+    /// Providers to query and merge, e.g. `["searxng", "vault"]`; SearXNG
+    /// alone (with paging) when empty
+    #[serde(default)]
+    pub providers: Vec<String>,
+}
+
+impl SearchOptions {
+    /// Query string parameters for SearXNG's `/search` endpoint
+    pub fn query_params(&self, query: &str) -> Vec<(&'static str, String)> {
+        let mut params = vec![("q", query.to_string()), ("format", "json".to_string())];
+
+        let list = |explicit: &[String], preset: &[&str]| {
+            if explicit.is_empty() {
+                preset.join(",")
+            } else {
+                explicit.join(",")
+            }
+        };
+        let categories = list(
+            &self.categories,
+            self.preset.map_or(&[], |p| p.categories()),
+        );
+        let engines = list(&self.engines, self.preset.map_or(&[], |p| p.engines()));
+        if !categories.is_empty() {
+            params.push(("categories", categories));
+        }
+        if !engines.is_empty() {
+            params.push(("engines", engines));
+        }
+
+        if let Some(language) = self.language.as_ref().filter(|l| !l.is_empty()) {
+            params.push(("language", language.clone()));
+        }
+        if let Some(safesearch) = self.safesearch {
+            let level = match safesearch {
+                SafeSearch::Off => "0",
+                SafeSearch::Moderate => "1",
+                SafeSearch::Strict => "2",
+            };
+            params.push(("safesearch", level.to_string()));
+        }
+        if let Some(range) = self
+            .time_range
+            .or_else(|| self.preset.and_then(|p| p.time_range()))
+        {
+            params.push(("time_range", range.as_str().to_string()));
+        }
+        params.push(("pageno", self.pageno.unwrap_or(1).max(1).to_string()));
+        params
+    }
+}
+
+/// Cursor pointing at a result page; opaque to callers
+pub fn cursor(page: u32) -> String {
+    format!("page:{}", page)
+}
+
+pub fn parse_cursor(cursor: &str) -> Result<u32, String> {
+    cursor
+        .strip_prefix("page:")
+        .and_then(|p| p.parse::<u32>().ok())
+        .filter(|p| *p >= 1)
+        .ok_or_else(|| format!("Invalid search cursor: {}", cursor))
+}
+
+/// Fetches `pages` consecutive result pages from `start` and merges them.
+/// A URL seen on an earlier page is dropped, but its engines are added to
+/// the first copy and the best score is kept. When `start` follows an
+/// earlier call, URLs of the page before it count as seen too, so "load
+/// more" still notices when SearXNG only repeats itself. Returns the cursor
+/// of the following page, or `None` once SearXNG runs out of new results.
+pub async fn search_pages(
+    client: &reqwest::Client,
+    base_url: &str,
+    query: &str,
+    options: &SearchOptions,
+    start: u32,
+    pages: u32,
+) -> Result<(Vec<SearXNGResult>, Option<String>), String> {
+    let mut merged: Vec<SearXNGResult> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut next = None;
+
+    // Returned by the previous call; only used to tell new results apart
+    let mut previous: HashSet<String> = HashSet::new();
+    if start > 1 {
+        let page_options = SearchOptions {
+            pageno: Some(start - 1),
+            ..options.clone()
+        };
+        if let Ok(results) =
+            crate::call_searxng_search(client, base_url, query, &page_options).await
+        {
+            previous.extend(results.iter().map(|r| crate::urls::normalize_url(&r.url)));
+        }
+    }
+
+    for page in start..start + pages.max(1) {
+        let page_options = SearchOptions {
+            pageno: Some(page),
+            ..options.clone()
+        };
+        let results = match crate::call_searxng_search(client, base_url, query, &page_options).await
+        {
+            Ok(results) => results,
+            // Later pages are a bonus; keep what we already have
+            Err(_) if page > start => break,
+            Err(e) => return Err(e),
+        };
+
+        let mut added = 0;
+        for result in results {
+            let key = crate::urls::normalize_url(&result.url);
+            if previous.contains(&key) {
+                continue;
+            }
+            match positions.get(&key) {
+                Some(&i) => {
+                    let first = &mut merged[i];
+                    for engine in result.engines {
+                        if !first.engines.contains(&engine) {
+                            first.engines.push(engine);
+                        }
+                    }
+                    first.score = match (first.score, result.score) {
+                        (Some(a), Some(b)) => Some(a.max(b)),
+                        (a, b) => a.or(b),
+                    };
+                }
+                None => {
+                    positions.insert(key, merged.len());
+                    merged.push(result);
+                    added += 1;
+                }
+            }
+        }
+        // Past the last page SearXNG returns nothing or repeats itself
+        if added == 0 {
+            next = None;
+            break;
+        }
+        next = Some(cursor(page + 1));
+    }
+    Ok((merged, next))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// A SearXNG stand-in with two pages of results that repeats the last
+    /// page for any later one, as SearXNG does
+    async fn searxng() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut head = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    if n == 0 {
+                        break;
+                    }
+                    head.extend_from_slice(&buf[..n]);
+                }
+                let urls: &[&str] = if String::from_utf8_lossy(&head).contains("pageno=1 ") {
+                    &["https://a.example/", "https://b.example/"]
+                } else {
+                    &["https://c.example/"]
+                };
+                let results: Vec<_> = urls
+                    .iter()
+                    .map(|url| serde_json::json!({ "url": url, "title": url }))
+                    .collect();
+                let body = serde_json::json!({ "results": results }).to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        base
+    }
+
+    #[tokio::test]
+    async fn load_more_ends_when_pages_repeat() {
+        let base = searxng().await;
+        let client = reqwest::Client::new();
+        let options = SearchOptions::default();
+        let urls = |results: Vec<crate::SearXNGResult>| {
+            results.into_iter().map(|r| r.url).collect::<Vec<_>>()
+        };
+
+        let (results, next) = search_pages(&client, &base, "q", &options, 1, 1)
+            .await
+            .unwrap();
+        assert_eq!(urls(results), ["https://a.example/", "https://b.example/"]);
+        assert_eq!(next.as_deref(), Some("page:2"));
+
+        let (results, next) = search_pages(&client, &base, "q", &options, 2, 1)
+            .await
+            .unwrap();
+        assert_eq!(urls(results), ["https://c.example/"]);
+        assert_eq!(next.as_deref(), Some("page:3"));
+
+        let (results, next) = search_pages(&client, &base, "q", &options, 3, 1)
+            .await
+            .unwrap();
+        assert!(results.is_empty());
+        assert_eq!(next, None);
+    }
+
+    #[test]
+    fn defaults_ask_for_the_first_json_page() {
+        assert_eq!(
+            SearchOptions::default().query_params("rust"),
+            [
+                ("q", "rust".to_string()),
+                ("format", "json".to_string()),
+                ("pageno", "1".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn presets_fill_in_what_is_not_explicit() {
+        let options = SearchOptions {
+            preset: Some(SearchPreset::News),
+            engines: vec!["bing news".to_string(), "reuters".to_string()],
+            ..Default::default()
+        };
+        let params = options.query_params("rust");
+        assert!(params.contains(&("categories", "news".to_string())));
+        assert!(params.contains(&("engines", "bing news,reuters".to_string())));
+        assert!(params.contains(&("time_range", "month".to_string())));
+
+        let options = SearchOptions {
+            preset: Some(SearchPreset::News),
+            categories: vec!["general".to_string()],
+            time_range: Some(TimeRange::Day),
+            ..Default::default()
+        };
+        let params = options.query_params("rust");
+        assert!(params.contains(&("categories", "general".to_string())));
+        assert!(params.contains(&("time_range", "day".to_string())));
+        assert!(!params.iter().any(|(k, _)| *k == "engines"));
+    }
+
+    #[test]
+    fn language_safesearch_and_page_are_passed() {
+        let options = SearchOptions {
+            language: Some("de-CH".to_string()),
+            safesearch: Some(SafeSearch::Strict),
+            pageno: Some(0),
+            ..Default::default()
+        };
+        let params = options.query_params("rust");
+        assert!(params.contains(&("language", "de-CH".to_string())));
+        assert!(params.contains(&("safesearch", "2".to_string())));
+        // Pages are 1-based
+        assert!(params.contains(&("pageno", "1".to_string())));
+
+        let empty_language = SearchOptions {
+            language: Some(String::new()),
+            ..Default::default()
+        };
+        assert!(!empty_language
+            .query_params("rust")
+            .iter()
+            .any(|(k, _)| *k == "language"));
+    }
+}
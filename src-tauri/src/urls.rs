@@ -0,0 +1,105 @@
+/// Query parameters that only track where a click came from. Plain `ref`
+/// is not one: GitHub and others use it to pick a branch or version.
+const TRACKING_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "dclid", "msclkid", "mc_cid", "mc_eid", "ref_src", "_hsenc", "_hsmi",
+    "igshid", "yclid",
+];
+
+/// Canonical form of a URL used as an identity key for dedupe and caching.
+/// Scheme (http/https), `www.`, fragments, tracking parameters, default
+/// ports and trailing slashes are ignored; remaining query parameters are
+/// sorted. Unparseable input is returned trimmed as-is.
+pub fn normalize_url(raw: &str) -> String {
+    let raw = raw.trim();
+    let Ok(url) = reqwest::Url::parse(raw) else {
+        return raw.to_string();
+    };
+
+    let host = url
+        .host_str()
+        .unwrap_or_default()
+        .to_lowercase()
+        .trim_start_matches("www.")
+        .to_string();
+    let port = match url.port() {
+        Some(p) if p != 80 && p != 443 => format!(":{}", p),
+        _ => String::new(),
+    };
+
+    let path = url.path().trim_end_matches('/');
+
+    let mut params: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(k, _)| {
+            let k = k.to_lowercase();
+            !k.starts_with("utm_") && !TRACKING_PARAMS.contains(&k.as_str())
+        })
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect();
+    params.sort();
+    let query = if params.is_empty() {
+        String::new()
+    } else {
+        let joined = params
+            .iter()
+            .map(|(k, v)| {
+                if v.is_empty() {
+                    urlencoding::encode(k).into_owned()
+                } else {
+                    format!("{}={}", urlencoding::encode(k), urlencoding::encode(v))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+        format!("?{}", joined)
+    };
+
+    format!("{}{}{}{}", host, port, path, query)
+}
+
+/// True when `host` is `domain` or one of its subdomains (`www.` ignored)
+pub fn domain_matches(host: &str, domain: &str) -> bool {
+    let host = host.trim_start_matches("www.").to_lowercase();
+    let domain = domain.trim().trim_start_matches("www.").to_lowercase();
+    !domain.is_empty() && (host == domain || host.ends_with(&format!(".{}", domain)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variants_of_a_page_normalize_alike() {
+        let key = normalize_url("https://example.com/docs?b=2&a=1");
+        for variant in [
+            "http://www.Example.com/docs/?a=1&b=2",
+            "https://example.com:443/docs?b=2&a=1#intro",
+            "https://example.com/docs?utm_source=x&a=1&fbclid=y&b=2",
+            "  https://example.com/docs?a=1&b=2  ",
+        ] {
+            assert_eq!(normalize_url(variant), key, "{}", variant);
+        }
+        assert_eq!(key, "example.com/docs?a=1&b=2");
+    }
+
+    #[test]
+    fn meaningful_differences_are_kept() {
+        assert_ne!(
+            normalize_url("https://example.com/docs"),
+            normalize_url("https://example.com:8443/docs")
+        );
+        assert_ne!(
+            normalize_url("https://github.com/o/r?ref=main"),
+            normalize_url("https://github.com/o/r?ref=dev")
+        );
+        assert_eq!(normalize_url("not a url "), "not a url");
+    }
+
+    #[test]
+    fn domains_match_their_subdomains() {
+        assert!(domain_matches("www.docs.example.com", "example.com"));
+        assert!(domain_matches("example.com", "www.example.com"));
+        assert!(!domain_matches("notexample.com", "example.com"));
+        assert!(!domain_matches("example.com", ""));
+    }
+}
@@ -0,0 +1,835 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// A frontmatter value: either a scalar or a YAML list
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum FrontmatterValue {
+    Text(String),
+    List(Vec<String>),
+}
+
+impl FrontmatterValue {
+    pub fn values(&self) -> Vec<&str> {
+        match self {
+            FrontmatterValue::Text(s) => vec![s.as_str()],
+            FrontmatterValue::List(items) => items.iter().map(|s| s.as_str()).collect(),
+        }
+    }
+}
+
+/// Everything we extract from a single note
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct NoteMeta {
+    pub path: String,
+    pub title: String,
+    /// Raw link targets (`[[Target#Heading|Alias]]` -> `Target`)
+    pub links: Vec<String>,
+    /// Lowercase tags without the leading `#`
+    pub tags: Vec<String>,
+    pub aliases: Vec<String>,
+    pub frontmatter: BTreeMap<String, FrontmatterValue>,
+    /// Last modification time in milliseconds since the epoch, when known
+    #[serde(default)]
+    pub modified: Option<i64>,
+}
+
+/// A note together with its resolved neighbourhood
+#[derive(Serialize, Clone)]
+pub struct NoteLinks {
+    pub note: NoteMeta,
+    pub outgoing: Vec<String>,
+    pub backlinks: Vec<String>,
+}
+
+/// Parses an Obsidian note into its metadata
+pub fn parse_note(path: &str, content: &str) -> NoteMeta {
+    let (frontmatter, body) = split_frontmatter(content);
+
+    let mut tags: BTreeSet<String> = BTreeSet::new();
+    let mut aliases = Vec::new();
+    if let Some(value) = frontmatter.get("tags").or(frontmatter.get("tag")) {
+        for v in value.values() {
+            for t in v.split([',', ' ']) {
+                let t = t.trim().trim_start_matches('#');
+                if !t.is_empty() {
+                    tags.insert(t.to_lowercase());
+                }
+            }
+        }
+    }
+    if let Some(value) = frontmatter.get("aliases").or(frontmatter.get("alias")) {
+        aliases = value.values().iter().map(|s| s.to_string()).collect();
+    }
+
+    let mut links = Vec::new();
+    let mut in_code_block = false;
+    for line in body.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+        let line = strip_inline_code(line);
+        extract_wikilinks(&line, &mut links);
+        extract_markdown_links(&line, &mut links);
+        extract_tags(&line, &mut tags);
+    }
+
+    let mut seen = BTreeSet::new();
+    links.retain(|l| seen.insert(l.clone()));
+
+    NoteMeta {
+        path: path.to_string(),
+        title: note_name(path),
+        links,
+        tags: tags.into_iter().collect(),
+        aliases,
+        frontmatter,
+        modified: None,
+    }
+}
+
+/// `Reference/Docs/Tokio.md` -> `Tokio`
+pub fn note_name(path: &str) -> String {
+    let file = path.rsplit('/').next().unwrap_or(path);
+    file.strip_suffix(".md").unwrap_or(file).to_string()
+}
+
+/// The YAML of a leading `---` block and the text after its closing `---`.
+/// The block may be empty, with the closing line right after the opening one.
+fn frontmatter_block(content: &str) -> Option<(&str, &str)> {
+    let trimmed = content.trim_start_matches('\u{feff}');
+    let rest = trimmed
+        .strip_prefix("---\n")
+        .or(trimmed.strip_prefix("---\r\n"))?;
+    if let Some(after) = rest.strip_prefix("---") {
+        return Some(("", after));
+    }
+    let end = rest.find("\n---")?;
+    Some((&rest[..end], &rest[end + 4..]))
+}
+
+/// Splits a leading `---` YAML block from the note body. Only the flat
+/// `key: value`, `key: [a, b]` and `key:\n  - item` forms are understood,
+/// which covers what Obsidian and TheFetcher write.
+pub fn split_frontmatter(content: &str) -> (BTreeMap<String, FrontmatterValue>, &str) {
+    let mut map = BTreeMap::new();
+    let Some((yaml, after)) = frontmatter_block(content) else {
+        return (map, content);
+    };
+    let body = after.trim_start_matches(['\r', '\n']);
+
+    let mut current_list: Option<String> = None;
+    for line in yaml.lines() {
+        let trimmed_line = line.trim();
+        if trimmed_line.is_empty() || trimmed_line.starts_with('#') {
+            continue;
+        }
+        if let Some(item) = trimmed_line.strip_prefix("- ") {
+            if let Some(key) = &current_list {
+                if let Some(FrontmatterValue::List(items)) = map.get_mut(key) {
+                    items.push(unquote(item));
+                }
+            }
+            continue;
+        }
+        current_list = None;
+        let Some((key, value)) = trimmed_line.split_once(':') else {
+            continue;
+        };
+        let key = key.trim().to_string();
+        let value = value.trim();
+        if value.is_empty() {
+            map.insert(key.clone(), FrontmatterValue::List(Vec::new()));
+            current_list = Some(key);
+        } else if value.starts_with('[') && value.ends_with(']') {
+            let items = value[1..value.len() - 1]
+                .split(',')
+                .map(unquote)
+                .filter(|s| !s.is_empty())
+                .collect();
+            map.insert(key, FrontmatterValue::List(items));
+        } else {
+            map.insert(key, FrontmatterValue::Text(unquote(value)));
+        }
+    }
+
+    (map, body)
+}
+
+/// Sets (or adds) scalar frontmatter fields, leaving everything else in the
+/// note untouched. A frontmatter block is created if the note has none.
+pub fn set_frontmatter_fields(content: &str, fields: &[(&str, String)]) -> String {
+    let (yaml, body) = match frontmatter_block(content) {
+        Some((yaml, after)) => (yaml, after.to_string()),
+        None => ("", format!("\n{}", content)),
+    };
+
+    let mut lines: Vec<String> = yaml.lines().map(|l| l.to_string()).collect();
+    for (key, value) in fields {
+        let prefix = format!("{}:", key);
+        let line = format!("{}: {}", key, value);
+        match lines.iter_mut().find(|l| l.starts_with(&prefix)) {
+            Some(existing) => *existing = line,
+            None => lines.push(line),
+        }
+    }
+
+    format!("---\n{}\n---{}", lines.join("\n"), body)
+}
+
+fn unquote(s: &str) -> String {
+    s.trim().trim_matches(|c| c == '"' || c == '\'').to_string()
+}
+
+fn strip_inline_code(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut in_code = false;
+    for c in line.chars() {
+        if c == '`' {
+            in_code = !in_code;
+        } else if !in_code {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn extract_wikilinks(line: &str, links: &mut Vec<String>) {
+    let mut rest = line;
+    while let Some(start) = rest.find("[[") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("]]") else {
+            break;
+        };
+        let inner = &after[..end];
+        let target = inner.split(['|', '#', '^']).next().unwrap_or("").trim();
+        if !target.is_empty() {
+            links.push(target.to_string());
+        }
+        rest = &after[end + 2..];
+    }
+}
+
+fn extract_markdown_links(line: &str, links: &mut Vec<String>) {
+    let mut rest = line;
+    while let Some(start) = rest.find("](") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find(')') else {
+            break;
+        };
+        let target = after[..end].split('#').next().unwrap_or("").trim();
+        if target.ends_with(".md") && !target.contains("://") {
+            let decoded = urlencoding::decode(target)
+                .map(|s| s.into_owned())
+                .unwrap_or_else(|_| target.to_string());
+            links.push(decoded.trim_end_matches(".md").to_string());
+        }
+        rest = &after[end + 1..];
+    }
+}
+
+fn extract_tags(line: &str, tags: &mut BTreeSet<String>) {
+    // Headings ("# Title") never count as tags
+    if line.trim_start().starts_with("# ") {
+        return;
+    }
+    let chars: Vec<char> = line.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let preceded_ok = i == 0 || chars[i - 1].is_whitespace();
+        if chars[i] == '#' && preceded_ok {
+            let tag: String = chars[i + 1..]
+                .iter()
+                .take_while(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '/'))
+                .collect();
+            // "#123" is an issue number, not a tag
+            if tag.chars().any(|c| !c.is_ascii_digit()) {
+                tags.insert(tag.to_lowercase());
+            }
+            i += tag.chars().count() + 1;
+        } else {
+            i += 1;
+        }
+    }
+}
+
+// ----------------------
+// Link Graph
+// ----------------------
+
+/// In-memory graph of the vault: notes by path, link resolution by
+/// name/alias, and the reverse (backlink) index.
+#[derive(Default)]
+pub struct VaultGraph {
+    notes: HashMap<String, NoteMeta>,
+    by_name: HashMap<String, String>,
+    backlinks: HashMap<String, BTreeSet<String>>,
+    /// Normalized `source:` URL -> note path
+    by_source: HashMap<String, String>,
+    /// When the vault was last scanned; `None` until the first build
+    built_at: Option<Instant>,
+}
+
+impl VaultGraph {
+    pub fn from_notes(notes: Vec<NoteMeta>) -> Self {
+        let mut graph = VaultGraph {
+            built_at: Some(Instant::now()),
+            ..Default::default()
+        };
+        for note in notes {
+            graph.notes.insert(note.path.clone(), note);
+        }
+        graph.reindex();
+        graph
+    }
+
+    pub fn is_built(&self) -> bool {
+        self.built_at.is_some()
+    }
+
+    /// Built less than `max_age` ago
+    pub fn is_fresh(&self, max_age: Duration) -> bool {
+        self.built_at.is_some_and(|at| at.elapsed() < max_age)
+    }
+
+    /// Adds or replaces a note (e.g. right after we saved it)
+    pub fn upsert(&mut self, note: NoteMeta) {
+        self.notes.insert(note.path.clone(), note);
+        self.reindex();
+    }
+
+    /// Path of the note already saved from this URL, if any
+    pub fn find_by_source(&self, url: &str) -> Option<String> {
+        self.by_source
+            .get(&crate::urls::normalize_url(url))
+            .cloned()
+    }
+
+    pub fn get(&self, path: &str) -> Option<&NoteMeta> {
+        self.notes.get(path)
+    }
+
+    pub fn notes(&self) -> impl Iterator<Item = &NoteMeta> {
+        self.notes.values()
+    }
+
+    /// Notes matching a structured filter, most recently modified first
+    pub fn filter_notes(&self, filter: &VaultFilter) -> Vec<&NoteMeta> {
+        let mut notes: Vec<&NoteMeta> = self
+            .notes
+            .values()
+            .filter(|n| filter.matches(&n.path, self))
+            .collect();
+        notes.sort_by(|a, b| b.modified.cmp(&a.modified).then(a.path.cmp(&b.path)));
+        notes
+    }
+
+    pub fn len(&self) -> usize {
+        self.notes.len()
+    }
+
+    /// Resolves a link target the way Obsidian does: exact path first, then
+    /// note name, then alias (case-insensitive).
+    pub fn resolve(&self, target: &str) -> Option<String> {
+        let with_ext = if target.ends_with(".md") {
+            target.to_string()
+        } else {
+            format!("{}.md", target)
+        };
+        if self.notes.contains_key(&with_ext) {
+            return Some(with_ext);
+        }
+        self.by_name.get(&note_name(target).to_lowercase()).cloned()
+    }
+
+    pub fn outgoing(&self, path: &str) -> Vec<String> {
+        let Some(note) = self.notes.get(path) else {
+            return Vec::new();
+        };
+        let mut seen = BTreeSet::new();
+        note.links
+            .iter()
+            .filter_map(|l| self.resolve(l))
+            .filter(|p| p != path && seen.insert(p.clone()))
+            .collect()
+    }
+
+    pub fn backlinks(&self, path: &str) -> Vec<String> {
+        self.backlinks
+            .get(path)
+            .map(|s| s.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Outgoing links followed by backlinks, without duplicates
+    pub fn neighbours(&self, path: &str) -> Vec<String> {
+        let mut seen = BTreeSet::new();
+        self.outgoing(path)
+            .into_iter()
+            .chain(self.backlinks(path))
+            .filter(|p| seen.insert(p.clone()))
+            .collect()
+    }
+
+    pub fn links_for(&self, path: &str) -> Option<NoteLinks> {
+        self.notes.get(path).map(|note| NoteLinks {
+            note: note.clone(),
+            outgoing: self.outgoing(path),
+            backlinks: self.backlinks(path),
+        })
+    }
+
+    fn reindex(&mut self) {
+        self.by_name.clear();
+        for note in self.notes.values() {
+            for alias in &note.aliases {
+                self.by_name.insert(alias.to_lowercase(), note.path.clone());
+            }
+        }
+        // Names win over aliases when both match
+        for note in self.notes.values() {
+            self.by_name
+                .insert(note.title.to_lowercase(), note.path.clone());
+        }
+
+        self.by_source.clear();
+        for note in self.notes.values() {
+            if let Some(FrontmatterValue::Text(source)) = note.frontmatter.get("source") {
+                self.by_source
+                    .insert(crate::urls::normalize_url(source), note.path.clone());
+            }
+        }
+
+        self.backlinks.clear();
+        for note in self.notes.values() {
+            for link in &note.links {
+                if let Some(target) = self.resolve(link) {
+                    if target != note.path {
+                        self.backlinks
+                            .entry(target)
+                            .or_default()
+                            .insert(note.path.clone());
+                    }
+                }
+            }
+        }
+    }
+}
+
+// ----------------------
+// Filters
+// ----------------------
+
+/// Comparison applied to a frontmatter field
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldOp {
+    Eq,
+    Contains,
+    Gte,
+    Lte,
+    Exists,
+}
+
+/// `fetched_date gte 2024-01-01`, `tags contains reference`, ...
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct FieldFilter {
+    pub field: String,
+    pub op: FieldOp,
+    #[serde(default)]
+    pub value: String,
+}
+
+impl FieldFilter {
+    fn matches(&self, note: &NoteMeta) -> bool {
+        let Some(actual) = note.frontmatter.get(&self.field) else {
+            return false;
+        };
+        let wanted = self.value.to_lowercase();
+        actual.values().iter().any(|v| {
+            let v = v.to_lowercase();
+            match self.op {
+                FieldOp::Exists => true,
+                FieldOp::Eq => v == wanted,
+                FieldOp::Contains => match actual {
+                    FrontmatterValue::List(_) => v == wanted,
+                    FrontmatterValue::Text(_) => v.contains(&wanted),
+                },
+                FieldOp::Gte => compare_values(&v, &wanted).is_ge(),
+                FieldOp::Lte => compare_values(&v, &wanted).is_le(),
+            }
+        })
+    }
+}
+
+/// Numbers compare numerically; everything else (including ISO dates)
+/// compares lexically.
+fn compare_values(a: &str, b: &str) -> std::cmp::Ordering {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(x), Ok(y)) => x.partial_cmp(&y).unwrap_or(std::cmp::Ordering::Equal),
+        _ => a.cmp(b),
+    }
+}
+
+/// `2024-05-01` or RFC 3339 -> milliseconds since the epoch
+fn parse_date_ms(value: &str) -> Option<i64> {
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Some(dt.timestamp_millis());
+    }
+    chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|dt| dt.and_utc().timestamp_millis())
+}
+
+/// Structured restrictions on vault results: folder prefix, tags,
+/// frontmatter fields and modification date range.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultFilter {
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub folder: Option<String>,
+    #[serde(default)]
+    pub fields: Vec<FieldFilter>,
+    /// Inclusive, `YYYY-MM-DD` or RFC 3339
+    #[serde(default)]
+    pub modified_after: Option<String>,
+    /// Inclusive, `YYYY-MM-DD` or RFC 3339
+    #[serde(default)]
+    pub modified_before: Option<String>,
+}
+
+impl VaultFilter {
+    /// Pulls `#tag` and `folder:Some/Path` tokens out of a free-text query so
+    /// the agent can filter without a separate argument syntax.
+    pub fn parse_inline(query: &str) -> (String, VaultFilter) {
+        let mut filter = VaultFilter::default();
+        let mut words = Vec::new();
+        for word in query.split_whitespace() {
+            if let Some(tag) = word.strip_prefix('#').filter(|t| !t.is_empty()) {
+                filter.tags.push(tag.to_lowercase());
+            } else if let Some(folder) = word.strip_prefix("folder:").filter(|f| !f.is_empty()) {
+                filter.folder = Some(folder.to_string());
+            } else {
+                words.push(word);
+            }
+        }
+        (words.join(" "), filter)
+    }
+
+    /// Merges another filter into this one; explicit values win over inline ones
+    pub fn extend(&mut self, other: VaultFilter) {
+        self.tags.extend(other.tags);
+        self.fields.extend(other.fields);
+        if self.folder.is_none() {
+            self.folder = other.folder;
+        }
+        if self.modified_after.is_none() {
+            self.modified_after = other.modified_after;
+        }
+        if self.modified_before.is_none() {
+            self.modified_before = other.modified_before;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.folder.is_none() && !self.needs_metadata()
+    }
+
+    /// Whether the filter can only be evaluated with the note's metadata
+    fn needs_metadata(&self) -> bool {
+        !self.tags.is_empty()
+            || !self.fields.is_empty()
+            || self.modified_after.is_some()
+            || self.modified_before.is_some()
+    }
+
+    /// Checks a note path against the filter. Notes missing from the graph
+    /// can only satisfy folder constraints.
+    pub fn matches(&self, path: &str, graph: &VaultGraph) -> bool {
+        if let Some(folder) = &self.folder {
+            let prefix = folder.trim_matches('/');
+            if !path.starts_with(&format!("{}/", prefix)) {
+                return false;
+            }
+        }
+        if !self.needs_metadata() {
+            return true;
+        }
+        let Some(note) = graph.get(path) else {
+            return false;
+        };
+
+        let tags_ok = self.tags.iter().all(|wanted| {
+            let wanted = wanted.trim_start_matches('#').to_lowercase();
+            // Nested tags: "#lang" matches "lang/rust"
+            note.tags
+                .iter()
+                .any(|t| *t == wanted || t.starts_with(&format!("{}/", wanted)))
+        });
+        if !tags_ok || !self.fields.iter().all(|f| f.matches(note)) {
+            return false;
+        }
+
+        if let Some(after) = self.modified_after.as_deref().and_then(parse_date_ms) {
+            if note.modified.is_none_or(|m| m < after) {
+                return false;
+            }
+        }
+        if let Some(before) = self.modified_before.as_deref().and_then(parse_date_ms) {
+            // A bare date covers the whole day
+            let end = if self
+                .modified_before
+                .as_deref()
+                .is_some_and(|d| d.len() == 10)
+            {
+                before + 86_400_000
+            } else {
+                before + 1
+            };
+            if note.modified.is_none_or(|m| m >= end) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+// ----------------------
+// Building from Obsidian
+// ----------------------
+
+/// Walks the vault through the Local REST API and parses every Markdown note
+pub async fn build_graph(client: &reqwest::Client, api_key: &str) -> Result<VaultGraph, String> {
+    let mut pending = vec![String::new()];
+    let mut files = Vec::new();
+
+    while let Some(dir) = pending.pop() {
+        for entry in list_vault_dir(client, &dir, api_key).await? {
+            let full = format!("{}{}", dir, entry);
+            if full.ends_with('/') {
+                pending.push(full);
+            } else if full.ends_with(".md") {
+                files.push(full);
+            }
+        }
+    }
+
+    let mut notes = Vec::with_capacity(files.len());
+    for path in files {
+        // Skip unreadable notes rather than failing the whole build
+        if let Ok(note) = fetch_note_json(client, &path, api_key).await {
+            let mut meta = parse_note(&path, &note.content);
+            meta.modified = note.stat.map(|s| s.mtime);
+            notes.push(meta);
+        }
+    }
+
+    Ok(VaultGraph::from_notes(notes))
+}
+
+#[derive(Deserialize)]
+struct NoteStat {
+    mtime: i64,
+}
+
+#[derive(Deserialize)]
+struct NoteJson {
+    content: String,
+    #[serde(default)]
+    stat: Option<NoteStat>,
+}
+
+/// Reads a note in the REST API's JSON form, which includes file stats
+async fn fetch_note_json(
+    client: &reqwest::Client,
+    path: &str,
+    api_key: &str,
+) -> Result<NoteJson, String> {
+    let url = format!(
+        "https://127.0.0.1:27124/vault/{}",
+        urlencoding::encode(path)
+    );
+    let res = client
+        .get(&url)
+        .header("Authorization", format!("Bearer {}", api_key))
+        .header("Accept", "application/vnd.olrapi.note+json")
+        .send()
+        .await
+        .map_err(|e| format!("Vault fetch failed: {}", e))?;
+
+    if !res.status().is_success() {
+        return Err(format!("Vault error: {}", res.status()));
+    }
+
+    res.json()
+        .await
+        .map_err(|e| format!("Failed to parse note JSON: {}", e))
+}
+
+/// Whether a note exists at `path`, for when the graph is unavailable
+pub async fn note_exists(
+    client: &reqwest::Client,
+    path: &str,
+    api_key: &str,
+) -> Result<bool, String> {
+    let url = format!(
+        "https://127.0.0.1:27124/vault/{}",
+        urlencoding::encode(path)
+    );
+    let res = client
+        .get(&url)
+        .header("Authorization", format!("Bearer {}", api_key))
+        .send()
+        .await
+        .map_err(|e| format!("Vault fetch failed: {}", e))?;
+
+    match res.status() {
+        reqwest::StatusCode::NOT_FOUND => Ok(false),
+        status if status.is_success() => Ok(true),
+        status => Err(format!("Vault error: {}", status)),
+    }
+}
+
+#[derive(Deserialize)]
+struct VaultListing {
+    #[serde(default)]
+    files: Vec<String>,
+}
+
+async fn list_vault_dir(
+    client: &reqwest::Client,
+    dir: &str,
+    api_key: &str,
+) -> Result<Vec<String>, String> {
+    let encoded_dir = dir
+        .split('/')
+        .map(|seg| urlencoding::encode(seg).into_owned())
+        .collect::<Vec<_>>()
+        .join("/");
+    let url = format!("https://127.0.0.1:27124/vault/{}", encoded_dir);
+
+    let res = client
+        .get(&url)
+        .header("Authorization", format!("Bearer {}", api_key))
+        .send()
+        .await
+        .map_err(|e| format!("Vault listing failed: {}", e))?;
+
+    if !res.status().is_success() {
+        return Err(format!("Vault listing error: {}", res.status()));
+    }
+
+    let listing: VaultListing = res
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse vault listing: {}", e))?;
+    Ok(listing.files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(value: &str) -> FrontmatterValue {
+        FrontmatterValue::Text(value.to_string())
+    }
+
+    #[test]
+    fn frontmatter_forms_are_parsed() {
+        let note = "---\nsource: \"https://a.example/\"\ntags: [api, 'docs']\naliases:\n  - One\n  - Two\n---\n\n# Title\n";
+        let (fm, body) = split_frontmatter(note);
+        assert_eq!(fm["source"], text("https://a.example/"));
+        assert_eq!(
+            fm["tags"],
+            FrontmatterValue::List(vec!["api".to_string(), "docs".to_string()])
+        );
+        assert_eq!(
+            fm["aliases"],
+            FrontmatterValue::List(vec!["One".to_string(), "Two".to_string()])
+        );
+        assert_eq!(body, "# Title\n");
+    }
+
+    #[test]
+    fn empty_and_crlf_frontmatter_blocks_are_found() {
+        let (fm, body) = split_frontmatter("---\n---\nBody");
+        assert!(fm.is_empty());
+        assert_eq!(body, "Body");
+
+        let (fm, body) = split_frontmatter("\u{feff}---\r\nkey: v\r\n---\r\nBody");
+        assert_eq!(fm["key"], text("v"));
+        assert_eq!(body, "Body");
+
+        let (fm, body) = split_frontmatter("No frontmatter\n---\n");
+        assert!(fm.is_empty());
+        assert_eq!(body, "No frontmatter\n---\n");
+    }
+
+    #[test]
+    fn set_frontmatter_fields_updates_or_adds_a_block() {
+        let fields = [("content_hash", "abc".to_string())];
+        assert_eq!(
+            set_frontmatter_fields("---\n---\nBody", &fields),
+            "---\ncontent_hash: abc\n---\nBody"
+        );
+        assert_eq!(
+            set_frontmatter_fields("---\ncontent_hash: old\nx: 1\n---\nBody", &fields),
+            "---\ncontent_hash: abc\nx: 1\n---\nBody"
+        );
+        assert_eq!(
+            set_frontmatter_fields("Body", &fields),
+            "---\ncontent_hash: abc\n---\nBody"
+        );
+    }
+
+    #[test]
+    fn inline_filters_merge_with_explicit_ones() {
+        let (text, inline) =
+            VaultFilter::parse_inline("tokio #Async folder:Reference/Docs runtime");
+        assert_eq!(text, "tokio runtime");
+        let mut filter = VaultFilter {
+            folder: Some("Projects".to_string()),
+            ..Default::default()
+        };
+        filter.extend(inline);
+        assert_eq!(filter.tags, vec!["async"]);
+        assert_eq!(filter.folder.as_deref(), Some("Projects"));
+
+        let graph = VaultGraph::from_notes(vec![
+            parse_note("Projects/A.md", "---\ntags: [async]\n---\n"),
+            parse_note("Projects/B.md", "---\ntags: [sync]\n---\n"),
+            parse_note("Reference/C.md", "---\ntags: [async]\n---\n"),
+        ]);
+        assert!(filter.matches("Projects/A.md", &graph));
+        assert!(!filter.matches("Projects/B.md", &graph));
+        assert!(!filter.matches("Reference/C.md", &graph));
+    }
+
+    #[test]
+    fn graphs_go_stale() {
+        let empty = VaultGraph::default();
+        assert!(!empty.is_built());
+        assert!(!empty.is_fresh(Duration::from_secs(60)));
+
+        let built = VaultGraph::from_notes(vec![parse_note(
+            "Reference/A.md",
+            "---\nsource: https://a.example/\n---\n# A",
+        )]);
+        assert!(built.is_built());
+        assert!(built.is_fresh(Duration::from_secs(60)));
+        assert!(!built.is_fresh(Duration::ZERO));
+        assert_eq!(
+            built.find_by_source("https://a.example").as_deref(),
+            Some("Reference/A.md")
+        );
+    }
+}
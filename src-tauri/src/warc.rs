@@ -0,0 +1,574 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::fetch::RawResponse;
+
+/// Snapshot IDs are `<UTC capture time>-<content digest>`
+const ID_TIME_FORMAT: &str = "%Y%m%d%H%M%S";
+const ID_DIGEST_LEN: usize = 16;
+
+/// Total size of stored snapshots; the oldest are removed beyond it
+const MAX_STORED_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+/// Content type of the rendered DOM in `conversion` records
+const RENDERED_TYPE: &str = "text/html; charset=utf-8";
+
+type Headers = Vec<(String, String)>;
+
+/// A stored copy of one HTTP response
+#[derive(Serialize, Debug, Clone)]
+pub struct Snapshot {
+    pub id: String,
+    pub url: String,
+    /// RFC 3339 capture time (UTC)
+    pub date: String,
+}
+
+/// WARC files of fetched responses, one per snapshot, in
+/// `<app data>/warc/<id>.warc.gz`. A response identical to one already
+/// stored for the same URL reuses that snapshot. Past `MAX_STORED_BYTES`
+/// the oldest snapshots are deleted.
+#[derive(Clone)]
+pub struct Snapshots {
+    dir: PathBuf,
+    /// Latest snapshot per normalized URL, for notes saved after a fetch
+    latest: Arc<Mutex<HashMap<String, Snapshot>>>,
+    stored: Arc<Mutex<Stored>>,
+}
+
+/// What is on disk, read once at startup
+#[derive(Default)]
+struct Stored {
+    /// File size per snapshot ID; IDs sort by capture time
+    sizes: BTreeMap<String, u64>,
+    /// Latest snapshot ID per content digest
+    by_digest: HashMap<String, String>,
+    bytes: u64,
+}
+
+impl Stored {
+    fn insert(&mut self, id: String, size: u64) {
+        if let Some((_, digest)) = id.split_once('-') {
+            let latest = self.by_digest.entry(digest.to_string()).or_default();
+            if *latest < id {
+                *latest = id.clone();
+            }
+        }
+        self.bytes += size;
+        if let Some(old) = self.sizes.insert(id, size) {
+            self.bytes -= old;
+        }
+    }
+
+    fn remove(&mut self, id: &str) {
+        if let Some(size) = self.sizes.remove(id) {
+            self.bytes -= size;
+        }
+        if let Some((_, digest)) = id.split_once('-') {
+            if self.by_digest.get(digest).map(String::as_str) == Some(id) {
+                self.by_digest.remove(digest);
+            }
+        }
+    }
+}
+
+impl Snapshots {
+    pub fn open(dir: PathBuf) -> Result<Self, String> {
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create snapshot dir: {}", e))?;
+        let mut stored = Stored::default();
+        for entry in std::fs::read_dir(&dir)
+            .map_err(|e| format!("Failed to read snapshot dir: {}", e))?
+            .filter_map(|entry| entry.ok())
+        {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let Some(id) = name.strip_suffix(".warc.gz") else {
+                continue;
+            };
+            if snapshot_date(id).is_some() {
+                let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+                stored.insert(id.to_string(), size);
+            }
+        }
+        Ok(Self {
+            dir,
+            latest: Arc::new(Mutex::new(HashMap::new())),
+            stored: Arc::new(Mutex::new(stored)),
+        })
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.warc.gz", id))
+    }
+
+    /// The snapshot taken by the most recent fetch of `url` in this session
+    pub fn latest(&self, url: &str) -> Option<Snapshot> {
+        let key = crate::urls::normalize_url(url);
+        self.latest.lock().unwrap().get(&key).cloned()
+    }
+
+    /// Writes `response` as a WARC `response` record, followed by the DOM
+    /// of a rendered page as a `conversion` record, unless the same URL and
+    /// content were stored before. Does file I/O; call it off the async
+    /// runtime.
+    pub fn write(
+        &self,
+        url: &str,
+        response: &RawResponse,
+        rendered: Option<&str>,
+    ) -> Result<Snapshot, String> {
+        let mut hasher = Sha256::new();
+        hasher.update(url.as_bytes());
+        hasher.update([0]);
+        hasher.update(&response.body);
+        if let Some(dom) = rendered {
+            hasher.update([0]);
+            hasher.update(dom.as_bytes());
+        }
+        let digest = format!("{:x}", hasher.finalize());
+        let digest = &digest[..ID_DIGEST_LEN];
+
+        let mut stored = self.stored.lock().unwrap();
+        let existing = stored.by_digest.get(digest).and_then(|id| {
+            Some(Snapshot {
+                date: snapshot_date(id)?,
+                url: url.to_string(),
+                id: id.clone(),
+            })
+        });
+        let snapshot = match existing {
+            Some(existing) => existing,
+            None => {
+                let now = Utc::now();
+                let id = format!("{}-{}", now.format(ID_TIME_FORMAT), digest);
+                let snapshot = Snapshot {
+                    url: url.to_string(),
+                    date: snapshot_date(&id)
+                        .unwrap_or_else(|| now.to_rfc3339_opts(SecondsFormat::Secs, true)),
+                    id,
+                };
+                let size = self.write_file(&snapshot, response, rendered)?;
+                stored.insert(snapshot.id.clone(), size);
+                let mut pruned = Vec::new();
+                while stored.bytes > MAX_STORED_BYTES && stored.sizes.len() > 1 {
+                    let Some(oldest) = stored.sizes.keys().next().cloned() else {
+                        break;
+                    };
+                    self.delete(&mut stored, &oldest);
+                    pruned.push(oldest);
+                }
+                self.forget(&pruned);
+                snapshot
+            }
+        };
+        drop(stored);
+        self.latest
+            .lock()
+            .unwrap()
+            .insert(crate::urls::normalize_url(url), snapshot.clone());
+        Ok(snapshot)
+    }
+
+    /// Deletes the snapshots captured before `before`, or all of them.
+    /// Returns how many were deleted. Notes keep their `snapshot` fields.
+    pub fn clear(&self, before: Option<DateTime<Utc>>) -> usize {
+        let cutoff = before.map(|time| time.format(ID_TIME_FORMAT).to_string());
+        let mut stored = self.stored.lock().unwrap();
+        let ids: Vec<String> = stored
+            .sizes
+            .keys()
+            .filter(|id| {
+                cutoff
+                    .as_ref()
+                    .is_none_or(|cutoff| id.as_str() < cutoff.as_str())
+            })
+            .cloned()
+            .collect();
+        for id in &ids {
+            self.delete(&mut stored, id);
+        }
+        drop(stored);
+        self.forget(&ids);
+        ids.len()
+    }
+
+    fn delete(&self, stored: &mut Stored, id: &str) {
+        let _ = std::fs::remove_file(self.path(id));
+        stored.remove(id);
+    }
+
+    /// Drops deleted snapshots from the per-URL latest map
+    fn forget(&self, ids: &[String]) {
+        if !ids.is_empty() {
+            self.latest
+                .lock()
+                .unwrap()
+                .retain(|_, snapshot| !ids.contains(&snapshot.id));
+        }
+    }
+
+    fn write_file(
+        &self,
+        snapshot: &Snapshot,
+        response: &RawResponse,
+        rendered: Option<&str>,
+    ) -> Result<u64, String> {
+        let date = &snapshot.date;
+        let info_id = record_id();
+        let info = format!(
+            "software: TheFetcher/{}\r\nformat: WARC File Format 1.1\r\n",
+            env!("CARGO_PKG_VERSION")
+        );
+        let info_record = record(
+            &[
+                ("WARC-Type", "warcinfo"),
+                ("WARC-Record-ID", &info_id),
+                ("WARC-Date", date),
+                ("WARC-Filename", &format!("{}.warc.gz", snapshot.id)),
+                ("Content-Type", "application/warc-fields"),
+            ],
+            info.as_bytes(),
+        );
+        let response_id = record_id();
+        let response_record = record(
+            &[
+                ("WARC-Type", "response"),
+                ("WARC-Record-ID", &response_id),
+                ("WARC-Date", date),
+                ("WARC-Target-URI", &snapshot.url),
+                ("WARC-Warcinfo-ID", &info_id),
+                ("Content-Type", "application/http; msgtype=response"),
+            ],
+            &http_message(response),
+        );
+        let mut records = vec![info_record, response_record];
+        if let Some(dom) = rendered {
+            records.push(record(
+                &[
+                    ("WARC-Type", "conversion"),
+                    ("WARC-Record-ID", &record_id()),
+                    ("WARC-Date", date),
+                    ("WARC-Target-URI", &snapshot.url),
+                    ("WARC-Refers-To", &response_id),
+                    ("WARC-Warcinfo-ID", &info_id),
+                    ("Content-Type", RENDERED_TYPE),
+                ],
+                dom.as_bytes(),
+            ));
+        }
+
+        // One gzip member per record, as WARC readers expect
+        let mut file = Vec::new();
+        for record in records {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder
+                .write_all(&record)
+                .and_then(|_| encoder.finish())
+                .map(|member| file.extend(member))
+                .map_err(|e| format!("Failed to compress snapshot: {}", e))?;
+        }
+        std::fs::write(self.path(&snapshot.id), &file)
+            .map_err(|e| format!("Failed to write snapshot: {}", e))?;
+        Ok(file.len() as u64)
+    }
+
+    /// The URL, response and rendered DOM stored under `id`
+    pub fn read(&self, id: &str) -> Result<(Snapshot, RawResponse, Option<String>), String> {
+        // Checking the shape also keeps the ID from naming other files
+        let date = snapshot_date(id).ok_or_else(|| format!("Invalid snapshot ID: {}", id))?;
+        let compressed = std::fs::read(self.path(id))
+            .map_err(|e| format!("Snapshot {} not found: {}", id, e))?;
+        let mut data = Vec::new();
+        MultiGzDecoder::new(compressed.as_slice())
+            .read_to_end(&mut data)
+            .map_err(|e| format!("Corrupt snapshot {}: {}", id, e))?;
+
+        let mut url = String::new();
+        let mut response = None;
+        let mut rendered = None;
+        let mut rest = data.as_slice();
+        while !rest.is_empty() {
+            let (headers, block, next) =
+                parse_record(rest).ok_or_else(|| format!("Corrupt snapshot {}", id))?;
+            rest = next;
+            let header = |name: &str| {
+                headers
+                    .iter()
+                    .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                    .map(|(_, v)| v.clone())
+            };
+            match header("WARC-Type").as_deref() {
+                Some("response") => {
+                    url = header("WARC-Target-URI").unwrap_or_default();
+                    response = Some(
+                        parse_http(block)
+                            .ok_or_else(|| format!("Snapshot {} holds no HTTP response", id))?,
+                    );
+                }
+                Some("conversion") => {
+                    rendered = Some(String::from_utf8_lossy(block).into_owned());
+                }
+                _ => {}
+            }
+        }
+        let response =
+            response.ok_or_else(|| format!("Snapshot {} holds no response record", id))?;
+        Ok((
+            Snapshot {
+                id: id.to_string(),
+                url,
+                date,
+            },
+            response,
+            rendered,
+        ))
+    }
+}
+
+/// RFC 3339 date encoded in a snapshot ID, `None` if it isn't one
+fn snapshot_date(id: &str) -> Option<String> {
+    let (time, digest) = id.split_once('-')?;
+    if digest.len() != ID_DIGEST_LEN || !digest.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let time = NaiveDateTime::parse_from_str(time, ID_TIME_FORMAT).ok()?;
+    Some(
+        DateTime::<Utc>::from_naive_utc_and_offset(time, Utc)
+            .to_rfc3339_opts(SecondsFormat::Secs, true),
+    )
+}
+
+fn record_id() -> String {
+    format!("<urn:uuid:{}>", uuid::Uuid::new_v4())
+}
+
+fn record(headers: &[(&str, &str)], block: &[u8]) -> Vec<u8> {
+    let mut out = b"WARC/1.1\r\n".to_vec();
+    for (name, value) in headers {
+        out.extend(format!("{}: {}\r\n", name, value).into_bytes());
+    }
+    out.extend(format!("Content-Length: {}\r\n\r\n", block.len()).into_bytes());
+    out.extend_from_slice(block);
+    out.extend_from_slice(b"\r\n\r\n");
+    out
+}
+
+/// The response as an HTTP/1.1 message. The body was already de-chunked
+/// by the client, so Transfer-Encoding is dropped and Content-Length set
+/// to what is stored.
+fn http_message(response: &RawResponse) -> Vec<u8> {
+    let reason = reqwest::StatusCode::from_u16(response.status)
+        .ok()
+        .and_then(|s| s.canonical_reason())
+        .unwrap_or("");
+    let mut out = format!("HTTP/1.1 {} {}\r\n", response.status, reason).into_bytes();
+    for (name, value) in &response.headers {
+        if name.eq_ignore_ascii_case("transfer-encoding")
+            || name.eq_ignore_ascii_case("content-length")
+        {
+            continue;
+        }
+        out.extend(format!("{}: {}\r\n", name, value).into_bytes());
+    }
+    out.extend(format!("Content-Length: {}\r\n\r\n", response.body.len()).into_bytes());
+    out.extend_from_slice(&response.body);
+    out
+}
+
+/// Header lines up to the first blank line, and what follows it
+fn split_head(data: &[u8]) -> Option<(Headers, &[u8])> {
+    let end = data.windows(4).position(|w| w == b"\r\n\r\n")?;
+    let head = String::from_utf8_lossy(&data[..end]);
+    let headers = head
+        .lines()
+        .skip(1)
+        .filter_map(|line| line.split_once(':'))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .collect();
+    Some((headers, &data[end + 4..]))
+}
+
+/// One WARC record: its headers, its block and the data after it
+fn parse_record(data: &[u8]) -> Option<(Headers, &[u8], &[u8])> {
+    if !data.starts_with(b"WARC/") {
+        return None;
+    }
+    let (headers, rest) = split_head(data)?;
+    let length: usize = headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("Content-Length"))?
+        .1
+        .parse()
+        .ok()?;
+    let block = rest.get(..length)?;
+    let next = rest[length..]
+        .strip_prefix(b"\r\n\r\n")
+        .unwrap_or(&rest[length..]);
+    Some((headers, block, next))
+}
+
+fn parse_http(block: &[u8]) -> Option<RawResponse> {
+    let status_line = block.split(|&b| b == b'\n').next()?;
+    let status = String::from_utf8_lossy(status_line)
+        .split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()?;
+    let (headers, body) = split_head(block)?;
+    Some(RawResponse {
+        status,
+        headers,
+        body: body.to_vec(),
+    })
+}
+
+/// Frontmatter fields that point a note at its snapshot
+pub fn frontmatter_fields(snapshot: &Snapshot) -> Vec<(&'static str, String)> {
+    vec![
+        ("snapshot", snapshot.id.clone()),
+        ("snapshot_date", snapshot.date.clone()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshots() -> (Snapshots, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("warc-{}", uuid::Uuid::new_v4()));
+        (Snapshots::open(dir.clone()).unwrap(), dir)
+    }
+
+    fn response(body: &str) -> RawResponse {
+        RawResponse {
+            status: 200,
+            headers: vec![
+                ("content-type".to_string(), "text/html".to_string()),
+                ("transfer-encoding".to_string(), "chunked".to_string()),
+            ],
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn rendered_dom_is_kept_apart_from_the_response() {
+        let (snapshots, dir) = snapshots();
+        let url = "https://app.example/";
+        let shell = "<div id=app></div>";
+        let dom = "<div id=app><h1>Hello</h1></div>";
+
+        let snapshot = snapshots.write(url, &response(shell), Some(dom)).unwrap();
+        let (read, stored, rendered) = snapshots.read(&snapshot.id).unwrap();
+        assert_eq!(read.url, url);
+        assert_eq!(read.date, snapshot.date);
+        assert_eq!(stored.status, 200);
+        assert_eq!(stored.body, shell.as_bytes());
+        assert_eq!(stored.header("content-type"), Some("text/html"));
+        assert_eq!(stored.header("transfer-encoding"), None);
+        assert_eq!(rendered.as_deref(), Some(dom));
+
+        let plain = snapshots.write(url, &response(shell), None).unwrap();
+        assert_ne!(plain.id, snapshot.id);
+        assert_eq!(snapshots.read(&plain.id).unwrap().2, None);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unchanged_responses_reuse_the_snapshot_after_a_restart() {
+        let (snapshots, dir) = snapshots();
+        let first = snapshots
+            .write("https://a.example/", &response("same"), None)
+            .unwrap();
+        let reopened = Snapshots::open(dir.clone()).unwrap();
+        let again = reopened
+            .write("https://a.example/", &response("same"), None)
+            .unwrap();
+        assert_eq!(again.id, first.id);
+        let other_url = reopened
+            .write("https://b.example/", &response("same"), None)
+            .unwrap();
+        assert_ne!(other_url.id, first.id);
+        assert_eq!(reopened.latest("https://a.example").unwrap().id, first.id);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn clear_deletes_files_and_forgets_them() {
+        let (snapshots, dir) = snapshots();
+        let snapshot = snapshots
+            .write("https://a.example/", &response("one"), None)
+            .unwrap();
+        let an_hour_ago = Utc::now() - chrono::Duration::hours(1);
+        assert_eq!(snapshots.clear(Some(an_hour_ago)), 0);
+        assert!(snapshots.read(&snapshot.id).is_ok());
+
+        assert_eq!(snapshots.clear(None), 1);
+        assert!(snapshots.read(&snapshot.id).is_err());
+        assert!(snapshots.latest("https://a.example/").is_none());
+        // Writing the same response again creates a new file
+        let rewritten = snapshots
+            .write("https://a.example/", &response("one"), None)
+            .unwrap();
+        assert!(snapshots.read(&rewritten.id).is_ok());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn records_round_trip() {
+        let block = b"binary\r\n\r\nblock with a blank line".to_vec();
+        let mut data = record(
+            &[
+                ("WARC-Type", "resource"),
+                ("WARC-Target-URI", "https://a.example/"),
+            ],
+            &block,
+        );
+        data.extend(record(&[("WARC-Type", "metadata")], b""));
+
+        let (headers, first, rest) = parse_record(&data).unwrap();
+        assert_eq!(first, block.as_slice());
+        assert!(headers.contains(&(
+            "WARC-Target-URI".to_string(),
+            "https://a.example/".to_string()
+        )));
+        assert!(headers.contains(&("Content-Length".to_string(), block.len().to_string())));
+
+        let (headers, second, rest) = parse_record(rest).unwrap();
+        assert!(headers.contains(&("WARC-Type".to_string(), "metadata".to_string())));
+        assert!(second.is_empty());
+        assert!(rest.is_empty());
+        assert!(parse_record(rest).is_none());
+    }
+
+    #[test]
+    fn http_messages_round_trip() {
+        let message = http_message(&response("<p>hi</p>"));
+        assert!(message.starts_with(b"HTTP/1.1 200 OK\r\n"));
+        let parsed = parse_http(&message).unwrap();
+        assert_eq!(parsed.status, 200);
+        assert_eq!(parsed.body, b"<p>hi</p>");
+        assert_eq!(parsed.header("content-type"), Some("text/html"));
+        // The stored body is no longer chunked
+        assert_eq!(parsed.header("transfer-encoding"), None);
+        assert_eq!(parsed.header("content-length"), Some("9"));
+    }
+
+    #[test]
+    fn damaged_records_are_rejected() {
+        let data = record(&[("WARC-Type", "resource")], b"full block");
+        assert!(parse_record(&data[..data.len() - 8]).is_none());
+        assert!(parse_record(b"HTTP/1.1 200 OK\r\n\r\n").is_none());
+    }
+}
@@ -14,12 +14,110 @@ export interface FetchResult {
     summary: string
     file_path: string
     error?: string
+    session_id?: string
+    duplicate_of?: string
+}
+
+export type SearchPreset = 'docs' | 'news' | 'code' | 'papers'
+
+export interface SearchOptions {
+    preset?: SearchPreset
+    categories?: string[]
+    engines?: string[]
+    language?: string
+    safesearch?: 'off' | 'moderate' | 'strict'
+    timeRange?: 'day' | 'week' | 'month' | 'year'
+    pageno?: number
+    deep?: number // Merge this many result pages
+    providers?: string[] // e.g. ['searxng', 'vault', 'fetched']; merged into one ranking
+}
+
+export interface SearchPage {
+    results: SearchResult[]
+    next_cursor?: string // Pass back as `cursor` for the following results
 }
 
 export interface SearchResult {
     url: string
     title: string
-    content?: string // Snippet or Full Answer
+    content?: string // Full answer already produced (Vault/Agent); selecting it skips fetching
+    snippet?: string // Search engine excerpt; the page still has to be fetched
+    in_vault?: string // Path of the note already saved from this URL
+    engine?: string
+    engines?: string[]
+    score?: number
+    published_date?: string
+    category?: string
+    thumbnail?: string
+}
+
+export interface BatchItemResult {
+    index: number
+    url: string
+    title: string
+    file_path?: string
+    error?: { kind: 'duplicate' | 'fetch' | 'summarize' | 'save', message: string }
+    warning?: string
+}
+
+export interface BatchSummary {
+    total: number
+    succeeded: number
+    skipped: number
+    failed: number
+    items: BatchItemResult[]
+}
+
+export interface BatchProgress {
+    index: number
+    url: string
+    step: string
+    status: string
+    message: string
+}
+
+export type JobStatus = 'queued' | 'running' | 'done' | 'failed' | 'cancelled'
+
+export interface Job {
+    id: number
+    kind: 'batch' | 'agent'
+    status: JobStatus
+    attempts: number
+    max_attempts: number
+    payload: Record<string, unknown>
+    result?: unknown
+    error?: string
+    created_at: string
+    updated_at: string
+}
+
+export type DiffBlock = { kind: 'text' | 'code'; text: string }
+
+export interface SectionDiff {
+    heading: string
+    status: 'added' | 'removed' | 'modified'
+    changes: { kind: 'added' | 'removed'; block: DiffBlock }[]
+}
+
+export interface SourceDiff {
+    url: string
+    has_previous: boolean
+    diff: { sections: SectionDiff[] }
+    summary?: string
+}
+
+export interface FieldFilter {
+    field: string
+    op: 'eq' | 'contains' | 'gte' | 'lte' | 'exists'
+    value?: string
+}
+
+export interface VaultFilter {
+    tags?: string[]
+    folder?: string
+    fields?: FieldFilter[]
+    modifiedAfter?: string // YYYY-MM-DD or RFC 3339
+    modifiedBefore?: string
 }
 
 export interface AgentSettings {
@@ -35,9 +133,13 @@ export const useAgentStore = defineStore('agent', () => {
     const lastResult = ref<FetchResult | null>(null)
     const lastQuery = ref('')
     const searchResults = ref<SearchResult[]>([])
+    // Where "Load more" continues the last web search
+    const searchCursor = ref<string | null>(null)
+    const searchOptions = ref<SearchOptions | null>(null)
+    const vaultSessionId = ref<string | null>(null)
     const settings = ref<AgentSettings>({
         aiSummarization: true,
-        headlessMode: false,
+        headlessMode: true,
         selectedModel: 'llama3.2:1b',
         obsidianApiKey: ''
     })
@@ -50,6 +152,7 @@ export const useAgentStore = defineStore('agent', () => {
         logs.value = []
         lastResult.value = null
         searchResults.value = []
+        searchCursor.value = null
     }
 
     const setRunning = (v: boolean) => {
@@ -60,13 +163,25 @@ export const useAgentStore = defineStore('agent', () => {
         lastResult.value = r
     }
 
-    const setSearchResults = (results: SearchResult[]) => {
+    const setSearchResults = (results: SearchResult[], cursor: string | null = null) => {
         searchResults.value = results
+        searchCursor.value = cursor
+    }
+
+    // Adds a further page, skipping URLs already listed
+    const appendSearchResults = (results: SearchResult[], cursor: string | null) => {
+        const seen = new Set(searchResults.value.map(r => r.url))
+        searchResults.value.push(...results.filter(r => !seen.has(r.url)))
+        searchCursor.value = cursor
+    }
+
+    const newVaultSession = () => {
+        vaultSessionId.value = null
     }
 
     const updateSettings = (s: AgentSettings) => {
         settings.value = s
     }
 
-    return { logs, isRunning, lastResult, lastQuery, searchResults, settings, addLog, clearLogs, setRunning, setResult, setSearchResults, updateSettings }
+    return { logs, isRunning, lastResult, lastQuery, searchResults, searchCursor, searchOptions, vaultSessionId, settings, addLog, clearLogs, setRunning, setResult, setSearchResults, appendSearchResults, newVaultSession, updateSettings }
 })